@@ -0,0 +1,5 @@
+ALTER TABLE statuses ADD COLUMN replies_count BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE statuses ADD COLUMN reblogs_count BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE statuses ADD COLUMN favourites_count BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE statuses ADD COLUMN engagements_count BIGINT GENERATED ALWAYS AS (replies_count + reblogs_count + favourites_count) STORED;
+DELETE FROM status_refreshes;
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS hashtag_aliases(
+    alias TEXT NOT NULL PRIMARY KEY,
+    canonical TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS hashtag_aliases_canonical_idx ON hashtag_aliases (canonical);
+CREATE UNIQUE INDEX IF NOT EXISTS subscribed_hashtags_lower_name_idx ON subscribed_hashtags (lower(name));
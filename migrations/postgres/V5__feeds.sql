@@ -0,0 +1,5 @@
+ALTER TABLE subscribed_hashtags ADD COLUMN feed TEXT NOT NULL DEFAULT 'default';
+ALTER TABLE subscribed_hashtags DROP CONSTRAINT subscribed_hashtags_pkey;
+ALTER TABLE subscribed_hashtags ADD PRIMARY KEY (feed, name);
+DROP INDEX IF EXISTS subscribed_hashtags_lower_name_idx;
+CREATE UNIQUE INDEX IF NOT EXISTS subscribed_hashtags_lower_name_idx ON subscribed_hashtags (feed, lower(name));
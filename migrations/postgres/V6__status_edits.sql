@@ -0,0 +1,5 @@
+ALTER TABLE statuses ADD COLUMN edited_at TIMESTAMPTZ;
+DELETE FROM status_tags a USING status_tags b
+WHERE a.ctid < b.ctid AND a.status_id = b.status_id AND a.name = b.name;
+DROP INDEX IF EXISTS status_tags_idx;
+CREATE UNIQUE INDEX IF NOT EXISTS status_tags_idx ON status_tags (status_id, name);
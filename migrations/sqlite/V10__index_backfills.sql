@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS index_backfills(
+    name TEXT NOT NULL PRIMARY KEY,
+    completed_at TEXT NOT NULL
+);
+-- A database without statuses has none to backfill, those it indexes later record their attachments
+INSERT INTO index_backfills (name, completed_at)
+SELECT 'attachments', datetime('now') WHERE NOT EXISTS (SELECT 1 FROM statuses);
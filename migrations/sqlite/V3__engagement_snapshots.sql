@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS status_engagements(
+    status_id TEXT NOT NULL,
+    recorded_at TEXT NOT NULL,
+    replies_count INT NOT NULL DEFAULT 0,
+    reblogs_count INT NOT NULL DEFAULT 0,
+    favourites_count INT NOT NULL DEFAULT 0
+);
+CREATE INDEX IF NOT EXISTS status_engagements_idx ON status_engagements (status_id, recorded_at);
+CREATE INDEX IF NOT EXISTS status_engagements_recorded_at_idx ON status_engagements (recorded_at);
+INSERT INTO status_engagements (status_id, recorded_at, replies_count, reblogs_count, favourites_count)
+SELECT s.id, COALESCE(sr.refreshed_at, s.created_at), s.replies_count, s.reblogs_count, s.favourites_count
+FROM statuses s
+LEFT JOIN status_refreshes sr ON sr.id = s.id;
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS hashtag_aliases(
+    alias TEXT NOT NULL PRIMARY KEY,
+    canonical TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS hashtag_aliases_canonical_idx ON hashtag_aliases (canonical);
+UPDATE subscribed_hashtags
+SET votes = (SELECT SUM(sh.votes) FROM subscribed_hashtags sh WHERE lower(sh.name) = lower(subscribed_hashtags.name)),
+    approved = (SELECT MAX(sh.approved) FROM subscribed_hashtags sh WHERE lower(sh.name) = lower(subscribed_hashtags.name));
+DELETE FROM subscribed_hashtags
+WHERE rowid NOT IN (SELECT MIN(rowid) FROM subscribed_hashtags GROUP BY lower(name));
+CREATE UNIQUE INDEX IF NOT EXISTS subscribed_hashtags_lower_name_idx ON subscribed_hashtags (lower(name));
@@ -0,0 +1,13 @@
+CREATE TABLE subscribed_hashtags_by_feed(
+    feed TEXT NOT NULL DEFAULT 'default',
+    name TEXT NOT NULL,
+    approved INTEGER NOT NULL DEFAULT 0,
+    votes INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT (DATETIME('now')),
+    PRIMARY KEY (feed, name)
+);
+INSERT INTO subscribed_hashtags_by_feed (feed, name, approved, votes, created_at)
+SELECT 'default', name, approved, votes, created_at FROM subscribed_hashtags;
+DROP TABLE subscribed_hashtags;
+ALTER TABLE subscribed_hashtags_by_feed RENAME TO subscribed_hashtags;
+CREATE UNIQUE INDEX IF NOT EXISTS subscribed_hashtags_lower_name_idx ON subscribed_hashtags (feed, lower(name));
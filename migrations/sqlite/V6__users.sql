@@ -0,0 +1,56 @@
+CREATE TABLE IF NOT EXISTS oauth_apps(
+    instance TEXT NOT NULL,
+    redirect_uri TEXT NOT NULL,
+    client_id TEXT NOT NULL,
+    client_secret TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (DATETIME('now')),
+    PRIMARY KEY (instance, redirect_uri)
+);
+CREATE TABLE IF NOT EXISTS oauth_logins(
+    state TEXT NOT NULL PRIMARY KEY,
+    instance TEXT NOT NULL,
+    redirect_uri TEXT NOT NULL,
+    return_to TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS users(
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    instance TEXT NOT NULL,
+    account_id TEXT NOT NULL,
+    acct TEXT NOT NULL,
+    display_name TEXT NOT NULL,
+    access_token TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (DATETIME('now')),
+    UNIQUE (instance, account_id)
+);
+CREATE TABLE IF NOT EXISTS sessions(
+    id TEXT NOT NULL PRIMARY KEY,
+    user_id INTEGER NOT NULL,
+    created_at TEXT NOT NULL,
+    expires_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS sessions_expires_at_idx ON sessions (expires_at);
+CREATE TABLE IF NOT EXISTS user_followed_tags(
+    user_id INTEGER NOT NULL,
+    feed TEXT NOT NULL,
+    name TEXT NOT NULL,
+    PRIMARY KEY (user_id, feed, name)
+);
+CREATE TABLE IF NOT EXISTS user_muted_accounts(
+    user_id INTEGER NOT NULL,
+    acct TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (DATETIME('now')),
+    PRIMARY KEY (user_id, acct)
+);
+CREATE TABLE IF NOT EXISTS user_hidden_statuses(
+    user_id INTEGER NOT NULL,
+    status_id TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (DATETIME('now')),
+    PRIMARY KEY (user_id, status_id)
+);
+CREATE TABLE IF NOT EXISTS user_saved_statuses(
+    user_id INTEGER NOT NULL,
+    status_id TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (DATETIME('now')),
+    PRIMARY KEY (user_id, status_id)
+);
@@ -0,0 +1,8 @@
+ALTER TABLE oauth_apps ADD COLUMN scopes TEXT NOT NULL DEFAULT 'read:accounts';
+CREATE TABLE IF NOT EXISTS resolved_statuses(
+    instance TEXT NOT NULL,
+    uri TEXT NOT NULL,
+    status_id TEXT NOT NULL,
+    resolved_at TEXT NOT NULL DEFAULT (DATETIME('now')),
+    PRIMARY KEY (instance, uri)
+);
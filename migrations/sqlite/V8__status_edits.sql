@@ -0,0 +1,4 @@
+ALTER TABLE statuses ADD COLUMN edited_at TEXT;
+DELETE FROM status_tags WHERE rowid NOT IN (SELECT MIN(rowid) FROM status_tags GROUP BY status_id, name);
+DROP INDEX IF EXISTS status_tags_idx;
+CREATE UNIQUE INDEX IF NOT EXISTS status_tags_idx ON status_tags (status_id, name);
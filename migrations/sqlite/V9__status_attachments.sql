@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS status_attachments(
+    status_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    type TEXT NOT NULL,
+    PRIMARY KEY (status_id, position)
+);
@@ -0,0 +1,153 @@
+use crate::settings::ApplicationSettings;
+use actix_web::dev::Payload;
+use actix_web::http::header;
+use actix_web::{FromRequest, HttpRequest, HttpResponse, error, web};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use std::future::{Ready, ready};
+use url::Url;
+
+/// Extractor guarding the administration routes.
+///
+/// The configured `admin-token` is accepted either as a bearer token, for
+/// scripts, or as the password of HTTP basic authentication, so that the admin
+/// pages can be used from a browser. Admin routes answer 404 when no token is
+/// configured.
+///
+/// Browsers send the basic authentication along with cross-site requests too,
+/// so the requests changing something are refused when their `Origin` or
+/// `Referer` is another site.
+pub struct Admin;
+
+impl FromRequest for Admin {
+    type Error = error::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let expected = req
+            .app_data::<web::Data<ApplicationSettings>>()
+            .and_then(|settings| settings.admin_token.clone())
+            .filter(|token| !token.is_empty());
+        let Some(expected) = expected else {
+            return ready(Err(error::ErrorNotFound("Not Found")));
+        };
+        if !is_same_origin(req) {
+            return ready(Err(error::ErrorForbidden("Cross-site request")));
+        }
+
+        let provided = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_authorization);
+        if provided.is_some_and(|token| constant_time_eq(token.as_bytes(), expected.as_bytes())) {
+            return ready(Ok(Admin));
+        }
+
+        let challenge = HttpResponse::Unauthorized()
+            .insert_header((header::WWW_AUTHENTICATE, r#"Basic realm="admin""#))
+            .finish();
+        ready(Err(error::InternalError::from_response(
+            "Unauthorized",
+            challenge,
+        )
+        .into()))
+    }
+}
+
+/// Whether a request changing something comes from the pages of this site.
+/// Scripts which send neither `Origin` nor `Referer` are let through, the
+/// browsers sending `Origin` with all their cross-site `POST` requests.
+fn is_same_origin(req: &HttpRequest) -> bool {
+    if req.method().is_safe() {
+        return true;
+    }
+    let headers = req.headers();
+    let Some(source) = headers
+        .get(header::ORIGIN)
+        .or_else(|| headers.get(header::REFERER))
+    else {
+        return true;
+    };
+    let Some(url) = source.to_str().ok().and_then(|s| Url::parse(s).ok()) else {
+        return false;
+    };
+    let origin = match (url.host_str(), url.port()) {
+        (Some(host), Some(port)) => format!("{}:{}", host, port),
+        (Some(host), None) => host.to_string(),
+        (None, _) => return false,
+    };
+    origin.eq_ignore_ascii_case(req.connection_info().host())
+}
+
+/// Extract the token from a `Bearer` or `Basic` authorization header.
+fn parse_authorization(value: &str) -> Option<String> {
+    let (scheme, credentials) = value.split_once(' ')?;
+    if scheme.eq_ignore_ascii_case("bearer") {
+        return Some(credentials.trim().to_string());
+    }
+    if scheme.eq_ignore_ascii_case("basic") {
+        let decoded = STANDARD.decode(credentials.trim()).ok()?;
+        let decoded = String::from_utf8(decoded).ok()?;
+        return decoded
+            .split_once(':')
+            .map(|(_, password)| password.to_string());
+    }
+    None
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_authorization_accepts_bearer_and_basic() {
+        assert_eq!(parse_authorization("Bearer s3cret"), Some("s3cret".into()));
+        // admin:s3cret
+        assert_eq!(
+            parse_authorization("Basic YWRtaW46czNjcmV0"),
+            Some("s3cret".into())
+        );
+        assert_eq!(parse_authorization("Digest s3cret"), None);
+        assert_eq!(parse_authorization("Basic not-base64"), None);
+    }
+
+    #[test]
+    fn is_same_origin_refuses_cross_site_posts() {
+        use actix_web::test::TestRequest;
+
+        let post = |name, value| {
+            TestRequest::post()
+                .insert_header((header::HOST, "timeline.example"))
+                .insert_header((name, value))
+                .to_http_request()
+        };
+        assert!(is_same_origin(&post(
+            header::ORIGIN,
+            "https://timeline.example"
+        )));
+        assert!(is_same_origin(&post(
+            header::REFERER,
+            "https://timeline.example/admin/workers"
+        )));
+        assert!(!is_same_origin(&post(
+            header::ORIGIN,
+            "https://evil.example"
+        )));
+        assert!(!is_same_origin(&post(header::ORIGIN, "null")));
+        assert!(is_same_origin(
+            &TestRequest::post()
+                .insert_header((header::HOST, "timeline.example"))
+                .to_http_request()
+        ));
+        assert!(is_same_origin(
+            &TestRequest::get()
+                .insert_header((header::ORIGIN, "https://evil.example"))
+                .to_http_request()
+        ));
+    }
+}
@@ -0,0 +1,255 @@
+use crate::api::controllers::interactions::interactions_config;
+use crate::api::dto::hashtag::TagPathDTO;
+use crate::api::dto::user::{
+    HomeInstanceDTO, LoginCallbackDTO, LoginDTO, LogoutDTO, MuteDTO, MutePathDTO, StatusPathDTO,
+};
+use crate::api::feed::CurrentFeed;
+use crate::api::session::{HOME_INSTANCE_COOKIE, SESSION_COOKIE, SignedInUser};
+use crate::domain::services::hashtag::SubscribedHashtagService;
+use crate::domain::services::user::{UserService, UserServiceError};
+use crate::settings::ApplicationSettings;
+use actix_web::cookie::time::Duration;
+use actix_web::cookie::{Cookie, SameSite};
+use actix_web::http::header;
+use actix_web::web::Html;
+use actix_web::{HttpRequest, HttpResponse, Responder, Scope, error, get, post, web};
+use tera::{Context, Tera};
+
+fn redirect(location: &str) -> HttpResponse {
+    HttpResponse::SeeOther()
+        .append_header((header::LOCATION, location))
+        .finish()
+}
+
+fn session_cookie<'c>(request: &HttpRequest, value: String) -> Cookie<'c> {
+    cookie(request, SESSION_COOKIE, value)
+}
+
+fn cookie<'c>(request: &HttpRequest, name: &'c str, value: String) -> Cookie<'c> {
+    Cookie::build(name, value)
+        .path("/")
+        .http_only(true)
+        .same_site(SameSite::Lax)
+        .secure(request.connection_info().scheme() == "https")
+        .finish()
+}
+
+#[post("/login")]
+async fn login(
+    form: web::Form<LoginDTO>,
+    user_service: web::Data<dyn UserService>,
+) -> Result<impl Responder, UserServiceError> {
+    let authorize_url = user_service
+        .start_login(&form.instance, form.return_to())
+        .await?;
+    Ok(redirect(&authorize_url))
+}
+
+#[get("/login/callback")]
+async fn login_callback(
+    request: HttpRequest,
+    query: web::Query<LoginCallbackDTO>,
+    user_service: web::Data<dyn UserService>,
+    settings: web::Data<ApplicationSettings>,
+) -> Result<impl Responder, UserServiceError> {
+    // The user denied the authorization on their instance
+    let (Some(state), Some(code)) = (&query.state, &query.code) else {
+        return Ok(redirect("/"));
+    };
+    let signed_in = user_service.complete_login(state, code).await?;
+
+    let mut cookie = session_cookie(&request, signed_in.session_id);
+    cookie.set_max_age(
+        Duration::try_from(*settings.accounts.session_lifetime).unwrap_or(Duration::days(30)),
+    );
+    let mut response = redirect(&signed_in.return_to);
+    response
+        .add_cookie(&cookie)
+        .map_err(|_| UserServiceError::InvalidLogin)?;
+    Ok(response)
+}
+
+#[post("/logout")]
+async fn logout(
+    request: HttpRequest,
+    form: web::Form<LogoutDTO>,
+    user_service: web::Data<dyn UserService>,
+) -> Result<impl Responder, UserServiceError> {
+    if let Some(cookie) = request.cookie(SESSION_COOKIE) {
+        user_service.logout(cookie.value()).await?;
+    }
+    let mut response = redirect(form.return_to());
+    let _ = response.add_removal_cookie(&session_cookie(&request, String::new()));
+    Ok(response)
+}
+
+/// Remember the instance of an anonymous visitor, to which the reply, boost
+/// and favourite links of the timeline point
+#[post("/home-instance")]
+async fn set_home_instance(
+    request: HttpRequest,
+    form: web::Form<HomeInstanceDTO>,
+    user_service: web::Data<dyn UserService>,
+) -> Result<impl Responder, UserServiceError> {
+    let mut response = redirect(form.return_to());
+    if form.instance.trim().is_empty() {
+        let _ = response.add_removal_cookie(&cookie(&request, HOME_INSTANCE_COOKIE, String::new()));
+        return Ok(response);
+    }
+    let instance = user_service.home_instance(&form.instance)?;
+    let mut home_instance = cookie(&request, HOME_INSTANCE_COOKIE, instance);
+    home_instance.set_max_age(Duration::days(365));
+    response
+        .add_cookie(&home_instance)
+        .map_err(|_| UserServiceError::InvalidInstance)?;
+    Ok(response)
+}
+
+#[get("/preferences")]
+async fn get_preferences(
+    user: SignedInUser,
+    feed: CurrentFeed,
+    user_service: web::Data<dyn UserService>,
+    subscribed_hashtag_service: web::Data<dyn SubscribedHashtagService>,
+    tmpl: web::Data<Tera>,
+) -> Result<impl Responder, error::Error> {
+    let preferences = user_service.preferences(&user, &feed.slug).await?;
+    let hashtags: Vec<(String, bool)> = subscribed_hashtag_service
+        .list_hashtags(&feed.slug)
+        .await?
+        .into_iter()
+        .map(|name| {
+            let followed = preferences.followed_tags.contains(&name.to_lowercase());
+            (name, followed)
+        })
+        .collect();
+    let mut muted_accounts: Vec<&String> = preferences.muted_accounts.iter().collect();
+    muted_accounts.sort();
+
+    let mut context = Context::new();
+    context.insert("feed", &feed.0);
+    context.insert("user", &user.0);
+    context.insert("hashtags", &hashtags);
+    context.insert("muted_accounts", &muted_accounts);
+    Ok(Html::new(
+        tmpl.render("account/preferences.html", &context)
+            .map_err(error::ErrorInternalServerError)?,
+    ))
+}
+
+fn preferences_updated() -> HttpResponse {
+    HttpResponse::Ok()
+        .append_header(("HX-Trigger", "preferences-updated"))
+        .finish()
+}
+
+#[post("/tags/{name}/follow")]
+async fn follow_tag(
+    user: SignedInUser,
+    feed: CurrentFeed,
+    path: web::Path<TagPathDTO>,
+    user_service: web::Data<dyn UserService>,
+) -> Result<impl Responder, UserServiceError> {
+    user_service
+        .follow_tag(&user, &feed.slug, &path.name, true)
+        .await?;
+    Ok(preferences_updated())
+}
+
+#[post("/tags/{name}/unfollow")]
+async fn unfollow_tag(
+    user: SignedInUser,
+    feed: CurrentFeed,
+    path: web::Path<TagPathDTO>,
+    user_service: web::Data<dyn UserService>,
+) -> Result<impl Responder, UserServiceError> {
+    user_service
+        .follow_tag(&user, &feed.slug, &path.name, false)
+        .await?;
+    Ok(preferences_updated())
+}
+
+#[post("/mutes")]
+async fn mute_account(
+    user: SignedInUser,
+    form: web::Form<MuteDTO>,
+    user_service: web::Data<dyn UserService>,
+) -> Result<impl Responder, UserServiceError> {
+    user_service.mute_account(&user, &form.acct, true).await?;
+    Ok(preferences_updated())
+}
+
+#[post("/mutes/{acct}/delete")]
+async fn unmute_account(
+    user: SignedInUser,
+    path: web::Path<MutePathDTO>,
+    user_service: web::Data<dyn UserService>,
+) -> Result<impl Responder, UserServiceError> {
+    user_service.mute_account(&user, &path.acct, false).await?;
+    Ok(preferences_updated())
+}
+
+#[post("/statuses/{id}/hide")]
+async fn hide_status(
+    user: SignedInUser,
+    path: web::Path<StatusPathDTO>,
+    user_service: web::Data<dyn UserService>,
+) -> Result<impl Responder, UserServiceError> {
+    user_service.hide_status(&user, &path.id, true).await?;
+    // The status is swapped out of the timeline
+    Ok(HttpResponse::Ok().finish())
+}
+
+fn render_save_button(tmpl: &Tera, id: &str, saved: bool) -> Result<Html, error::Error> {
+    let mut context = Context::new();
+    context.insert("status_id", id);
+    context.insert("saved", &saved);
+    Ok(Html::new(
+        tmpl.render("account/save_button.html", &context)
+            .map_err(error::ErrorInternalServerError)?,
+    ))
+}
+
+#[post("/statuses/{id}/save")]
+async fn save_status(
+    user: SignedInUser,
+    path: web::Path<StatusPathDTO>,
+    user_service: web::Data<dyn UserService>,
+    tmpl: web::Data<Tera>,
+) -> Result<impl Responder, error::Error> {
+    user_service.save_status(&user, &path.id, true).await?;
+    render_save_button(&tmpl, &path.id, true)
+}
+
+#[post("/statuses/{id}/unsave")]
+async fn unsave_status(
+    user: SignedInUser,
+    path: web::Path<StatusPathDTO>,
+    user_service: web::Data<dyn UserService>,
+    tmpl: web::Data<Tera>,
+) -> Result<impl Responder, error::Error> {
+    user_service.save_status(&user, &path.id, false).await?;
+    render_save_button(&tmpl, &path.id, false)
+}
+
+fn me_scope(path: &str) -> Scope {
+    web::scope(path)
+        .service(get_preferences)
+        .service(follow_tag)
+        .service(unfollow_tag)
+        .service(mute_account)
+        .service(unmute_account)
+        .service(hide_status)
+        .service(save_status)
+        .service(unsave_status)
+        .configure(interactions_config)
+}
+
+pub fn account_config(cfg: &mut web::ServiceConfig) {
+    cfg.service(login)
+        .service(login_callback)
+        .service(logout)
+        .service(set_home_instance)
+        .service(me_scope("/me"))
+        .service(me_scope("/f/{feed}/me"));
+}
@@ -0,0 +1,305 @@
+use crate::api::auth::Admin;
+use crate::api::dto::hashtag::{AliasDTO, AliasPathDTO, TagPathDTO};
+use crate::api::dto::status::RefreshStatusesDTO;
+use crate::api::feed::CurrentFeed;
+use crate::domain::models::command::{WorkerCommand, WorkerCommands};
+use crate::domain::models::feed::Feeds;
+use crate::domain::services::hashtag::SubscribedHashtagService;
+use crate::domain::services::health::HealthService;
+use crate::domain::services::status::StatusService;
+use crate::settings::ApplicationSettings;
+use actix_web::web::Html;
+use actix_web::{HttpResponse, Responder, Scope, error, get, post, web};
+use std::error::Error;
+use tera::{Context, Tera};
+
+#[get("")]
+async fn get_admin(
+    _admin: Admin,
+    feed: CurrentFeed,
+    feeds: web::Data<Feeds>,
+    tmpl: web::Data<Tera>,
+) -> Result<impl Responder, error::Error> {
+    let mut context = Context::new();
+    context.insert("feed", &feed.0);
+    context.insert("feeds", &feeds.iter().collect::<Vec<_>>());
+    Ok(Html::new(
+        tmpl.render("admin/index.html", &context)
+            .map_err(error::ErrorInternalServerError)?,
+    ))
+}
+
+#[get("/suggestions")]
+async fn list_suggestions(
+    _admin: Admin,
+    feed: CurrentFeed,
+    subscribed_hashtag_service: web::Data<dyn SubscribedHashtagService>,
+    status_service: web::Data<dyn StatusService>,
+    tmpl: web::Data<Tera>,
+    settings: web::Data<ApplicationSettings>,
+) -> Result<impl Responder, error::Error> {
+    let suggestions = subscribed_hashtag_service
+        .list_suggestions(&feed.slug)
+        .await?;
+
+    let tracked = subscribed_hashtag_service
+        .list_tracked_hashtags(&feed.slug)
+        .await?;
+    let subscribed: Vec<String> = tracked
+        .iter()
+        .chain(suggestions.iter().map(|(name, _)| name))
+        .map(|name| name.to_lowercase())
+        .collect();
+    let rising = &settings.rising_tags;
+    let candidates: Vec<_> = status_service
+        .rising_tags(
+            &tracked,
+            *rising.window,
+            *rising.baseline,
+            rising.min_count,
+            rising.min_ratio,
+            rising
+                .limit
+                .saturating_add(subscribed.len().try_into().unwrap_or(u16::MAX)),
+        )
+        .await?
+        .into_iter()
+        .filter(|tag| !subscribed.contains(&tag.name.to_lowercase()))
+        .take(rising.limit.into())
+        .collect();
+
+    let mut context = Context::new();
+    context.insert("feed", &feed.0);
+    context.insert("suggestions", &suggestions);
+    context.insert("candidates", &candidates);
+    Ok(Html::new(
+        tmpl.render("admin/suggestions.html", &context)
+            .map_err(error::ErrorInternalServerError)?,
+    ))
+}
+
+#[get("/cooccurrences")]
+async fn list_cooccurrences(
+    _admin: Admin,
+    feed: CurrentFeed,
+    subscribed_hashtag_service: web::Data<dyn SubscribedHashtagService>,
+    status_service: web::Data<dyn StatusService>,
+    tmpl: web::Data<Tera>,
+    settings: web::Data<ApplicationSettings>,
+) -> Result<impl Responder, error::Error> {
+    let hashtags = subscribed_hashtag_service
+        .list_tracked_hashtags(&feed.slug)
+        .await?;
+    let cooccurrence = &settings.tag_cooccurrence;
+    let related_tags = status_service
+        .related_tags(
+            &hashtags,
+            *cooccurrence.window,
+            cooccurrence.min_count,
+            cooccurrence.limit,
+        )
+        .await?;
+
+    let mut context = Context::new();
+    context.insert("feed", &feed.0);
+    context.insert("suggestions", &related_tags.suggestions);
+    context.insert("related", &related_tags.related);
+    Ok(Html::new(
+        tmpl.render("admin/cooccurrences.html", &context)
+            .map_err(error::ErrorInternalServerError)?,
+    ))
+}
+
+#[post("/tags/{name}/approve")]
+async fn approve_tag(
+    _admin: Admin,
+    feed: CurrentFeed,
+    path: web::Path<TagPathDTO>,
+    subscribed_hashtag_service: web::Data<dyn SubscribedHashtagService>,
+) -> Result<impl Responder, Box<dyn Error>> {
+    subscribed_hashtag_service
+        .approve_hashtag(&feed.slug, &path.name)
+        .await?;
+    Ok(HttpResponse::Ok()
+        .append_header(("HX-Trigger", "suggestions-updated"))
+        .finish())
+}
+
+#[post("/tags/{name}/reject")]
+async fn reject_tag(
+    _admin: Admin,
+    feed: CurrentFeed,
+    path: web::Path<TagPathDTO>,
+    subscribed_hashtag_service: web::Data<dyn SubscribedHashtagService>,
+) -> Result<impl Responder, Box<dyn Error>> {
+    subscribed_hashtag_service
+        .reject_hashtag(&feed.slug, &path.name)
+        .await?;
+    Ok(HttpResponse::Ok()
+        .append_header(("HX-Trigger", "suggestions-updated"))
+        .finish())
+}
+
+#[get("/aliases")]
+async fn list_aliases(
+    _admin: Admin,
+    feed: CurrentFeed,
+    subscribed_hashtag_service: web::Data<dyn SubscribedHashtagService>,
+    tmpl: web::Data<Tera>,
+) -> Result<impl Responder, error::Error> {
+    let aliases = subscribed_hashtag_service.list_aliases().await?;
+    let mut context = Context::new();
+    context.insert("feed", &feed.0);
+    context.insert("aliases", &aliases);
+    Ok(Html::new(
+        tmpl.render("admin/aliases.html", &context)
+            .map_err(error::ErrorInternalServerError)?,
+    ))
+}
+
+#[post("/aliases")]
+async fn add_alias(
+    _admin: Admin,
+    form: web::Form<AliasDTO>,
+    subscribed_hashtag_service: web::Data<dyn SubscribedHashtagService>,
+) -> Result<impl Responder, Box<dyn Error>> {
+    subscribed_hashtag_service
+        .add_alias(form.alias.trim(), form.canonical.trim())
+        .await?;
+    Ok(HttpResponse::Ok()
+        .append_header(("HX-Trigger", "suggestions-updated"))
+        .finish())
+}
+
+#[post("/aliases/{alias}/delete")]
+async fn delete_alias(
+    _admin: Admin,
+    path: web::Path<AliasPathDTO>,
+    subscribed_hashtag_service: web::Data<dyn SubscribedHashtagService>,
+) -> Result<impl Responder, Box<dyn Error>> {
+    subscribed_hashtag_service.remove_alias(&path.alias).await?;
+    Ok(HttpResponse::Ok()
+        .append_header(("HX-Trigger", "suggestions-updated"))
+        .finish())
+}
+
+#[get("/workers")]
+async fn list_workers(
+    _admin: Admin,
+    feed: CurrentFeed,
+    subscribed_hashtag_service: web::Data<dyn SubscribedHashtagService>,
+    health_service: web::Data<dyn HealthService>,
+    status_service: web::Data<dyn StatusService>,
+    worker_commands: web::Data<WorkerCommands>,
+    tmpl: web::Data<Tera>,
+) -> Result<impl Responder, error::Error> {
+    let mut context = Context::new();
+    context.insert("feed", &feed.0);
+    context.insert("workers", &health_service.workers());
+    context.insert("pending", &worker_commands.pending());
+    context.insert("pending_repairs", &status_service.pending_repairs());
+    context.insert(
+        "hashtags",
+        &subscribed_hashtag_service
+            .list_tracked_hashtags(&feed.slug)
+            .await?,
+    );
+    Ok(Html::new(
+        tmpl.render("admin/workers.html", &context)
+            .map_err(error::ErrorInternalServerError)?,
+    ))
+}
+
+/// Queue the command to its running worker
+fn queue_command(
+    worker_commands: &WorkerCommands,
+    command: WorkerCommand,
+) -> Result<HttpResponse, error::Error> {
+    let pending = worker_commands.send(command)?;
+    Ok(HttpResponse::Accepted()
+        .append_header(("HX-Trigger", "commands-updated"))
+        .json(serde_json::json!({ "pending": pending })))
+}
+
+/// Name of the polled hashtag, as stored in the cursors
+async fn polled_hashtag(
+    subscribed_hashtag_service: &dyn SubscribedHashtagService,
+    name: &str,
+) -> Result<String, error::Error> {
+    subscribed_hashtag_service
+        .list_polled_hashtags()
+        .await?
+        .into_iter()
+        .find(|hashtag| hashtag.eq_ignore_ascii_case(name))
+        .ok_or_else(|| error::ErrorNotFound("Hashtag not polled"))
+}
+
+#[post("/ingest")]
+async fn ingest(
+    _admin: Admin,
+    worker_commands: web::Data<WorkerCommands>,
+) -> Result<impl Responder, error::Error> {
+    queue_command(
+        &worker_commands,
+        WorkerCommand::UpdateTimeline { tag: None },
+    )
+}
+
+#[post("/tags/{name}/ingest")]
+async fn ingest_tag(
+    _admin: Admin,
+    path: web::Path<TagPathDTO>,
+    subscribed_hashtag_service: web::Data<dyn SubscribedHashtagService>,
+    worker_commands: web::Data<WorkerCommands>,
+) -> Result<impl Responder, error::Error> {
+    let tag = polled_hashtag(subscribed_hashtag_service.get_ref(), &path.name).await?;
+    queue_command(
+        &worker_commands,
+        WorkerCommand::UpdateTimeline { tag: Some(tag) },
+    )
+}
+
+#[post("/tags/{name}/reset-cursor")]
+async fn reset_cursor(
+    _admin: Admin,
+    path: web::Path<TagPathDTO>,
+    subscribed_hashtag_service: web::Data<dyn SubscribedHashtagService>,
+    worker_commands: web::Data<WorkerCommands>,
+) -> Result<impl Responder, error::Error> {
+    let tag = polled_hashtag(subscribed_hashtag_service.get_ref(), &path.name).await?;
+    queue_command(&worker_commands, WorkerCommand::ResetCursor { tag })
+}
+
+#[post("/statuses/refresh")]
+async fn refresh_statuses(
+    _admin: Admin,
+    form: web::Form<RefreshStatusesDTO>,
+    worker_commands: web::Data<WorkerCommands>,
+) -> Result<impl Responder, error::Error> {
+    let ids = form
+        .parse_ids()
+        .ok_or_else(|| error::ErrorBadRequest("Invalid status IDs"))?;
+    queue_command(&worker_commands, WorkerCommand::RefreshStatuses { ids })
+}
+
+fn admin_scope(path: &str) -> Scope {
+    web::scope(path)
+        .service(get_admin)
+        .service(list_suggestions)
+        .service(list_cooccurrences)
+        .service(list_aliases)
+        .service(add_alias)
+        .service(delete_alias)
+        .service(approve_tag)
+        .service(reject_tag)
+        .service(list_workers)
+        .service(ingest)
+        .service(ingest_tag)
+        .service(reset_cursor)
+        .service(refresh_statuses)
+}
+
+pub fn admin_config(cfg: &mut web::ServiceConfig) {
+    cfg.service(admin_scope("/admin"))
+        .service(admin_scope("/f/{feed}/admin"));
+}
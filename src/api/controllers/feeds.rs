@@ -0,0 +1,28 @@
+use crate::api::feed::CurrentFeed;
+use crate::api::session::{CurrentUser, HomeInstance};
+use crate::domain::models::feed::Feeds;
+use actix_web::web::Html;
+use actix_web::{Responder, error, web};
+use tera::{Context, Tera};
+
+async fn get_index(
+    feed: CurrentFeed,
+    user: CurrentUser,
+    home_instance: HomeInstance,
+    feeds: web::Data<Feeds>,
+    tmpl: web::Data<Tera>,
+) -> Result<impl Responder, error::Error> {
+    let mut context = Context::new();
+    context.insert("feed", &feed.0);
+    context.insert("feeds", &feeds.iter().collect::<Vec<_>>());
+    context.insert("user", &user.0);
+    context.insert("home_instance", &home_instance.0);
+    Ok(Html::new(
+        tmpl.render("index.html", &context)
+            .map_err(error::ErrorInternalServerError)?,
+    ))
+}
+
+pub fn feeds_config(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource(["/", "/f/{feed}"]).route(web::get().to(get_index)));
+}
@@ -1,17 +1,27 @@
 use crate::api::dto::hashtag::SuggestTagDTO;
+use crate::api::feed::CurrentFeed;
 use crate::domain::services::hashtag::SubscribedHashtagService;
 use crate::domain::services::status::StatusService;
+use crate::settings::ApplicationSettings;
 use actix_web::web::Html;
-use actix_web::{HttpResponse, Responder, error, get, post, web};
+use actix_web::{HttpResponse, Responder, Scope, error, get, post, web};
 use std::error::Error;
 use tera::{Context, Tera};
 
 #[get("")]
 async fn list_tags(
+    feed: CurrentFeed,
     subscribed_hashtags_service: web::Data<dyn SubscribedHashtagService>,
+    status_service: web::Data<dyn StatusService>,
     tmpl: web::Data<Tera>,
 ) -> Result<impl Responder, error::Error> {
-    let hashtags: Vec<String> = subscribed_hashtags_service.list_hashtags()?;
+    let hashtags: Vec<String> = status_service
+        .display_names(
+            subscribed_hashtags_service
+                .list_hashtags(&feed.slug)
+                .await?,
+        )
+        .await?;
     let mut context = Context::new();
     context.insert("hashtags", &hashtags);
     Ok(Html::new(
@@ -22,12 +32,32 @@ async fn list_tags(
 
 #[get("/popular")]
 async fn list_popular_tags(
+    feed: CurrentFeed,
+    subscribed_hashtags_service: web::Data<dyn SubscribedHashtagService>,
     status_service: web::Data<dyn StatusService>,
     tmpl: web::Data<Tera>,
+    settings: web::Data<ApplicationSettings>,
 ) -> Result<impl Responder, error::Error> {
-    let hashtags = status_service.popular_tags(vec![7, 30], 5)?;
+    let tracked = subscribed_hashtags_service
+        .list_tracked_hashtags(&feed.slug)
+        .await?;
+    let hashtags = status_service
+        .popular_tags(&tracked, vec![7, 30], 5)
+        .await?;
+    let rising = &settings.rising_tags;
+    let rising_tags = status_service
+        .rising_tags(
+            &tracked,
+            *rising.window,
+            *rising.baseline,
+            rising.min_count,
+            rising.min_ratio,
+            rising.limit,
+        )
+        .await?;
     let mut context = Context::new();
     context.insert("hashtags", &hashtags);
+    context.insert("rising_tags", &rising_tags);
     Ok(Html::new(
         tmpl.render("hashtags/list_popular.html", &context)
             .map_err(|e| error::ErrorInternalServerError(e))?,
@@ -36,22 +66,26 @@ async fn list_popular_tags(
 
 #[post("")]
 async fn suggest_tag(
+    feed: CurrentFeed,
     subscribed_hashtags_service: web::Data<dyn SubscribedHashtagService>,
     form: web::Form<SuggestTagDTO>,
 ) -> Result<impl Responder, Box<dyn Error>> {
     subscribed_hashtags_service
-        .suggest_hashtag(form.hashtag.as_str())
+        .suggest_hashtag(&feed.slug, form.hashtag.as_str())
         .await?;
     Ok(HttpResponse::Ok()
         .append_header(("HX-Trigger", "tags-updated"))
         .finish())
 }
 
+fn hashtags_scope(path: &str) -> Scope {
+    web::scope(path)
+        .service(list_tags)
+        .service(list_popular_tags)
+        .service(suggest_tag)
+}
+
 pub fn hashtags_config(cfg: &mut web::ServiceConfig) {
-    cfg.service(
-        web::scope("/tags")
-            .service(list_tags)
-            .service(list_popular_tags)
-            .service(suggest_tag),
-    );
+    cfg.service(hashtags_scope("/tags"))
+        .service(hashtags_scope("/f/{feed}/tags"));
 }
@@ -0,0 +1,54 @@
+use crate::api::auth::Admin;
+use crate::domain::models::health::WorkerState;
+use crate::domain::services::health::HealthService;
+use crate::infrastructure::metrics::Metrics;
+use actix_web::http::header::ContentType;
+use actix_web::{HttpResponse, Responder, error, get, web};
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct StatusReport {
+    workers: Vec<WorkerState>,
+}
+
+/// Liveness probe, answering as long as the server is running
+#[get("/healthz")]
+async fn healthz() -> impl Responder {
+    HttpResponse::Ok().body("ok")
+}
+
+/// Readiness probe, answering 503 when a dependency is unavailable
+#[get("/readyz")]
+async fn readyz(health_service: web::Data<dyn HealthService>) -> impl Responder {
+    let readiness = health_service.readiness().await;
+    if readiness.ready {
+        HttpResponse::Ok().json(readiness)
+    } else {
+        HttpResponse::ServiceUnavailable().json(readiness)
+    }
+}
+
+/// Runs of the background workers, including their error messages, for the
+/// administrator only
+#[get("/status")]
+async fn status(_admin: Admin, health_service: web::Data<dyn HealthService>) -> impl Responder {
+    HttpResponse::Ok().json(StatusReport {
+        workers: health_service.workers(),
+    })
+}
+
+/// Metrics in the Prometheus text format
+#[get("/metrics")]
+async fn metrics(metrics: web::Data<Metrics>) -> Result<impl Responder, error::Error> {
+    let text = metrics.encode().map_err(error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::plaintext())
+        .body(text))
+}
+
+pub fn health_config(cfg: &mut web::ServiceConfig) {
+    cfg.service(healthz)
+        .service(readyz)
+        .service(status)
+        .service(metrics);
+}
@@ -0,0 +1,188 @@
+use crate::api::dto::user::{InteractionDTO, ReplyDTO, StatusPathDTO};
+use crate::api::session::SignedInUser;
+use crate::domain::models::interaction::{Interaction, InteractionState};
+use crate::domain::services::interaction::{InteractionError, InteractionService};
+use actix_web::web::Html;
+use actix_web::{Responder, error, get, post, web};
+use log::warn;
+use tera::{Context, Tera};
+
+/// Failures are rendered in the fragments rather than as error responses,
+/// which htmx would not swap in
+fn error_message(error: &InteractionError) -> String {
+    warn!("Interaction failed: {}", error);
+    match error {
+        InteractionError::StatusError(_) | InteractionError::DbError(_) => {
+            "Something went wrong, please try again".to_string()
+        }
+        _ => error.to_string(),
+    }
+}
+
+fn render(tmpl: &Tera, template: &str, context: &Context) -> Result<Html, error::Error> {
+    Ok(Html::new(
+        tmpl.render(template, context)
+            .map_err(error::ErrorInternalServerError)?,
+    ))
+}
+
+async fn interact(
+    user: SignedInUser,
+    path: web::Path<StatusPathDTO>,
+    form: web::Form<InteractionDTO>,
+    interaction_service: web::Data<dyn InteractionService>,
+    tmpl: web::Data<Tera>,
+    interaction: Interaction,
+    active: bool,
+) -> Result<Html, error::Error> {
+    let mut context = Context::new();
+    match interaction_service
+        .interact(&user, &path.id, interaction, active)
+        .await
+    {
+        Ok(state) => {
+            context.extend(Context::from_serialize(state).map_err(error::ErrorInternalServerError)?)
+        }
+        Err(e) => {
+            // Render the button as it was
+            let state = InteractionState {
+                interaction,
+                status_id: path.id.clone(),
+                active: !active,
+                count: form.count,
+            };
+            context
+                .extend(Context::from_serialize(state).map_err(error::ErrorInternalServerError)?);
+            context.insert("error", &error_message(&e));
+        }
+    }
+    render(&tmpl, "account/interaction_button.html", &context)
+}
+
+#[post("/statuses/{id}/favourite")]
+async fn favourite(
+    user: SignedInUser,
+    path: web::Path<StatusPathDTO>,
+    form: web::Form<InteractionDTO>,
+    interaction_service: web::Data<dyn InteractionService>,
+    tmpl: web::Data<Tera>,
+) -> Result<impl Responder, error::Error> {
+    interact(
+        user,
+        path,
+        form,
+        interaction_service,
+        tmpl,
+        Interaction::Favourite,
+        true,
+    )
+    .await
+}
+
+#[post("/statuses/{id}/unfavourite")]
+async fn unfavourite(
+    user: SignedInUser,
+    path: web::Path<StatusPathDTO>,
+    form: web::Form<InteractionDTO>,
+    interaction_service: web::Data<dyn InteractionService>,
+    tmpl: web::Data<Tera>,
+) -> Result<impl Responder, error::Error> {
+    interact(
+        user,
+        path,
+        form,
+        interaction_service,
+        tmpl,
+        Interaction::Favourite,
+        false,
+    )
+    .await
+}
+
+#[post("/statuses/{id}/reblog")]
+async fn reblog(
+    user: SignedInUser,
+    path: web::Path<StatusPathDTO>,
+    form: web::Form<InteractionDTO>,
+    interaction_service: web::Data<dyn InteractionService>,
+    tmpl: web::Data<Tera>,
+) -> Result<impl Responder, error::Error> {
+    interact(
+        user,
+        path,
+        form,
+        interaction_service,
+        tmpl,
+        Interaction::Reblog,
+        true,
+    )
+    .await
+}
+
+#[post("/statuses/{id}/unreblog")]
+async fn unreblog(
+    user: SignedInUser,
+    path: web::Path<StatusPathDTO>,
+    form: web::Form<InteractionDTO>,
+    interaction_service: web::Data<dyn InteractionService>,
+    tmpl: web::Data<Tera>,
+) -> Result<impl Responder, error::Error> {
+    interact(
+        user,
+        path,
+        form,
+        interaction_service,
+        tmpl,
+        Interaction::Reblog,
+        false,
+    )
+    .await
+}
+
+#[get("/statuses/{id}/reply")]
+async fn get_reply_form(
+    _user: SignedInUser,
+    path: web::Path<StatusPathDTO>,
+    interaction_service: web::Data<dyn InteractionService>,
+    tmpl: web::Data<Tera>,
+) -> Result<impl Responder, error::Error> {
+    let mut context = Context::new();
+    context.insert("status_id", &path.id);
+    match interaction_service.author(&path.id).await {
+        Ok(acct) => context.insert("text", &format!("@{} ", acct)),
+        Err(e) => context.insert("error", &error_message(&e)),
+    }
+    render(&tmpl, "account/reply_form.html", &context)
+}
+
+#[post("/statuses/{id}/reply")]
+async fn reply(
+    user: SignedInUser,
+    path: web::Path<StatusPathDTO>,
+    form: web::Form<ReplyDTO>,
+    interaction_service: web::Data<dyn InteractionService>,
+    tmpl: web::Data<Tera>,
+) -> Result<impl Responder, error::Error> {
+    let mut context = Context::new();
+    match interaction_service.reply(&user, &path.id, &form.text).await {
+        Ok(url) => {
+            context.insert("url", &url);
+            render(&tmpl, "account/reply_sent.html", &context)
+        }
+        Err(e) => {
+            context.insert("status_id", &path.id);
+            context.insert("text", &form.text);
+            context.insert("error", &error_message(&e));
+            render(&tmpl, "account/reply_form.html", &context)
+        }
+    }
+}
+
+pub fn interactions_config(cfg: &mut web::ServiceConfig) {
+    cfg.service(favourite)
+        .service(unfavourite)
+        .service(reblog)
+        .service(unreblog)
+        .service(get_reply_form)
+        .service(reply);
+}
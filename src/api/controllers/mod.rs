@@ -1,2 +1,8 @@
+pub mod account;
+pub mod admin;
+pub mod feeds;
 pub mod hashtags;
+pub mod health;
+pub mod interactions;
+pub mod statuses;
 pub mod timeline;
@@ -0,0 +1,68 @@
+use crate::api::dto::status::SparklineDTO;
+use crate::api::feed::CurrentFeed;
+use crate::domain::services::status::StatusService;
+use crate::services::user::check_status_id;
+use actix_web::web::Html;
+use actix_web::{Responder, error, get, web};
+use log::warn;
+use tera::{Context, Tera};
+
+/// Page of a stored status, with its thread
+#[get("/{id}")]
+async fn get_status(
+    path: web::Path<String>,
+    feed: CurrentFeed,
+    status_service: web::Data<dyn StatusService>,
+    tmpl: web::Data<Tera>,
+) -> Result<impl Responder, error::Error> {
+    let id = path.into_inner();
+    check_status_id(&id).map_err(|_| error::ErrorNotFound("Not Found"))?;
+    let status = status_service
+        .load_statuses(vec![id.clone()])
+        .await?
+        .pop()
+        .ok_or_else(|| error::ErrorNotFound("Not Found"))?;
+    let engagements = status_service.engagement_history(&id).await?;
+    let versions = status_service.status_history(&id).await?;
+    // The status is worth showing even when its thread cannot be fetched
+    let thread = status_service
+        .thread_context(&id)
+        .await
+        .inspect_err(|e| warn!("Unable to get the thread of status {}: {}", id, e))
+        .ok();
+
+    let mut context = Context::new();
+    context.insert("feed", &feed.0);
+    context.insert("status", &status);
+    context.insert("sparkline", &SparklineDTO::new(&engagements));
+    context.insert("versions", &versions);
+    context.insert("thread", &thread);
+    Ok(Html::new(
+        tmpl.render("statuses/detail.html", &context)
+            .map_err(error::ErrorInternalServerError)?,
+    ))
+}
+
+#[get("/{id}/engagements")]
+async fn get_engagements(
+    path: web::Path<String>,
+    status_service: web::Data<dyn StatusService>,
+    tmpl: web::Data<Tera>,
+) -> Result<impl Responder, error::Error> {
+    let id = path.into_inner();
+    let history = status_service.engagement_history(&id).await?;
+    let mut context = Context::new();
+    context.insert("sparkline", &SparklineDTO::new(&history));
+    Ok(Html::new(
+        tmpl.render("statuses/engagements.html", &context)
+            .map_err(error::ErrorInternalServerError)?,
+    ))
+}
+
+pub fn statuses_config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/statuses")
+            .service(get_engagements)
+            .service(get_status),
+    );
+}
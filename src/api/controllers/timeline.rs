@@ -1,53 +1,150 @@
+use crate::api::feed::CurrentFeed;
+use crate::api::session::{CurrentUser, ExtractorFuture, HomeInstance, SignedInUser};
+use crate::domain::models::feed::Feed;
+use crate::domain::models::user::UserPreferences;
 use crate::domain::services::hashtag::SubscribedHashtagService;
 use crate::domain::services::status::StatusService;
+use crate::domain::services::user::UserService;
 use crate::settings::ApplicationSettings;
+use actix_web::dev::Payload;
 use actix_web::http::header::HttpDate;
 use actix_web::http::{StatusCode, header};
 use actix_web::web::Html;
 use actix_web::{
-    CustomizeResponder, Either, HttpRequest, HttpResponse, Responder, error, get, web,
+    CustomizeResponder, Either, FromRequest, HttpRequest, HttpResponse, Responder, Scope, error,
+    get, web,
 };
 use chrono::Utc;
 use log::{debug, error};
 use megalodon::entities::Status;
 use serde::Serialize;
+use std::collections::HashSet;
 use std::error::Error;
 use std::str::FromStr;
 use std::time::SystemTime;
 use tera::{Context, Tera};
 
 #[derive(Serialize)]
-struct TimelineContext {
+struct TimelineContext<'a> {
     statuses: Vec<Status>,
+    signed_in: bool,
+    saved_statuses: Option<&'a HashSet<String>>,
+    /// Instance of the anonymous visitor, to interact with the statuses
+    home_instance: Option<&'a str>,
+}
+
+/// Preferences of the signed-in user, applied on top of the shared index,
+/// and home instance of the anonymous visitors
+struct Personalization {
+    preferences: Option<UserPreferences>,
+    home_instance: Option<String>,
+}
+
+impl FromRequest for Personalization {
+    type Error = error::Error;
+    type Future = ExtractorFuture<Self>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let user = CurrentUser::from_request(req, payload);
+        let home_instance = HomeInstance::from_request(req, payload).into_inner();
+        let feed = CurrentFeed::from_request(req, payload).into_inner();
+        let user_service = req.app_data::<web::Data<dyn UserService>>().cloned();
+        Box::pin(async move {
+            let CurrentUser(user) = user.await?;
+            let HomeInstance(home_instance) = home_instance?;
+            let Some(user) = user else {
+                return Ok(Personalization {
+                    preferences: None,
+                    home_instance,
+                });
+            };
+            let feed = feed?;
+            let user_service = user_service
+                .ok_or_else(|| error::ErrorInternalServerError("user service is not configured"))?;
+            Ok(Personalization {
+                preferences: Some(user_service.preferences(&user, &feed.slug).await?),
+                home_instance,
+            })
+        })
+    }
+}
+
+impl Personalization {
+    /// Hashtags of the feed, restricted to the ones followed by the user
+    async fn hashtags(
+        &self,
+        feed: &Feed,
+        subscribed_hashtag_service: &dyn SubscribedHashtagService,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        let tracked = subscribed_hashtag_service
+            .list_tracked_hashtags(&feed.slug)
+            .await?;
+        Ok(match &self.preferences {
+            Some(preferences) if !preferences.followed_tags.is_empty() => preferences
+                .timeline_hashtags(tracked, &subscribed_hashtag_service.list_aliases().await?),
+            _ => tracked,
+        })
+    }
+
+    /// Number of statuses to retrieve before filtering
+    fn limit(&self, feed: &Feed) -> u16 {
+        match &self.preferences {
+            Some(preferences) => preferences.overfetch(feed.timeline_statuses_count),
+            None => feed.timeline_statuses_count,
+        }
+    }
+
+    fn apply(&self, feed: &Feed, statuses: Vec<Status>) -> Vec<Status> {
+        match &self.preferences {
+            Some(preferences) => preferences.apply(statuses, feed.timeline_statuses_count),
+            None => statuses,
+        }
+    }
 }
 
 async fn build_timeline(
     tmpl: web::Data<Tera>,
     settings: web::Data<ApplicationSettings>,
     statuses: Vec<Status>,
+    personalization: &Personalization,
     last_modified: Option<HttpDate>,
 ) -> Result<CustomizeResponder<Html>, error::Error> {
-    let timeline_context = TimelineContext { statuses };
+    let timeline_context = TimelineContext {
+        statuses,
+        signed_in: personalization.preferences.is_some(),
+        saved_statuses: personalization
+            .preferences
+            .as_ref()
+            .map(|p| &p.saved_statuses),
+        home_instance: personalization.home_instance.as_deref(),
+    };
+    // Personal timelines change as soon as the preferences do
+    let max_age = if personalization.preferences.is_some() {
+        header::CacheDirective::NoCache
+    } else {
+        header::CacheDirective::MaxAge(
+            settings
+                .timeline_update_frequency
+                .as_secs()
+                .try_into()
+                .unwrap_or(300),
+        )
+    };
     Context::from_serialize(timeline_context)
         .and_then(|context| tmpl.render("timeline.html", &context))
         .map(|rendered| {
-            let customized_res =
-                Html::new(rendered)
-                    .customize()
-                    .append_header(header::CacheControl(vec![
-                        header::CacheDirective::Private,
-                        header::CacheDirective::MaxAge(
-                            settings
-                                .timeline_update_frequency
-                                .as_secs()
-                                .try_into()
-                                .unwrap_or(300),
-                        ),
-                        header::CacheDirective::Extension(
-                            "stale-while-revalidate".to_string(),
-                            Some("120".to_string()),
-                        ),
-                    ]));
+            let customized_res = Html::new(rendered)
+                .customize()
+                .append_header(header::CacheControl(vec![
+                    header::CacheDirective::Private,
+                    max_age,
+                    header::CacheDirective::Extension(
+                        "stale-while-revalidate".to_string(),
+                        Some("120".to_string()),
+                    ),
+                ]))
+                // The links depend on the session and the home instance
+                .append_header((header::VARY, "Cookie"));
             match last_modified {
                 Some(v) => customized_res.append_header(header::LastModified(v)),
                 None => customized_res,
@@ -65,18 +162,23 @@ async fn build_timeline(
 #[get("")]
 async fn get_timeline(
     request: HttpRequest,
+    feed: CurrentFeed,
+    personalization: Personalization,
     subscribed_hashtag_service: web::Data<dyn SubscribedHashtagService>,
     status_service: web::Data<dyn StatusService>,
     tmpl: web::Data<Tera>,
     settings: web::Data<ApplicationSettings>,
 ) -> Result<impl Responder, error::Error> {
-    let hashtags = subscribed_hashtag_service.list_hashtags()?;
+    let hashtags = personalization
+        .hashtags(&feed, subscribed_hashtag_service.as_ref())
+        .await?;
 
     let statuses = status_service
-        .retrieve_statuses(Some(&hashtags), settings.timeline_statuses_count)
+        .retrieve_statuses(Some(&hashtags), feed.media, personalization.limit(&feed))
         .await?;
 
     debug!("{} statuses retrieved from storage", statuses.len());
+    let statuses = personalization.apply(&feed, statuses);
 
     let most_recent_dt = statuses
         .get(0)
@@ -101,36 +203,129 @@ async fn get_timeline(
     }
 
     Ok(Either::Right(
-        build_timeline(tmpl, settings, statuses, Some(most_recent)).await?,
+        build_timeline(
+            tmpl,
+            settings,
+            statuses,
+            &personalization,
+            Some(most_recent),
+        )
+        .await?,
     ))
 }
 
 #[get("/popular")]
 async fn get_popular(
+    feed: CurrentFeed,
+    personalization: Personalization,
     subscribed_hashtag_service: web::Data<dyn SubscribedHashtagService>,
     status_service: web::Data<dyn StatusService>,
     tmpl: web::Data<Tera>,
     settings: web::Data<ApplicationSettings>,
 ) -> Result<impl Responder, error::Error> {
-    let hashtags = subscribed_hashtag_service.list_hashtags()?;
+    let hashtags = personalization
+        .hashtags(&feed, subscribed_hashtag_service.as_ref())
+        .await?;
 
     let statuses = status_service
         .popular_statuses(
             Some(&hashtags),
+            feed.media,
             Utc::now() - chrono::Duration::days(7),
-            settings.timeline_statuses_count,
+            personalization.limit(&feed),
         )
         .await?;
 
     debug!("{} statuses retrieved from storage", statuses.len());
+    let statuses = personalization.apply(&feed, statuses);
+
+    build_timeline(tmpl, settings, statuses, &personalization, None).await
+}
+
+#[get("/hot")]
+async fn get_hot(
+    feed: CurrentFeed,
+    personalization: Personalization,
+    subscribed_hashtag_service: web::Data<dyn SubscribedHashtagService>,
+    status_service: web::Data<dyn StatusService>,
+    tmpl: web::Data<Tera>,
+    settings: web::Data<ApplicationSettings>,
+) -> Result<impl Responder, error::Error> {
+    let hashtags = personalization
+        .hashtags(&feed, subscribed_hashtag_service.as_ref())
+        .await?;
+
+    let statuses = status_service
+        .hot_statuses(Some(&hashtags), feed.media, personalization.limit(&feed))
+        .await?;
+
+    debug!("{} statuses retrieved from storage", statuses.len());
+    let statuses = personalization.apply(&feed, statuses);
+
+    build_timeline(tmpl, settings, statuses, &personalization, None).await
+}
+
+#[get("/rising")]
+async fn get_rising(
+    feed: CurrentFeed,
+    personalization: Personalization,
+    subscribed_hashtag_service: web::Data<dyn SubscribedHashtagService>,
+    status_service: web::Data<dyn StatusService>,
+    tmpl: web::Data<Tera>,
+    settings: web::Data<ApplicationSettings>,
+) -> Result<impl Responder, error::Error> {
+    let hashtags = personalization
+        .hashtags(&feed, subscribed_hashtag_service.as_ref())
+        .await?;
+
+    let statuses = status_service
+        .fastest_growing_statuses(
+            Some(&hashtags),
+            feed.media,
+            Utc::now() - chrono::Duration::days(1),
+            personalization.limit(&feed),
+        )
+        .await?;
+
+    debug!("{} statuses retrieved from storage", statuses.len());
+    let statuses = personalization.apply(&feed, statuses);
+
+    build_timeline(tmpl, settings, statuses, &personalization, None).await
+}
+
+#[get("/saved")]
+async fn get_saved(
+    user: SignedInUser,
+    status_service: web::Data<dyn StatusService>,
+    user_service: web::Data<dyn UserService>,
+    tmpl: web::Data<Tera>,
+    settings: web::Data<ApplicationSettings>,
+) -> Result<impl Responder, error::Error> {
+    let ids = user_service.saved_statuses(&user).await?;
+    let statuses = status_service.load_statuses(ids).await?;
+
+    debug!("{} saved statuses retrieved from storage", statuses.len());
+
+    let personalization = Personalization {
+        preferences: Some(UserPreferences {
+            saved_statuses: statuses.iter().map(|status| status.id.clone()).collect(),
+            ..Default::default()
+        }),
+        home_instance: None,
+    };
+    build_timeline(tmpl, settings, statuses, &personalization, None).await
+}
 
-    build_timeline(tmpl, settings, statuses, None).await
+fn timeline_scope(path: &str) -> Scope {
+    web::scope(path)
+        .service(get_timeline)
+        .service(get_popular)
+        .service(get_hot)
+        .service(get_rising)
+        .service(get_saved)
 }
 
 pub fn timeline_config(cfg: &mut web::ServiceConfig) {
-    cfg.service(
-        web::scope("/timeline")
-            .service(get_timeline)
-            .service(get_popular),
-    );
+    cfg.service(timeline_scope("/timeline"))
+        .service(timeline_scope("/f/{feed}/timeline"));
 }
@@ -4,3 +4,19 @@ use serde::Deserialize;
 pub struct SuggestTagDTO {
     pub hashtag: String,
 }
+
+#[derive(Deserialize)]
+pub struct AliasDTO {
+    pub alias: String,
+    pub canonical: String,
+}
+
+#[derive(Deserialize)]
+pub struct TagPathDTO {
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+pub struct AliasPathDTO {
+    pub alias: String,
+}
@@ -1 +1,3 @@
 pub mod hashtag;
+pub mod status;
+pub mod user;
@@ -0,0 +1,134 @@
+use crate::domain::models::status::EngagementSnapshot;
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+
+const SPARKLINE_WIDTH: f64 = 120.0;
+const SPARKLINE_HEIGHT: f64 = 24.0;
+/// Statuses refreshed by a single administration request
+const MAX_REFRESHED_IDS: usize = 100;
+
+#[derive(Serialize, Debug)]
+pub struct SparklineDTO {
+    pub width: f64,
+    pub height: f64,
+    /// SVG polyline points of the total engagements over time
+    pub points: String,
+    pub engagements_count: u32,
+    /// Engagements gained per hour over the last day of history
+    pub velocity: f64,
+}
+
+impl SparklineDTO {
+    pub fn new(snapshots: &[EngagementSnapshot]) -> Self {
+        let (first, last) = match (snapshots.first(), snapshots.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => {
+                return Self {
+                    width: SPARKLINE_WIDTH,
+                    height: SPARKLINE_HEIGHT,
+                    points: String::new(),
+                    engagements_count: 0,
+                    velocity: 0.0,
+                };
+            }
+        };
+
+        let span = (last.recorded_at - first.recorded_at).num_seconds().max(1) as f64;
+        let max = snapshots
+            .iter()
+            .map(EngagementSnapshot::engagements_count)
+            .max()
+            .unwrap_or(0)
+            .max(1) as f64;
+        let points = snapshots
+            .iter()
+            .map(|snapshot| {
+                let x = if snapshots.len() == 1 {
+                    SPARKLINE_WIDTH
+                } else {
+                    (snapshot.recorded_at - first.recorded_at).num_seconds() as f64 / span
+                        * SPARKLINE_WIDTH
+                };
+                let y =
+                    SPARKLINE_HEIGHT - snapshot.engagements_count() as f64 / max * SPARKLINE_HEIGHT;
+                format!("{:.1},{:.1}", x, y)
+            })
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        let window_start = last.recorded_at - Duration::days(1);
+        let velocity = snapshots
+            .iter()
+            .find(|snapshot| snapshot.recorded_at >= window_start)
+            .filter(|snapshot| snapshot.recorded_at < last.recorded_at)
+            .map(|snapshot| {
+                let hours = (last.recorded_at - snapshot.recorded_at).num_seconds() as f64 / 3600.0;
+                (last.engagements_count() as f64 - snapshot.engagements_count() as f64) / hours
+            })
+            .unwrap_or(0.0);
+
+        Self {
+            width: SPARKLINE_WIDTH,
+            height: SPARKLINE_HEIGHT,
+            points,
+            engagements_count: last.engagements_count(),
+            velocity,
+        }
+    }
+}
+
+/// Status IDs to refresh, separated by whitespace or commas
+#[derive(Deserialize)]
+pub struct RefreshStatusesDTO {
+    pub ids: String,
+}
+
+impl RefreshStatusesDTO {
+    /// The deduplicated IDs, or `None` when one of them is not a status ID
+    pub fn parse_ids(&self) -> Option<Vec<String>> {
+        let mut ids: Vec<String> = vec![];
+        for id in self
+            .ids
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|id| !id.is_empty())
+        {
+            if !id.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            if !ids.iter().any(|other| other == id) {
+                ids.push(id.to_string());
+            }
+        }
+        (!ids.is_empty() && ids.len() <= MAX_REFRESHED_IDS).then_some(ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn snapshot(hour: u32, favourites_count: u32) -> EngagementSnapshot {
+        EngagementSnapshot {
+            recorded_at: Utc.with_ymd_and_hms(2025, 1, 1, hour, 0, 0).unwrap(),
+            replies_count: 0,
+            reblogs_count: 0,
+            favourites_count,
+        }
+    }
+
+    #[test]
+    fn sparkline_scales_points_and_computes_velocity() {
+        let sparkline = SparklineDTO::new(&[snapshot(0, 0), snapshot(1, 5), snapshot(2, 10)]);
+        assert_eq!(sparkline.points, "0.0,24.0 60.0,12.0 120.0,0.0");
+        assert_eq!(sparkline.engagements_count, 10);
+        assert_eq!(sparkline.velocity, 5.0);
+    }
+
+    #[test]
+    fn sparkline_handles_empty_history() {
+        let sparkline = SparklineDTO::new(&[]);
+        assert!(sparkline.points.is_empty());
+        assert_eq!(sparkline.velocity, 0.0);
+    }
+}
@@ -0,0 +1,80 @@
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct LoginDTO {
+    pub instance: String,
+    /// Path to go back to once signed in
+    pub return_to: Option<String>,
+}
+
+/// Only redirect to local paths
+fn local_path(path: Option<&str>) -> &str {
+    match path {
+        Some(path) if path.starts_with('/') && !path.starts_with("//") => path,
+        _ => "/",
+    }
+}
+
+impl LoginDTO {
+    pub fn return_to(&self) -> &str {
+        local_path(self.return_to.as_deref())
+    }
+}
+
+#[derive(Deserialize)]
+pub struct LogoutDTO {
+    pub return_to: Option<String>,
+}
+
+impl LogoutDTO {
+    pub fn return_to(&self) -> &str {
+        local_path(self.return_to.as_deref())
+    }
+}
+
+#[derive(Deserialize)]
+pub struct HomeInstanceDTO {
+    /// Forget the home instance when empty
+    pub instance: String,
+    pub return_to: Option<String>,
+}
+
+impl HomeInstanceDTO {
+    pub fn return_to(&self) -> &str {
+        local_path(self.return_to.as_deref())
+    }
+}
+
+#[derive(Deserialize)]
+pub struct LoginCallbackDTO {
+    pub code: Option<String>,
+    pub state: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct MuteDTO {
+    pub acct: String,
+}
+
+#[derive(Deserialize)]
+pub struct MutePathDTO {
+    pub acct: String,
+}
+
+#[derive(Deserialize)]
+pub struct StatusPathDTO {
+    pub id: String,
+}
+
+/// Counter shown on an interaction button, rendered back unchanged when the
+/// instance refuses the action
+#[derive(Deserialize)]
+pub struct InteractionDTO {
+    #[serde(default)]
+    pub count: u32,
+}
+
+#[derive(Deserialize)]
+pub struct ReplyDTO {
+    pub text: String,
+}
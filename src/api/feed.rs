@@ -0,0 +1,39 @@
+use crate::domain::models::feed::{Feed, Feeds};
+use actix_web::dev::Payload;
+use actix_web::{FromRequest, HttpRequest, error, web};
+use std::future::{Ready, ready};
+use std::ops::Deref;
+
+/// Extractor resolving the feed of the request from the `{feed}` path
+/// segment, or the default feed for the routes outside of `/f/{feed}`.
+pub struct CurrentFeed(pub Feed);
+
+impl Deref for CurrentFeed {
+    type Target = Feed;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl FromRequest for CurrentFeed {
+    type Error = error::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let Some(feeds) = req.app_data::<web::Data<Feeds>>() else {
+            return ready(Err(error::ErrorInternalServerError(
+                "feeds are not configured",
+            )));
+        };
+        let feed = match req.match_info().get("feed") {
+            Some(slug) => feeds.get(slug),
+            None => Some(feeds.default_feed()),
+        };
+        ready(
+            feed.cloned()
+                .map(CurrentFeed)
+                .ok_or_else(|| error::ErrorNotFound("Not Found")),
+        )
+    }
+}
@@ -0,0 +1,32 @@
+use crate::infrastructure::metrics::Metrics;
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{Error, web};
+use std::time::Instant;
+
+/// Middleware recording the duration of the requests per route pattern, so
+/// that the feeds and status IDs do not end up in the labels. Static files and
+/// unknown paths are grouped as `other`.
+pub async fn record_request(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let started = Instant::now();
+    let metrics = req.app_data::<web::Data<Metrics>>().cloned();
+    let res = next.call(req).await?;
+    if let Some(metrics) = metrics {
+        let route = res
+            .request()
+            .match_pattern()
+            .filter(|pattern| !pattern.is_empty())
+            .unwrap_or_else(|| "other".to_string());
+        metrics.http_request(
+            res.request().method().as_str(),
+            &route,
+            res.status().as_u16(),
+            started.elapsed(),
+        );
+    }
+    Ok(res)
+}
@@ -1,2 +1,6 @@
+pub mod auth;
 pub mod controllers;
 pub mod dto;
+pub mod feed;
+pub mod metrics;
+pub mod session;
@@ -0,0 +1,87 @@
+use crate::domain::models::user::User;
+use crate::domain::services::user::UserService;
+use actix_web::dev::Payload;
+use actix_web::{FromRequest, HttpRequest, error, web};
+use std::future::{Future, Ready, ready};
+use std::ops::Deref;
+use std::pin::Pin;
+
+/// Cookie holding the ID of the server-side session
+pub const SESSION_COOKIE: &str = "session";
+/// Cookie holding the instance of an anonymous visitor
+pub const HOME_INSTANCE_COOKIE: &str = "home_instance";
+
+/// Future of the extractors reading the database
+pub type ExtractorFuture<T> = Pin<Box<dyn Future<Output = Result<T, error::Error>>>>;
+
+/// Extractor resolving the signed-in user from the session cookie, if any.
+pub struct CurrentUser(pub Option<User>);
+
+impl FromRequest for CurrentUser {
+    type Error = error::Error;
+    type Future = ExtractorFuture<Self>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let Some(cookie) = req.cookie(SESSION_COOKIE) else {
+            return Box::pin(ready(Ok(CurrentUser(None))));
+        };
+        let user_service = req.app_data::<web::Data<dyn UserService>>().cloned();
+        Box::pin(async move {
+            let user_service = user_service
+                .ok_or_else(|| error::ErrorInternalServerError("user service is not configured"))?;
+            Ok(CurrentUser(
+                user_service.current_user(cookie.value()).await?,
+            ))
+        })
+    }
+}
+
+/// Extractor for the routes reserved to signed-in users, responding with
+/// 401 to anonymous visitors.
+pub struct SignedInUser(pub User);
+
+impl Deref for SignedInUser {
+    type Target = User;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl FromRequest for SignedInUser {
+    type Error = error::Error;
+    type Future = ExtractorFuture<Self>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let user = CurrentUser::from_request(req, payload);
+        Box::pin(async move {
+            user.await?
+                .0
+                .map(SignedInUser)
+                .ok_or_else(|| error::ErrorUnauthorized("Sign in required"))
+        })
+    }
+}
+
+/// Extractor reading the home instance of the visitor from its cookie,
+/// ignoring values which are not a valid instance address.
+pub struct HomeInstance(pub Option<String>);
+
+impl FromRequest for HomeInstance {
+    type Error = error::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let Some(cookie) = req.cookie(HOME_INSTANCE_COOKIE) else {
+            return ready(Ok(HomeInstance(None)));
+        };
+        let Some(user_service) = req.app_data::<web::Data<dyn UserService>>() else {
+            return ready(Err(error::ErrorInternalServerError(
+                "user service is not configured",
+            )));
+        };
+        ready(Ok(HomeInstance(
+            user_service.home_instance(cookie.value()).ok(),
+        )))
+    }
+}
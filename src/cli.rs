@@ -0,0 +1,393 @@
+use crate::container::Container;
+use crate::domain::models::feed::DEFAULT_FEED;
+use crate::domain::services::hashtag::SubscribedHashtagService;
+use crate::infrastructure::database::{postgres, sqlite};
+use crate::infrastructure::services::templating;
+use crate::services::user::check_status_id;
+use crate::settings::ApplicationSettings;
+use actix_settings::BasicSettings;
+use clap::{Parser, Subcommand};
+use std::error::Error;
+use std::path::PathBuf;
+
+/// Curated timeline of the Mastodon posts with media for a set of hashtags
+#[derive(Parser, Debug)]
+#[command(version, about)]
+pub struct Cli {
+    /// Configuration file
+    #[arg(
+        long,
+        global = true,
+        env = "MEDIA_TIMELINE_CONFIG",
+        default_value = "config.toml"
+    )]
+    pub config: PathBuf,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Start the web server and the background workers (default)
+    Serve,
+    /// Manage the hashtags of a feed
+    Tags {
+        /// Slug of the feed
+        #[arg(long, default_value = DEFAULT_FEED)]
+        feed: String,
+        #[command(subcommand)]
+        command: TagsCommand,
+    },
+    /// Manage the stored statuses
+    Status {
+        #[command(subcommand)]
+        command: StatusCommand,
+    },
+    /// Rebuild or verify the index of the stored statuses
+    Index {
+        #[command(subcommand)]
+        command: IndexCommand,
+    },
+    /// Write the hashtags, cursors and statuses to a JSON Lines archive
+    Export { path: PathBuf },
+    /// Merge an archive written by `export` into the current data
+    Import { path: PathBuf },
+    /// Apply the pending database migrations
+    Migrate,
+    /// Print the size of the status index and the hashtags of each feed
+    Stats,
+    /// Inspect the configuration
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TagsCommand {
+    /// List the subscribed hashtags and the pending suggestions
+    List,
+    /// Approve a pending suggestion
+    Approve { name: String },
+    /// Reject a suggestion, or unsubscribe from a hashtag
+    Reject { name: String },
+    /// Subscribe to a hashtag
+    Add { name: String },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum StatusCommand {
+    /// Fetch statuses again from Mastodon and store them
+    Refresh {
+        #[arg(required = true)]
+        ids: Vec<String>,
+    },
+    /// Remove a status from the storage and the index
+    Delete { id: String },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum IndexCommand {
+    /// Index again every status file, e.g. after losing the database
+    Rebuild {
+        /// Statuses indexed per transaction
+        #[arg(long, default_value_t = 500)]
+        batch_size: usize,
+    },
+    /// List the indexed statuses without a file, and the files not indexed
+    Verify,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Validate the configuration and the templates
+    Check,
+}
+
+/// Run a command which does not need the services
+pub fn run_standalone(
+    command: &Command,
+    settings: &BasicSettings<ApplicationSettings>,
+) -> Option<Result<(), Box<dyn Error>>> {
+    match command {
+        Command::Migrate => Some(migrate(settings)),
+        Command::Config {
+            command: ConfigCommand::Check,
+        } => Some(check_config(settings)),
+        _ => None,
+    }
+}
+
+/// Run a command against the services of the container
+pub async fn run(command: Command, container: &Container) -> Result<(), Box<dyn Error>> {
+    match command {
+        Command::Tags { feed, command } => tags(container, &feed, command).await,
+        Command::Status { command } => status(container, command).await,
+        Command::Index { command } => index(container, command).await,
+        Command::Export { path } => {
+            let report = container.archive_service.export(&path).await?;
+            println!(
+                "Exported {} hashtags, {} aliases, {} cursors and {} statuses to {}",
+                report.hashtags,
+                report.aliases,
+                report.cursors,
+                report.statuses,
+                path.display()
+            );
+            if report.missing_files > 0 {
+                eprintln!(
+                    "{} indexed statuses were skipped, their file is missing or unparseable",
+                    report.missing_files
+                );
+            }
+            Ok(())
+        }
+        Command::Import { path } => {
+            let report = container.archive_service.import(&path).await?;
+            println!(
+                "Imported an archive of version {}: {} hashtags, {} aliases, {} cursors, {} statuses ({} already up to date) and {} engagement snapshots",
+                report.version,
+                report.hashtags,
+                report.aliases,
+                report.cursors,
+                report.statuses,
+                report.statuses_up_to_date,
+                report.engagement_snapshots
+            );
+            if report.unknown_records > 0 {
+                eprintln!("{} unknown records were skipped", report.unknown_records);
+            }
+            Ok(())
+        }
+        Command::Stats => stats(container).await,
+        command => Err(format!("{:?} is not handled by the services", command).into()),
+    }
+}
+
+fn migrate(settings: &BasicSettings<ApplicationSettings>) -> Result<(), Box<dyn Error>> {
+    let paths = &settings.application.paths;
+    let errors = paths.check();
+    if !errors.is_empty() {
+        return Err(errors.join("\n").into());
+    }
+    let pool = sqlite::open(&paths.database())?;
+    report_migrations("The SQLite database", sqlite::migrate(&pool)?);
+    if let Some(url) = &settings.application.database_url {
+        let pool = postgres::open(url)?;
+        report_migrations("The PostgreSQL database", postgres::migrate(&pool)?);
+    }
+    Ok(())
+}
+
+fn report_migrations(database: &str, applied: Vec<String>) {
+    if applied.is_empty() {
+        println!("{} is up to date", database);
+    }
+    for migration in applied {
+        println!("Applied {}", migration);
+    }
+}
+
+fn check_config(settings: &BasicSettings<ApplicationSettings>) -> Result<(), Box<dyn Error>> {
+    let mut errors = settings.application.validate();
+    errors.extend(settings.application.paths.check());
+    if let Err(e) = templating::initialize_tera(&settings.application.paths.templates_dir) {
+        errors.push(format!("templates: {}", e));
+    }
+    if errors.is_empty() {
+        println!("The configuration is valid");
+        return Ok(());
+    }
+    for error in &errors {
+        eprintln!("{}", error);
+    }
+    Err(format!("{} configuration error(s)", errors.len()).into())
+}
+
+async fn tags(
+    container: &Container,
+    feed: &str,
+    command: TagsCommand,
+) -> Result<(), Box<dyn Error>> {
+    if container.feeds.get(feed).is_none() {
+        return Err(format!("Unknown feed {}", feed).into());
+    }
+    let service = &container.subscribed_hashtag_service;
+    match command {
+        TagsCommand::List => {
+            for name in service.list_hashtags(feed).await? {
+                println!("#{}", name);
+            }
+            for (name, attributes) in service.list_suggestions(feed).await? {
+                println!("#{} (pending, {} votes)", name, attributes.votes);
+            }
+        }
+        TagsCommand::Approve { name } => {
+            if !is_suggested(service.as_ref(), feed, &name).await? {
+                return Err(format!("#{} is not a pending suggestion", name).into());
+            }
+            service.approve_hashtag(feed, &name).await?;
+            println!("Approved #{}", name);
+        }
+        TagsCommand::Reject { name } => {
+            let subscribed = service
+                .list_hashtags(feed)
+                .await?
+                .iter()
+                .any(|hashtag| hashtag.eq_ignore_ascii_case(&name));
+            if !subscribed && !is_suggested(service.as_ref(), feed, &name).await? {
+                return Err(format!("#{} is neither subscribed nor suggested", name).into());
+            }
+            service.reject_hashtag(feed, &name).await?;
+            println!("Rejected #{}", name);
+        }
+        TagsCommand::Add { name } => {
+            let name = name.trim_start_matches('#');
+            if name.is_empty() {
+                return Err("The hashtag must not be empty".into());
+            }
+            service.approve_hashtag(feed, name).await?;
+            println!("Subscribed to #{}", name);
+        }
+    }
+    Ok(())
+}
+
+async fn is_suggested(
+    service: &dyn SubscribedHashtagService,
+    feed: &str,
+    name: &str,
+) -> Result<bool, Box<dyn Error>> {
+    Ok(service
+        .list_suggestions(feed)
+        .await?
+        .iter()
+        .any(|(suggestion, _)| suggestion.eq_ignore_ascii_case(name)))
+}
+
+async fn status(container: &Container, command: StatusCommand) -> Result<(), Box<dyn Error>> {
+    let service = &container.status_service;
+    match command {
+        StatusCommand::Refresh { ids } => {
+            if let Some(id) = ids.iter().find(|id| check_status_id(id).is_err()) {
+                return Err(format!("Invalid status ID {}", id).into());
+            }
+            let statuses = service.fetch_statuses(&ids).await?;
+            let report = service.persist_statuses(&statuses).await?;
+            for failure in &report.failed {
+                eprintln!(
+                    "Status {} was not refreshed: {}",
+                    failure.status_id, failure.error
+                );
+            }
+            for id in ids
+                .iter()
+                .filter(|id| !statuses.iter().any(|status| &status.id == *id))
+            {
+                eprintln!("Status {} was not found", id);
+            }
+            println!(
+                "Refreshed {}/{} statuses",
+                report.persisted.len(),
+                ids.len()
+            );
+        }
+        StatusCommand::Delete { id } => {
+            if check_status_id(&id).is_err() {
+                return Err(format!("Invalid status ID {}", id).into());
+            }
+            if !service.delete_status(&id).await? {
+                return Err(format!("Status {} is not stored", id).into());
+            }
+            println!("Deleted status {}", id);
+        }
+    }
+    Ok(())
+}
+
+async fn index(container: &Container, command: IndexCommand) -> Result<(), Box<dyn Error>> {
+    let service = &container.status_service;
+    match command {
+        IndexCommand::Rebuild { batch_size } => {
+            let report = service.reindex(batch_size).await?;
+            for path in &report.unparseable {
+                eprintln!("Unparseable status file {}", path);
+            }
+            println!(
+                "Indexed {}/{} status files, {} unparseable",
+                report.indexed,
+                report.files,
+                report.unparseable.len()
+            );
+        }
+        IndexCommand::Verify => {
+            let consistency = service.verify_index().await?;
+            for id in &consistency.missing_files {
+                println!("Status {} is indexed but not stored", id);
+            }
+            for id in &consistency.unindexed_files {
+                println!("Status {} is stored but not indexed", id);
+            }
+            if !consistency.is_consistent() {
+                return Err(format!(
+                    "{} indexed statuses without a file, {} files not indexed",
+                    consistency.missing_files.len(),
+                    consistency.unindexed_files.len()
+                )
+                .into());
+            }
+            println!("The index matches the status files");
+        }
+    }
+    Ok(())
+}
+
+async fn stats(container: &Container) -> Result<(), Box<dyn Error>> {
+    let stats = container.status_service.index_stats().await?;
+    println!("Statuses: {}", stats.statuses);
+    if let (Some(oldest), Some(newest)) = (stats.oldest, stats.newest) {
+        println!("  from {} to {}", oldest, newest);
+    }
+    println!("Tags: {}", stats.tags);
+    println!("Engagement snapshots: {}", stats.engagement_snapshots);
+
+    let service = &container.subscribed_hashtag_service;
+    println!(
+        "Polled hashtags: {}",
+        service.list_polled_hashtags().await?.len()
+    );
+    for feed in container.feeds.iter() {
+        println!(
+            "Feed {}: {} hashtags, {} pending suggestions",
+            feed.slug,
+            service.list_hashtags(&feed.slug).await?.len(),
+            service.list_suggestions(&feed.slug).await?.len()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn cli_parses_the_subcommands() {
+        Cli::command().debug_assert();
+
+        let cli = Cli::try_parse_from(["media-timeline", "tags", "--feed", "minis", "add", "x"]);
+        assert!(matches!(
+            cli.unwrap().command,
+            Some(Command::Tags { feed, command: TagsCommand::Add { name } })
+                if feed == "minis" && name == "x"
+        ));
+        assert!(Cli::try_parse_from(["media-timeline", "status", "refresh"]).is_err());
+        assert!(
+            Cli::try_parse_from(["media-timeline"])
+                .unwrap()
+                .command
+                .is_none()
+        );
+    }
+}
@@ -1,17 +1,46 @@
+use crate::domain::models::command::WorkerCommands;
+use crate::domain::models::feed::Feeds;
+use crate::domain::models::health::WorkerStates;
+use crate::domain::repositories::archive::ArchiveRepository;
+use crate::domain::repositories::hashtag::SubscribedHashtagRepository;
+use crate::domain::repositories::health::HealthRepository;
+use crate::domain::repositories::status::{RecentStatusRepository, StatusIndexRepository};
+use crate::domain::services::archive::ArchiveService;
 use crate::domain::services::hashtag::SubscribedHashtagService;
+use crate::domain::services::health::HealthService;
+use crate::domain::services::interaction::InteractionService;
 use crate::domain::services::status::StatusService;
-use crate::infrastructure::database::sqlite;
+use crate::domain::services::user::UserService;
+use crate::infrastructure::database::{postgres, sqlite};
+use crate::infrastructure::metrics::Metrics;
+use crate::infrastructure::repositories::archive::ArchiveSqliteRepository;
+use crate::infrastructure::repositories::batching::BatchingStatusIndexRepository;
 use crate::infrastructure::repositories::hashtag::SubscribedHashtagSqliteRepository;
+use crate::infrastructure::repositories::health::HealthSqliteRepository;
+use crate::infrastructure::repositories::postgres::archive::ArchivePostgresRepository;
+use crate::infrastructure::repositories::postgres::hashtag::SubscribedHashtagPostgresRepository;
+use crate::infrastructure::repositories::postgres::health::HealthPostgresRepository;
+use crate::infrastructure::repositories::postgres::status::{
+    RecentStatusPostgresRepository, StatusPostgresRepository,
+};
 use crate::infrastructure::repositories::status::{
     RecentStatusSqliteRepository, StatusSqliteRepository,
 };
+use crate::infrastructure::repositories::user::UserSqliteRepository;
+use crate::infrastructure::services::instance::InstanceClient;
 use crate::infrastructure::services::mastodon::MastodonClient;
 use crate::infrastructure::services::templating;
+use crate::services::archive::ArchiveServiceImpl;
 use crate::services::hashtag::SubscribedHashtagServiceImpl;
+use crate::services::health::HealthServiceImpl;
+use crate::services::interaction::InteractionServiceImpl;
+use crate::services::ranking::HotRanker;
 use crate::services::status::StatusServiceImpl;
+use crate::services::user::UserServiceImpl;
 use crate::settings::ApplicationSettings;
 use actix_settings::BasicSettings;
 use actix_web::web;
+use std::error::Error;
 use std::sync::Arc;
 use tera::Tera;
 
@@ -20,28 +49,71 @@ const PKG_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 pub struct Container {
     pub settings: BasicSettings<ApplicationSettings>,
+    pub feeds: Arc<Feeds>,
     pub tera: Arc<Tera>,
     pub mastodon: Arc<MastodonClient>,
+    pub metrics: Arc<Metrics>,
     pub status_service: Arc<dyn StatusService>,
     pub subscribed_hashtag_service: Arc<dyn SubscribedHashtagService>,
+    pub user_service: Arc<dyn UserService>,
+    pub interaction_service: Arc<dyn InteractionService>,
+    pub health_service: Arc<dyn HealthService>,
+    pub archive_service: Arc<dyn ArchiveService>,
+    /// Shared with the `WorkerTracker`, which updates it
+    pub worker_states: Arc<WorkerStates>,
+    /// Shared with the `WorkerTracker`, which drains it
+    pub worker_commands: Arc<WorkerCommands>,
 }
 
 impl Container {
-    pub async fn new(settings: BasicSettings<ApplicationSettings>) -> Self {
-        let tera =
-            templating::initialize_tera().expect("Unable to initialize templating engine Tera");
+    pub async fn new(settings: BasicSettings<ApplicationSettings>) -> Result<Self, Box<dyn Error>> {
+        let paths = &settings.application.paths;
+        let tera = templating::initialize_tera(&paths.templates_dir).map_err(|e| {
+            format!(
+                "Unable to load the templates from {}: {}",
+                paths.templates_dir.display(),
+                e
+            )
+        })?;
 
         let user_agent = Some(String::from(format!("{}/{}", PKG_NAME, PKG_VERSION)));
+        let metrics =
+            Arc::new(Metrics::new().map_err(|e| format!("Unable to register the metrics: {}", e))?);
         let mastodon = Arc::new(
-            MastodonClient::new("https://dice.camp".to_owned(), user_agent)
-                .expect("Unable to initialize the Mastodon client"),
+            MastodonClient::new(
+                "https://dice.camp".to_owned(),
+                user_agent.clone(),
+                metrics.clone(),
+            )
+            .map_err(|e| format!("Unable to initialize the Mastodon client: {}", e))?,
         );
+        let instance_client = Arc::new(InstanceClient::new(user_agent));
 
-        let pool = Arc::new(sqlite::new().expect("Unable to initialize the database connection"));
-        let subscribed_hashtag_repository =
-            Arc::new(SubscribedHashtagSqliteRepository::new(pool.clone()));
-        let recent_status_repository = Arc::new(RecentStatusSqliteRepository::new(pool.clone()));
-        let status_index_repository = Arc::new(StatusSqliteRepository::new(pool.clone()));
+        let pool = Arc::new(sqlite::new(&paths.database()).map_err(|e| {
+            format!(
+                "Unable to open the database {}: {}",
+                paths.database().display(),
+                e
+            )
+        })?);
+        let user_repository = Arc::new(UserSqliteRepository::new(pool.clone()));
+        let repositories = match &settings.application.database_url {
+            Some(url) => {
+                let pool = Arc::new(
+                    postgres::new(url)
+                        .map_err(|e| format!("Unable to open the PostgreSQL database: {}", e))?,
+                );
+                Repositories::postgres(pool)
+            }
+            None => Repositories::sqlite(pool),
+        };
+        let Repositories {
+            subscribed_hashtag_repository,
+            recent_status_repository,
+            status_index_repository,
+            health_repository,
+            archive_repository,
+        } = repositories;
 
         let subscribed_hashtag_service = Arc::new(SubscribedHashtagServiceImpl::new(
             subscribed_hashtag_repository,
@@ -50,22 +122,100 @@ impl Container {
             mastodon.clone(),
             recent_status_repository.clone(),
             status_index_repository.clone(),
+            HotRanker::new(settings.application.hot_ranking.clone()),
+            metrics.clone(),
+            paths.statuses_dir(),
+        ));
+        let user_service = Arc::new(UserServiceImpl::new(
+            instance_client.clone(),
+            user_repository.clone(),
+            settings.application.accounts.clone(),
+        ));
+        let interaction_service = Arc::new(InteractionServiceImpl::new(
+            instance_client,
+            user_repository,
+            status_service.clone(),
+        ));
+        let archive_service = Arc::new(ArchiveServiceImpl::new(
+            archive_repository,
+            status_index_repository.clone(),
+            status_service.clone(),
+        ));
+        let worker_states = Arc::new(WorkerStates::default());
+        let health_service = Arc::new(HealthServiceImpl::new(
+            health_repository,
+            worker_states.clone(),
+            paths.data_dir.clone(),
         ));
 
-        Container {
+        Ok(Container {
+            feeds: Arc::new(settings.application.feeds()),
             settings,
             tera: Arc::new(tera),
             mastodon,
+            metrics,
             status_service,
             subscribed_hashtag_service,
-        }
+            user_service,
+            interaction_service,
+            health_service,
+            archive_service,
+            worker_states,
+            worker_commands: Arc::new(WorkerCommands::default()),
+        })
     }
 
     pub fn config(&self, cfg: &mut web::ServiceConfig) {
         cfg.app_data(web::Data::new(self.settings.application.clone()))
+            .app_data(web::Data::from(self.feeds.clone()))
             .app_data(web::Data::from(self.tera.clone()))
             .app_data(web::Data::from(self.mastodon.clone()))
+            .app_data(web::Data::from(self.metrics.clone()))
             .app_data(web::Data::from(self.status_service.clone()))
-            .app_data(web::Data::from(self.subscribed_hashtag_service.clone()));
+            .app_data(web::Data::from(self.subscribed_hashtag_service.clone()))
+            .app_data(web::Data::from(self.user_service.clone()))
+            .app_data(web::Data::from(self.interaction_service.clone()))
+            .app_data(web::Data::from(self.health_service.clone()))
+            .app_data(web::Data::from(self.worker_commands.clone()));
+    }
+}
+
+/// Repositories of the data shared by the replicas, stored in PostgreSQL when
+/// `database-url` is set and in SQLite otherwise
+struct Repositories {
+    subscribed_hashtag_repository: Arc<dyn SubscribedHashtagRepository>,
+    recent_status_repository: Arc<dyn RecentStatusRepository>,
+    status_index_repository: Arc<dyn StatusIndexRepository>,
+    health_repository: Arc<dyn HealthRepository>,
+    archive_repository: Arc<dyn ArchiveRepository>,
+}
+
+impl Repositories {
+    fn sqlite(pool: Arc<sqlite::Connection>) -> Self {
+        Self {
+            subscribed_hashtag_repository: Arc::new(SubscribedHashtagSqliteRepository::new(
+                pool.clone(),
+            )),
+            recent_status_repository: Arc::new(RecentStatusSqliteRepository::new(pool.clone())),
+            status_index_repository: Arc::new(BatchingStatusIndexRepository::new(Arc::new(
+                StatusSqliteRepository::new(pool.clone()),
+            ))),
+            health_repository: Arc::new(HealthSqliteRepository::new(pool.clone())),
+            archive_repository: Arc::new(ArchiveSqliteRepository::new(pool)),
+        }
+    }
+
+    fn postgres(pool: Arc<postgres::Connection>) -> Self {
+        Self {
+            subscribed_hashtag_repository: Arc::new(SubscribedHashtagPostgresRepository::new(
+                pool.clone(),
+            )),
+            recent_status_repository: Arc::new(RecentStatusPostgresRepository::new(pool.clone())),
+            status_index_repository: Arc::new(BatchingStatusIndexRepository::new(Arc::new(
+                StatusPostgresRepository::new(pool.clone()),
+            ))),
+            health_repository: Arc::new(HealthPostgresRepository::new(pool.clone())),
+            archive_repository: Arc::new(ArchivePostgresRepository::new(pool)),
+        }
     }
 }
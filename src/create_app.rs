@@ -1,5 +1,11 @@
+use crate::api::controllers::account::account_config;
+use crate::api::controllers::admin::admin_config;
+use crate::api::controllers::feeds::feeds_config;
 use crate::api::controllers::hashtags::hashtags_config;
+use crate::api::controllers::health::health_config;
+use crate::api::controllers::statuses::statuses_config;
 use crate::api::controllers::timeline::timeline_config;
+use crate::api::metrics::record_request;
 use crate::container::Container;
 use actix_files::Files;
 use actix_web::App;
@@ -22,6 +28,7 @@ pub fn create_app(
 > {
     App::new()
         .configure(|cfg| container.config(cfg))
+        .wrap(middleware::from_fn(record_request))
         .wrap(Condition::new(
             container.settings.actix.enable_log,
             Logger::new(r#"%{r}a "%r" %s %b "%{Referer}i" "%{User-Agent}i" %T"#),
@@ -31,7 +38,15 @@ pub fn create_app(
             middleware::Compress::default(),
         ))
         .wrap(middleware::NormalizePath::trim())
+        .configure(health_config)
         .configure(hashtags_config)
         .configure(timeline_config)
-        .service(Files::new("/", "static").index_file("index.html"))
+        .configure(statuses_config)
+        .configure(admin_config)
+        .configure(account_config)
+        .configure(feeds_config)
+        .service(Files::new(
+            "/",
+            &container.settings.application.paths.static_dir,
+        ))
 }
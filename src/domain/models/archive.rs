@@ -0,0 +1,80 @@
+use crate::domain::models::status::EngagementSnapshot;
+use chrono::{DateTime, Utc};
+use megalodon::entities::Status;
+use serde::{Deserialize, Serialize};
+
+pub const ARCHIVE_FORMAT: &str = "media-timeline";
+
+/// Version of the archives written by this release. Archives of an older
+/// version are converted when read; newer ones are refused.
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// A line of an archive, the first one being the header
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum ArchiveRecord {
+    Header {
+        format: String,
+        version: u32,
+        exported_at: DateTime<Utc>,
+    },
+    Hashtag {
+        feed: String,
+        name: String,
+        approved: bool,
+        votes: u32,
+        created_at: DateTime<Utc>,
+    },
+    Alias {
+        alias: String,
+        canonical: String,
+    },
+    /// Most recent status ID fetched for a hashtag
+    Cursor {
+        tag: String,
+        status_id: String,
+    },
+    Status {
+        status: Box<Status>,
+        refreshed_at: Option<DateTime<Utc>>,
+        #[serde(default)]
+        engagements: Vec<EngagementSnapshot>,
+    },
+    /// Record of a later version, skipped when imported
+    #[serde(other)]
+    Unknown,
+}
+
+impl ArchiveRecord {
+    pub fn header() -> Self {
+        ArchiveRecord::Header {
+            format: ARCHIVE_FORMAT.to_string(),
+            version: ARCHIVE_VERSION,
+            exported_at: Utc::now(),
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct ExportReport {
+    pub hashtags: usize,
+    pub aliases: usize,
+    pub cursors: usize,
+    pub statuses: usize,
+    /// Indexed statuses skipped because their file is missing
+    pub missing_files: usize,
+}
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct ImportReport {
+    pub version: u32,
+    pub hashtags: usize,
+    pub aliases: usize,
+    pub cursors: usize,
+    pub statuses: usize,
+    /// Statuses stored more recently than in the archive
+    pub statuses_up_to_date: usize,
+    /// Engagement snapshots which were not already recorded
+    pub engagement_snapshots: usize,
+    pub unknown_records: usize,
+}
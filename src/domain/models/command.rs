@@ -0,0 +1,179 @@
+use actix_web::ResponseError;
+use actix_web::http::StatusCode;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, RwLock};
+use thiserror::Error;
+use tokio::sync::Notify;
+
+/// Commands waiting in the queue of a worker before new ones are refused
+const MAX_PENDING_COMMANDS: usize = 100;
+
+pub const TIMELINE_UPDATER: &str = "timeline updater";
+pub const STATUS_REFRESHER: &str = "status refresher";
+pub const STATUS_REPAIRER: &str = "status repairer";
+
+/// On-demand work requested from the administration, handled by the running
+/// worker between two of its runs
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+pub enum WorkerCommand {
+    /// Fetch the new statuses of a polled hashtag, or of all of them
+    UpdateTimeline { tag: Option<String> },
+    /// Forget the most recent status ID of a hashtag, so that the next update
+    /// starts again from its latest page
+    ResetCursor { tag: String },
+    /// Fetch the given statuses again from Mastodon
+    RefreshStatuses { ids: Vec<String> },
+}
+
+impl WorkerCommand {
+    /// Name of the worker handling the command
+    pub fn worker(&self) -> &'static str {
+        match self {
+            WorkerCommand::UpdateTimeline { .. } | WorkerCommand::ResetCursor { .. } => {
+                TIMELINE_UPDATER
+            }
+            WorkerCommand::RefreshStatuses { .. } => STATUS_REFRESHER,
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum CommandError {
+    #[error("The {0} is not running")]
+    NotRunning(&'static str),
+    #[error("Too many commands are waiting for the {0}")]
+    QueueFull(&'static str),
+}
+
+impl ResponseError for CommandError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            CommandError::NotRunning(_) => StatusCode::CONFLICT,
+            CommandError::QueueFull(_) => StatusCode::TOO_MANY_REQUESTS,
+        }
+    }
+}
+
+/// Commands waiting for a single worker
+#[derive(Default)]
+pub struct CommandQueue {
+    commands: Mutex<VecDeque<WorkerCommand>>,
+    notify: Notify,
+}
+
+impl CommandQueue {
+    pub fn pop(&self) -> Option<WorkerCommand> {
+        self.commands
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .pop_front()
+    }
+
+    /// Wait until a command is pushed, returning immediately when one was
+    /// pushed since the last wait
+    pub async fn notified(&self) {
+        self.notify.notified().await
+    }
+
+    fn push(&self, command: WorkerCommand) -> Result<usize, CommandError> {
+        let mut commands = self.commands.lock().unwrap_or_else(|e| e.into_inner());
+        if commands.len() >= MAX_PENDING_COMMANDS {
+            return Err(CommandError::QueueFull(command.worker()));
+        }
+        commands.push_back(command);
+        self.notify.notify_one();
+        Ok(commands.len())
+    }
+
+    fn pending(&self) -> Vec<WorkerCommand> {
+        self.commands
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .cloned()
+            .collect()
+    }
+}
+
+/// Queues of the running workers, filled by the administration and drained
+/// by the `WorkerTracker`
+#[derive(Default)]
+pub struct WorkerCommands(RwLock<HashMap<&'static str, Arc<CommandQueue>>>);
+
+impl WorkerCommands {
+    pub fn register(&self, name: &'static str) -> Arc<CommandQueue> {
+        self.0
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(name)
+            .or_default()
+            .clone()
+    }
+
+    pub fn unregister(&self, name: &str) {
+        self.0
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(name);
+    }
+
+    /// Queue the command for its worker, returning the number of commands
+    /// now waiting for it
+    pub fn send(&self, command: WorkerCommand) -> Result<usize, CommandError> {
+        let worker = command.worker();
+        let queue = self
+            .0
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(worker)
+            .cloned()
+            .ok_or(CommandError::NotRunning(worker))?;
+        queue.push(command)
+    }
+
+    pub fn pending(&self) -> Vec<WorkerCommand> {
+        self.0
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .flat_map(|queue| queue.pending())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_are_queued_to_their_worker() {
+        let commands = WorkerCommands::default();
+        let queue = commands.register(TIMELINE_UPDATER);
+
+        let refresh = WorkerCommand::RefreshStatuses {
+            ids: vec!["1".to_string()],
+        };
+        assert!(matches!(
+            commands.send(refresh),
+            Err(CommandError::NotRunning(STATUS_REFRESHER))
+        ));
+
+        let update = WorkerCommand::UpdateTimeline { tag: None };
+        assert_eq!(commands.send(update.clone()).unwrap(), 1);
+        assert_eq!(commands.pending(), vec![update.clone()]);
+        assert_eq!(queue.pop(), Some(update));
+        assert_eq!(queue.pop(), None);
+
+        for _ in 0..MAX_PENDING_COMMANDS {
+            commands
+                .send(WorkerCommand::UpdateTimeline { tag: None })
+                .unwrap();
+        }
+        assert!(matches!(
+            commands.send(WorkerCommand::UpdateTimeline { tag: None }),
+            Err(CommandError::QueueFull(TIMELINE_UPDATER))
+        ));
+    }
+}
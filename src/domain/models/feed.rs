@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_FEED: &str = "default";
+
+/// Kinds of media of the statuses served by a feed
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum MediaFilter {
+    /// Images, videos, animated GIFs and audio
+    #[default]
+    All,
+    /// Only the statuses whose attachments are all images. The attachments of
+    /// an unknown type are displayed as images, and count as such.
+    Images,
+}
+
+/// A curated timeline, with its own hashtags and branding.
+#[derive(Serialize, Clone, Debug)]
+pub struct Feed {
+    pub slug: String,
+    pub title: String,
+    /// Introduction shown next to the timeline, as HTML
+    pub intro: String,
+    pub timeline_statuses_count: u16,
+    pub media: MediaFilter,
+    /// Prefix of the feed routes, empty for the default feed
+    pub base_path: String,
+}
+
+impl Feed {
+    pub fn is_default(&self) -> bool {
+        self.slug == DEFAULT_FEED
+    }
+}
+
+/// The feeds served by the application. The default feed always exists.
+#[derive(Clone, Debug)]
+pub struct Feeds {
+    feeds: Vec<Feed>,
+}
+
+impl Feeds {
+    pub fn new(mut feeds: Vec<Feed>, default: Feed) -> Self {
+        if !feeds.iter().any(Feed::is_default) {
+            feeds.insert(0, default);
+        }
+        Self { feeds }
+    }
+
+    pub fn get(&self, slug: &str) -> Option<&Feed> {
+        self.feeds.iter().find(|feed| feed.slug == slug)
+    }
+
+    pub fn default_feed(&self) -> &Feed {
+        self.get(DEFAULT_FEED)
+            .expect("the default feed is always registered")
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Feed> {
+        self.feeds.iter()
+    }
+}
@@ -1,5 +1,6 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct HashtagAttributes {
@@ -17,3 +18,36 @@ impl Default for HashtagAttributes {
         }
     }
 }
+
+/// A hashtag used significantly more in the current window than in the
+/// preceding baseline period.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RisingTag {
+    pub name: String,
+    /// Number of statuses in the current window
+    pub count: u32,
+    /// Number of statuses expected in the current window from the baseline usage
+    pub baseline_count: f64,
+    pub ratio: f64,
+}
+
+/// How often two hashtags are used on the same statuses.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TagCooccurrence {
+    pub name: String,
+    pub related: String,
+    /// Number of statuses tagged with both hashtags
+    pub count: u32,
+    /// How much more often both hashtags are used together than if they were
+    /// independent
+    pub lift: f64,
+}
+
+/// Tags used along with the subscribed hashtags.
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct RelatedTags {
+    /// Unsubscribed tags, ranked by the lift of their strongest association
+    pub suggestions: Vec<TagCooccurrence>,
+    /// Tags most often used with each subscribed hashtag
+    pub related: BTreeMap<String, Vec<TagCooccurrence>>,
+}
@@ -0,0 +1,202 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::RwLock;
+
+/// Outcome of a readiness check
+#[derive(Serialize, Clone, Debug)]
+pub struct Check {
+    pub name: &'static str,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Readiness {
+    pub ready: bool,
+    pub checks: Vec<Check>,
+}
+
+impl Readiness {
+    pub fn new(checks: Vec<Check>) -> Self {
+        Self {
+            ready: checks.iter().all(|check| check.error.is_none()),
+            checks,
+        }
+    }
+}
+
+/// Number of supervision events kept per worker
+const MAX_EVENTS: usize = 20;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SupervisionEventKind {
+    Panicked,
+    Restarted,
+    BackingOff,
+    Stopped,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SupervisionEvent {
+    pub at: DateTime<Utc>,
+    pub kind: SupervisionEventKind,
+    pub message: String,
+}
+
+/// Runs of a background worker since the start of the process
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct WorkerState {
+    pub name: String,
+    pub running: bool,
+    pub last_started_at: Option<DateTime<Utc>>,
+    pub last_finished_at: Option<DateTime<Utc>>,
+    pub last_success_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<DateTime<Utc>>,
+    /// Items processed by the last successful run
+    pub last_items_processed: usize,
+    pub items_processed: u64,
+    pub runs: u64,
+    pub failures: u64,
+    pub consecutive_failures: u32,
+    pub restarts: u64,
+    /// Set once the supervisor gave up on the worker
+    pub stopped: bool,
+    /// Most recent supervision events, oldest first
+    pub events: Vec<SupervisionEvent>,
+}
+
+impl WorkerState {
+    pub fn started(&mut self) {
+        self.running = true;
+        self.last_started_at = Some(Utc::now());
+    }
+
+    pub fn succeeded(&mut self, items: usize) {
+        let now = Utc::now();
+        self.finished(now);
+        self.last_success_at = Some(now);
+        self.last_items_processed = items;
+        self.items_processed += items as u64;
+        self.consecutive_failures = 0;
+    }
+
+    pub fn failed(&mut self, error: String) {
+        self.command_failed(error);
+        self.consecutive_failures += 1;
+    }
+
+    /// Record a failure which does not affect the scheduling of the runs
+    pub fn command_failed(&mut self, error: String) {
+        let now = Utc::now();
+        self.finished(now);
+        self.failures += 1;
+        self.last_error = Some(error);
+        self.last_error_at = Some(now);
+    }
+
+    pub fn panicked(&mut self, message: String) {
+        self.failed(format!("panicked: {}", message));
+        self.event(SupervisionEventKind::Panicked, message);
+    }
+
+    pub fn restarted(&mut self, message: String) {
+        self.restarts += 1;
+        self.event(SupervisionEventKind::Restarted, message);
+    }
+
+    pub fn stopped(&mut self, message: String) {
+        self.stopped = true;
+        self.event(SupervisionEventKind::Stopped, message);
+    }
+
+    pub fn event(&mut self, kind: SupervisionEventKind, message: String) {
+        if self.events.len() >= MAX_EVENTS {
+            self.events.remove(0);
+        }
+        self.events.push(SupervisionEvent {
+            at: Utc::now(),
+            kind,
+            message,
+        });
+    }
+
+    fn finished(&mut self, now: DateTime<Utc>) {
+        self.running = false;
+        self.runs += 1;
+        self.last_finished_at = Some(now);
+    }
+}
+
+/// States of the registered workers, updated by the `WorkerTracker` and
+/// reported by the status endpoint
+#[derive(Default)]
+pub struct WorkerStates(RwLock<Vec<WorkerState>>);
+
+impl WorkerStates {
+    pub fn register(&self, name: &str) {
+        let mut states = self.0.write().unwrap_or_else(|e| e.into_inner());
+        if !states.iter().any(|state| state.name == name) {
+            states.push(WorkerState {
+                name: name.to_string(),
+                ..Default::default()
+            });
+        }
+    }
+
+    pub fn update(&self, name: &str, f: impl FnOnce(&mut WorkerState)) {
+        let mut states = self.0.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(state) = states.iter_mut().find(|state| state.name == name) {
+            f(state);
+        }
+    }
+
+    pub fn list(&self) -> Vec<WorkerState> {
+        self.0.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn worker_states_record_the_runs() {
+        let states = WorkerStates::default();
+        states.register("updater");
+
+        states.update("updater", WorkerState::started);
+        assert!(states.list()[0].running);
+        states.update("updater", |state| state.succeeded(3));
+        states.update("updater", WorkerState::started);
+        states.update("updater", |state| state.failed("timeout".to_string()));
+        states.update("unknown", WorkerState::started);
+
+        let state = &states.list()[0];
+        assert!(!state.running);
+        assert_eq!((state.runs, state.failures), (2, 1));
+        assert_eq!(state.consecutive_failures, 1);
+        assert_eq!(state.items_processed, 3);
+        assert_eq!(state.last_error.as_deref(), Some("timeout"));
+        assert!(state.last_success_at <= state.last_error_at);
+        assert_eq!(states.list().len(), 1);
+    }
+
+    #[test]
+    fn worker_state_keeps_the_recent_events() {
+        let mut state = WorkerState::default();
+        for i in 0..MAX_EVENTS + 5 {
+            state.panicked(format!("panic {}", i));
+        }
+        state.stopped("too many restarts".to_string());
+
+        assert!(state.stopped);
+        assert_eq!(state.consecutive_failures as usize, MAX_EVENTS + 5);
+        assert_eq!(state.events.len(), MAX_EVENTS);
+        assert_eq!(state.events[0].message, "panic 6");
+        assert_eq!(
+            state.events.last().unwrap().kind,
+            SupervisionEventKind::Stopped
+        );
+    }
+}
@@ -0,0 +1,19 @@
+use serde::Serialize;
+
+/// Action on a status performed through the home instance of the user
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Interaction {
+    Favourite,
+    Reblog,
+}
+
+/// State of an interaction button once the action is performed
+#[derive(Serialize, Clone, Debug)]
+pub struct InteractionState {
+    pub interaction: Interaction,
+    /// ID of the status in the index
+    pub status_id: String,
+    pub active: bool,
+    pub count: u32,
+}
@@ -1 +1,9 @@
+pub mod archive;
+pub mod command;
+pub mod feed;
 pub mod hashtag;
+pub mod health;
+pub mod interaction;
+pub mod repair;
+pub mod status;
+pub mod user;
@@ -0,0 +1,68 @@
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+
+/// Statuses waiting for a repair before new ones are dropped, to be queued
+/// again the next time they are read
+const MAX_PENDING_REPAIRS: usize = 10_000;
+
+/// Indexed statuses whose file is missing or no longer parses, waiting to be
+/// fetched again from Mastodon
+#[derive(Default)]
+pub struct StatusRepairs {
+    ids: Mutex<BTreeSet<String>>,
+}
+
+impl StatusRepairs {
+    /// Queue the status, returning whether it was not already pending
+    pub fn queue(&self, id: &str) -> bool {
+        let mut ids = self.ids.lock().unwrap_or_else(|e| e.into_inner());
+        if ids.len() >= MAX_PENDING_REPAIRS {
+            return false;
+        }
+        ids.insert(id.to_string())
+    }
+
+    /// Remove up to `limit` statuses from the queue
+    pub fn take(&self, limit: usize) -> Vec<String> {
+        let mut ids = self.ids.lock().unwrap_or_else(|e| e.into_inner());
+        let taken: Vec<String> = ids.iter().take(limit).cloned().collect();
+        for id in &taken {
+            ids.remove(id);
+        }
+        taken
+    }
+
+    pub fn pending(&self) -> usize {
+        self.ids.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+}
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct RepairReport {
+    /// Statuses fetched again and written
+    pub repaired: usize,
+    /// Statuses deleted from Mastodon, removed from the storage
+    pub deleted: usize,
+    /// Statuses fetched but not written
+    pub failed: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repairs_are_queued_once() {
+        let repairs = StatusRepairs::default();
+        assert!(repairs.queue("2"));
+        assert!(repairs.queue("1"));
+        assert!(!repairs.queue("2"));
+        assert_eq!(repairs.pending(), 2);
+
+        assert_eq!(repairs.take(1), vec!["1"]);
+        assert_eq!(repairs.take(10), vec!["2"]);
+        assert!(repairs.take(10).is_empty());
+        assert!(repairs.queue("1"));
+    }
+}
@@ -0,0 +1,93 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Engagement counters of a status as observed at a given refresh.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EngagementSnapshot {
+    pub recorded_at: DateTime<Utc>,
+    pub replies_count: u32,
+    pub reblogs_count: u32,
+    pub favourites_count: u32,
+}
+
+impl EngagementSnapshot {
+    pub fn engagements_count(&self) -> u32 {
+        self.replies_count + self.reblogs_count + self.favourites_count
+    }
+}
+
+/// Ranking inputs of an indexed status.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct IndexedStatus {
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+    pub account_acct: String,
+    pub replies_count: u32,
+    pub reblogs_count: u32,
+    pub favourites_count: u32,
+}
+
+/// Size of the status index
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct IndexStats {
+    pub statuses: u64,
+    /// Distinct (lowercased) tags of the indexed statuses
+    pub tags: u64,
+    pub engagement_snapshots: u64,
+    pub oldest: Option<DateTime<Utc>>,
+    pub newest: Option<DateTime<Utc>>,
+}
+
+/// Outcome of rebuilding the index from the status files
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct ReindexReport {
+    pub files: usize,
+    pub indexed: usize,
+    /// Paths of the files which could not be read or parsed
+    pub unparseable: Vec<String>,
+}
+
+/// Outcome of the recovery of the storage at startup
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct RecoveryReport {
+    /// Temporary files of interrupted writes, removed once stale
+    pub temp_files: usize,
+    /// Status files which were not indexed, indexed now
+    pub indexed: usize,
+    /// Status files indexed again to record the types of their attachments
+    pub reindexed: usize,
+    /// Indexed statuses without a file, queued for a repair
+    pub queued_repairs: usize,
+    /// Paths of the files not indexed which could not be read or parsed
+    pub unparseable: Vec<String>,
+}
+
+/// Outcome of persisting fetched statuses
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct PersistReport {
+    /// IDs of the statuses written and indexed
+    pub persisted: Vec<String>,
+    pub failed: Vec<PersistFailure>,
+}
+
+/// Status which could not be written or indexed
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct PersistFailure {
+    pub status_id: String,
+    pub error: String,
+}
+
+/// Differences between the index and the status files
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct IndexConsistency {
+    /// Indexed statuses without a file
+    pub missing_files: Vec<String>,
+    /// Status files which are not indexed
+    pub unindexed_files: Vec<String>,
+}
+
+impl IndexConsistency {
+    pub fn is_consistent(&self) -> bool {
+        self.missing_files.is_empty() && self.unindexed_files.is_empty()
+    }
+}
@@ -0,0 +1,129 @@
+use megalodon::entities::Status;
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// A visitor signed in with the account of their own instance.
+#[derive(Serialize, Clone, Debug)]
+pub struct User {
+    pub id: i64,
+    /// Base URL of the home instance of the user, e.g. `https://mastodon.social`
+    pub instance: String,
+    pub account_id: String,
+    pub acct: String,
+    pub display_name: String,
+    #[serde(skip)]
+    pub access_token: String,
+}
+
+/// OAuth client registered on an instance for one of our redirect URIs.
+#[derive(Clone, Debug)]
+pub struct OAuthApp {
+    pub client_id: String,
+    pub client_secret: String,
+}
+
+/// Sign-in started on an instance, waiting for the OAuth callback.
+#[derive(Clone, Debug)]
+pub struct PendingLogin {
+    pub instance: String,
+    pub redirect_uri: String,
+    /// Path to go back to once signed in
+    pub return_to: String,
+}
+
+/// Personal preferences applied on top of the shared index.
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct UserPreferences {
+    /// Lowercased hashtags of the feed followed by the user, all of them when empty
+    pub followed_tags: HashSet<String>,
+    pub muted_accounts: HashSet<String>,
+    pub hidden_statuses: HashSet<String>,
+    pub saved_statuses: HashSet<String>,
+}
+
+impl UserPreferences {
+    /// Restrict the hashtags tracked by a feed to the ones followed by the
+    /// user, keeping the aliases of the followed hashtags.
+    pub fn timeline_hashtags(
+        &self,
+        tracked: Vec<String>,
+        aliases: &[(String, String)],
+    ) -> Vec<String> {
+        if self.followed_tags.is_empty() {
+            return tracked;
+        }
+        tracked
+            .into_iter()
+            .filter(|name| {
+                let name = name.to_lowercase();
+                let canonical = aliases
+                    .iter()
+                    .find(|(alias, _)| *alias == name)
+                    .map(|(_, canonical)| canonical)
+                    .unwrap_or(&name);
+                self.followed_tags.contains(canonical)
+            })
+            .collect()
+    }
+
+    pub fn is_visible(&self, status: &Status) -> bool {
+        !self.hidden_statuses.contains(&status.id)
+            && !self
+                .muted_accounts
+                .contains(&status.account.acct.to_lowercase())
+    }
+
+    /// Remove the hidden statuses and the ones from muted accounts
+    pub fn apply(&self, statuses: Vec<Status>, limit: u16) -> Vec<Status> {
+        statuses
+            .into_iter()
+            .filter(|status| self.is_visible(status))
+            .take(limit.into())
+            .collect()
+    }
+
+    /// Number of statuses to retrieve so that `apply` is likely to still
+    /// return `limit` statuses once the hidden ones are filtered out.
+    pub fn overfetch(&self, limit: u16) -> u16 {
+        if self.hidden_statuses.is_empty() && self.muted_accounts.is_empty() {
+            limit
+        } else {
+            limit.saturating_mul(2)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preferences(followed: &[&str]) -> UserPreferences {
+        UserPreferences {
+            followed_tags: followed.iter().map(|name| name.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn timeline_hashtags_keeps_everything_without_followed_tags() {
+        let tracked = vec!["miniatures".to_string(), "warhammer".to_string()];
+        assert_eq!(
+            preferences(&[]).timeline_hashtags(tracked.clone(), &[]),
+            tracked
+        );
+    }
+
+    #[test]
+    fn timeline_hashtags_keeps_aliases_of_followed_tags() {
+        let tracked = vec![
+            "miniatures".to_string(),
+            "minis".to_string(),
+            "warhammer".to_string(),
+        ];
+        let aliases = vec![("minis".to_string(), "miniatures".to_string())];
+        assert_eq!(
+            preferences(&["miniatures"]).timeline_hashtags(tracked, &aliases),
+            vec!["miniatures".to_string(), "minis".to_string()]
+        );
+    }
+}
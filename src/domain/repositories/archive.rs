@@ -0,0 +1,31 @@
+use crate::domain::models::archive::ArchiveRecord;
+use crate::domain::models::status::EngagementSnapshot;
+use crate::infrastructure::error::DbError;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+pub trait ArchiveRepository: 'static + Sync + Send {
+    /// List the subscribed hashtags of all the feeds, as `Hashtag` records
+    fn list_hashtags(&self) -> Result<Vec<ArchiveRecord>, DbError>;
+
+    /// List the hashtag aliases, as `Alias` records
+    fn list_aliases(&self) -> Result<Vec<ArchiveRecord>, DbError>;
+
+    /// List the most recent status ID of each hashtag, as `Cursor` records
+    fn list_cursors(&self) -> Result<Vec<ArchiveRecord>, DbError>;
+
+    /// Last refresh of each indexed status
+    fn list_refreshes(&self) -> Result<HashMap<String, DateTime<Utc>>, DbError>;
+
+    /// Merge the hashtag, alias and cursor records in a single transaction,
+    /// ignoring the other ones. Merging the same records again has no effect.
+    fn merge_records(&self, records: &[ArchiveRecord]) -> Result<(), DbError>;
+
+    /// Record the engagement snapshots of a status which are not already
+    /// recorded, returning how many were
+    fn merge_engagements(
+        &self,
+        status_id: &str,
+        snapshots: &[EngagementSnapshot],
+    ) -> Result<usize, DbError>;
+}
@@ -1,8 +1,20 @@
+use crate::domain::models::hashtag::HashtagAttributes;
+use crate::infrastructure::error::DbError;
 use async_trait::async_trait;
-use std::error::Error;
 
 #[async_trait]
 pub trait SubscribedHashtagRepository: 'static + Sync + Send {
-    fn increment_vote(&self, key: &str) -> Result<(), Box<dyn Error>>;
-    fn list(&self) -> Result<Vec<String>, Box<dyn Error>>;
+    fn increment_vote(&self, feed: &str, key: &str) -> Result<(), DbError>;
+    fn list(&self, feed: &str) -> Result<Vec<String>, DbError>;
+    /// List the suggested hashtags waiting for approval, most voted first
+    fn list_pending(&self, feed: &str) -> Result<Vec<(String, HashtagAttributes)>, DbError>;
+    fn set_approved(&self, feed: &str, key: &str, approved: bool) -> Result<(), DbError>;
+    fn delete(&self, feed: &str, key: &str) -> Result<(), DbError>;
+    /// List the approved hashtags, lowercased, and the aliases of their alias
+    /// groups, for a single feed or across all of them
+    fn list_tracked(&self, feed: Option<&str>) -> Result<Vec<String>, DbError>;
+    /// List the `(alias, canonical)` pairs, both lowercased
+    fn list_aliases(&self) -> Result<Vec<(String, String)>, DbError>;
+    fn set_alias(&self, alias: &str, canonical: &str) -> Result<(), DbError>;
+    fn delete_alias(&self, alias: &str) -> Result<(), DbError>;
 }
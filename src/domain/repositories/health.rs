@@ -0,0 +1,9 @@
+use crate::infrastructure::error::DbError;
+
+pub trait HealthRepository: 'static + Sync + Send {
+    /// Run a trivial query against the database
+    fn ping(&self) -> Result<(), DbError>;
+
+    /// Names of the migrations which are not applied to the database
+    fn pending_migrations(&self) -> Result<Vec<String>, DbError>;
+}
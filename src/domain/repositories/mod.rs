@@ -1,2 +1,5 @@
+pub mod archive;
 pub mod hashtag;
+pub mod health;
 pub mod status;
+pub mod user;
@@ -1,12 +1,19 @@
+use crate::domain::models::feed::MediaFilter;
+use crate::domain::models::hashtag::{RisingTag, TagCooccurrence};
+use crate::domain::models::status::{EngagementSnapshot, IndexStats, IndexedStatus};
 use crate::infrastructure::error::DbError;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use megalodon::entities::Status;
+use std::collections::HashMap;
+use std::time::Duration;
 
 #[async_trait]
 pub trait RecentStatusRepository: 'static + Sync + Send {
     fn get_recent_status_id(&self, key: &str) -> Result<Option<String>, DbError>;
     fn set_recent_status_id(&self, key: &String, value: &String) -> Result<(), DbError>;
+    /// Forget the most recent status ID, returning whether there was one
+    fn delete_recent_status_id(&self, key: &str) -> Result<bool, DbError>;
 }
 
 pub trait StatusIndexRepository: 'static + Sync + Send {
@@ -15,16 +22,26 @@ pub trait StatusIndexRepository: 'static + Sync + Send {
     fn search_statuses(
         &self,
         hashtags: Option<&Vec<String>>,
+        media: MediaFilter,
         limit: u16,
     ) -> Result<Vec<String>, DbError>;
 
     fn popular_statuses(
         &self,
         hashtags_o: Option<&Vec<String>>,
+        media: MediaFilter,
         since: DateTime<Utc>,
         limit: u16,
     ) -> Result<Vec<String>, DbError>;
 
+    /// List the ranking inputs of the statuses created after `since`.
+    fn list_ranking_candidates(
+        &self,
+        hashtags_o: Option<&Vec<String>>,
+        media: MediaFilter,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<IndexedStatus>, DbError>;
+
     fn list_stale_statuses(
         &self,
         since: DateTime<Utc>,
@@ -32,6 +49,76 @@ pub trait StatusIndexRepository: 'static + Sync + Send {
         limit: u16,
     ) -> Result<Vec<String>, DbError>;
 
-    fn popular_tags(&self, duration_days: &u16, limit: &u16)
-    -> Result<Vec<(String, u32)>, DbError>;
+    /// Count the statuses per alias group, named after their canonical hashtag.
+    /// When `hashtags_o` is set, only the statuses using one of them are counted.
+    fn popular_tags(
+        &self,
+        hashtags_o: Option<&[String]>,
+        duration_days: &u16,
+        limit: &u16,
+    ) -> Result<Vec<(String, u32)>, DbError>;
+
+    /// Find the most common casing of each hashtag, keyed by lowercased name.
+    fn preferred_casings(&self, names: &[String]) -> Result<HashMap<String, String>, DbError>;
+
+    /// List the alias groups, named after their canonical hashtag, used at
+    /// least `min_ratio` times more since `since` than on average between
+    /// `baseline_since` and `since`, among the statuses using one of
+    /// `hashtags_o` when set.
+    fn rising_tags(
+        &self,
+        hashtags_o: Option<&[String]>,
+        since: DateTime<Utc>,
+        baseline_since: DateTime<Utc>,
+        min_count: u32,
+        min_ratio: f64,
+        limit: u16,
+    ) -> Result<Vec<RisingTag>, DbError>;
+
+    /// List the pairs of (lowercased) tags used together on at least
+    /// `min_count` statuses created after `since`, in both directions.
+    fn tag_cooccurrences(
+        &self,
+        since: DateTime<Utc>,
+        min_count: u32,
+    ) -> Result<Vec<TagCooccurrence>, DbError>;
+
+    /// List the engagement snapshots of a status, oldest first.
+    fn engagement_history(&self, status_id: &str) -> Result<Vec<EngagementSnapshot>, DbError>;
+
+    /// List statuses by engagements gained per hour between their first and
+    /// last snapshot recorded after `since`, negative when they lost some.
+    fn engagement_velocity(
+        &self,
+        hashtags_o: Option<&Vec<String>>,
+        media: MediaFilter,
+        since: DateTime<Utc>,
+        limit: u16,
+    ) -> Result<Vec<(String, f64)>, DbError>;
+
+    /// Keep only the latest snapshot per `resolution` bucket for statuses
+    /// created before `created_before`. Returns the number of deleted snapshots.
+    fn compact_engagements(
+        &self,
+        created_before: DateTime<Utc>,
+        resolution: Duration,
+    ) -> Result<usize, DbError>;
+
+    /// Remove a status with its tags, refreshes and engagement snapshots.
+    /// Returns whether the status was indexed.
+    fn delete_status(&self, status_id: &str) -> Result<bool, DbError>;
+
+    /// Index statuses read from the storage, along with the time they were
+    /// written, without recording engagement snapshots
+    fn reindex_statuses(&self, statuses: Vec<(&Status, DateTime<Utc>)>) -> Result<(), DbError>;
+
+    fn list_status_ids(&self) -> Result<Vec<String>, DbError>;
+
+    /// Whether the one-off backfill `name` of the index was completed
+    fn is_backfilled(&self, name: &str) -> Result<bool, DbError>;
+
+    /// Record that the one-off backfill `name` of the index was completed
+    fn mark_backfilled(&self, name: &str) -> Result<(), DbError>;
+
+    fn index_stats(&self) -> Result<IndexStats, DbError>;
 }
@@ -0,0 +1,91 @@
+use crate::domain::models::user::{OAuthApp, PendingLogin, User};
+use crate::infrastructure::error::DbError;
+use chrono::{DateTime, Utc};
+
+pub trait UserRepository: 'static + Sync + Send {
+    /// Retrieve the OAuth client registered on `instance` for `redirect_uri`
+    /// with the given space-separated `scopes`
+    fn get_app(
+        &self,
+        instance: &str,
+        redirect_uri: &str,
+        scopes: &str,
+    ) -> Result<Option<OAuthApp>, DbError>;
+    fn save_app(
+        &self,
+        instance: &str,
+        redirect_uri: &str,
+        scopes: &str,
+        app: &OAuthApp,
+    ) -> Result<(), DbError>;
+    fn count_apps(&self) -> Result<u32, DbError>;
+
+    /// Remember a sign-in until its callback, and forget the ones started
+    /// before `expired_before`
+    fn save_login(
+        &self,
+        state: &str,
+        login: &PendingLogin,
+        created_at: DateTime<Utc>,
+        expired_before: DateTime<Utc>,
+    ) -> Result<(), DbError>;
+    /// Retrieve and forget a sign-in started after `since`
+    fn take_login(
+        &self,
+        state: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Option<PendingLogin>, DbError>;
+
+    /// Create or update the user of an account, returning its ID
+    fn upsert_user(
+        &self,
+        instance: &str,
+        account_id: &str,
+        acct: &str,
+        display_name: &str,
+        access_token: &str,
+    ) -> Result<i64, DbError>;
+
+    fn create_session(
+        &self,
+        session_id: &str,
+        user_id: i64,
+        created_at: DateTime<Utc>,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), DbError>;
+    /// Retrieve the user of a session expiring after `now`
+    fn get_session_user(
+        &self,
+        session_id: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Option<User>, DbError>;
+    fn delete_session(&self, session_id: &str) -> Result<(), DbError>;
+    fn delete_expired_sessions(&self, now: DateTime<Utc>) -> Result<usize, DbError>;
+
+    /// List the lowercased hashtags of `feed` followed by the user
+    fn list_followed_tags(&self, user_id: i64, feed: &str) -> Result<Vec<String>, DbError>;
+    fn set_followed_tag(
+        &self,
+        user_id: i64,
+        feed: &str,
+        name: &str,
+        followed: bool,
+    ) -> Result<(), DbError>;
+    fn list_muted_accounts(&self, user_id: i64) -> Result<Vec<String>, DbError>;
+    fn set_muted_account(&self, user_id: i64, acct: &str, muted: bool) -> Result<(), DbError>;
+    fn list_hidden_statuses(&self, user_id: i64) -> Result<Vec<String>, DbError>;
+    fn set_hidden_status(&self, user_id: i64, status_id: &str, hidden: bool)
+    -> Result<(), DbError>;
+    /// List the saved statuses, most recently saved first
+    fn list_saved_statuses(&self, user_id: i64) -> Result<Vec<String>, DbError>;
+    fn set_saved_status(&self, user_id: i64, status_id: &str, saved: bool) -> Result<(), DbError>;
+
+    /// Retrieve the ID on `instance` of the status identified by `uri`
+    fn get_resolved_status(&self, instance: &str, uri: &str) -> Result<Option<String>, DbError>;
+    fn set_resolved_status(
+        &self,
+        instance: &str,
+        uri: &str,
+        status_id: &str,
+    ) -> Result<(), DbError>;
+}
@@ -0,0 +1,37 @@
+use crate::domain::models::archive::{ExportReport, ImportReport};
+use crate::domain::services::status::StatusServiceError;
+use async_trait::async_trait;
+use std::io;
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ArchiveError {
+    #[error("Unable to read or write the archive: {0}")]
+    FileError(#[from] io::Error),
+    #[error("Invalid record on line {line}: {source}")]
+    InvalidRecord {
+        line: usize,
+        source: serde_json::Error,
+    },
+    #[error("The archive does not start with a media-timeline header")]
+    InvalidHeader,
+    #[error("The archive version {0} is newer than the supported one")]
+    UnsupportedVersion(u32),
+
+    #[error(transparent)]
+    StatusError(#[from] StatusServiceError),
+    #[error(transparent)]
+    DbError(#[from] crate::infrastructure::error::DbError),
+}
+
+#[async_trait]
+pub trait ArchiveService: 'static + Sync + Send {
+    /// Write the hashtags, cursors and statuses with their engagements to a
+    /// JSON Lines archive
+    async fn export(&self, path: &Path) -> Result<ExportReport, ArchiveError>;
+
+    /// Merge an archive into the current data. Importing the same archive
+    /// again has no effect.
+    async fn import(&self, path: &Path) -> Result<ImportReport, ArchiveError>;
+}
@@ -1,8 +1,29 @@
+use crate::domain::models::hashtag::HashtagAttributes;
 use async_trait::async_trait;
 use std::error::Error;
 
 #[async_trait]
 pub trait SubscribedHashtagService: 'static + Sync + Send {
-    fn list_hashtags(&self) -> Result<Vec<String>, Box<dyn Error>>;
-    async fn suggest_hashtag(&self, key: &str) -> Result<(), Box<dyn Error>>;
+    /// List the approved hashtags of the feed, one per alias group
+    async fn list_hashtags(&self, feed: &str) -> Result<Vec<String>, Box<dyn Error>>;
+    /// List the approved hashtags and their aliases, all of which belong in
+    /// the timeline of the feed
+    async fn list_tracked_hashtags(&self, feed: &str) -> Result<Vec<String>, Box<dyn Error>>;
+    /// List the hashtags to poll, deduplicated across all the feeds
+    async fn list_polled_hashtags(&self) -> Result<Vec<String>, Box<dyn Error>>;
+    async fn suggest_hashtag(&self, feed: &str, key: &str) -> Result<(), Box<dyn Error>>;
+    /// List the suggested hashtags waiting for approval
+    async fn list_suggestions(
+        &self,
+        feed: &str,
+    ) -> Result<Vec<(String, HashtagAttributes)>, Box<dyn Error>>;
+    /// Approve a hashtag, subscribing to it if it was never suggested
+    async fn approve_hashtag(&self, feed: &str, key: &str) -> Result<(), Box<dyn Error>>;
+    /// Reject a suggested hashtag, or unsubscribe from an approved one
+    async fn reject_hashtag(&self, feed: &str, key: &str) -> Result<(), Box<dyn Error>>;
+    /// List the `(alias, canonical)` pairs of the alias groups
+    async fn list_aliases(&self) -> Result<Vec<(String, String)>, Box<dyn Error>>;
+    /// Present `alias` as `canonical`, case-insensitively
+    async fn add_alias(&self, alias: &str, canonical: &str) -> Result<(), Box<dyn Error>>;
+    async fn remove_alias(&self, alias: &str) -> Result<(), Box<dyn Error>>;
 }
@@ -0,0 +1,11 @@
+use crate::domain::models::health::{Readiness, WorkerState};
+use async_trait::async_trait;
+
+#[async_trait]
+pub trait HealthService: 'static + Sync + Send {
+    /// Check that the database is reachable and migrated, that the data
+    /// directory is writable and that no worker was stopped
+    async fn readiness(&self) -> Readiness;
+
+    fn workers(&self) -> Vec<WorkerState>;
+}
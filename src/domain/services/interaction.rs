@@ -0,0 +1,80 @@
+use crate::domain::models::interaction::{Interaction, InteractionState};
+use crate::domain::models::user::User;
+use crate::domain::services::status::StatusServiceError;
+use actix_web::ResponseError;
+use actix_web::http::StatusCode;
+use async_trait::async_trait;
+use megalodon::error::Error;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum InteractionError {
+    #[error("Invalid status ID")]
+    InvalidStatus,
+    #[error("This post is no longer available")]
+    NotFound,
+    #[error("Your instance could not find this post")]
+    NotResolved,
+    #[error("Your instance refused the action, please sign in again")]
+    Unauthorized,
+
+    #[error("Unable to communicate with your instance")]
+    InstanceError(Box<Error>),
+
+    #[error(transparent)]
+    StatusError(#[from] StatusServiceError),
+    #[error(transparent)]
+    DbError(#[from] crate::infrastructure::error::DbError),
+}
+
+impl From<Box<Error>> for InteractionError {
+    fn from(error: Box<Error>) -> Self {
+        match error.as_ref() {
+            // Tokens issued before the write scopes were requested end up here
+            Error::OwnError(own) if matches!(own.status, Some(401 | 403)) => {
+                InteractionError::Unauthorized
+            }
+            _ => InteractionError::InstanceError(error),
+        }
+    }
+}
+
+impl ResponseError for InteractionError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            InteractionError::InvalidStatus => StatusCode::BAD_REQUEST,
+            InteractionError::NotFound | InteractionError::NotResolved => StatusCode::NOT_FOUND,
+            InteractionError::Unauthorized => StatusCode::UNAUTHORIZED,
+            InteractionError::InstanceError(_) => StatusCode::BAD_GATEWAY,
+            InteractionError::StatusError(_) | InteractionError::DbError(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
+
+/// Favourites, boosts and replies performed through the home instance of the
+/// users, on which the indexed statuses are first resolved by their URI
+#[async_trait]
+pub trait InteractionService: 'static + Sync + Send {
+    /// Perform or undo `interaction` on the indexed status `id`
+    async fn interact(
+        &self,
+        user: &User,
+        id: &str,
+        interaction: Interaction,
+        active: bool,
+    ) -> Result<InteractionState, InteractionError>;
+
+    /// Full address of the author of the indexed status `id`, to mention
+    /// them in replies
+    async fn author(&self, id: &str) -> Result<String, InteractionError>;
+
+    /// Reply to the indexed status `id`, returning the URL of the reply
+    async fn reply(
+        &self,
+        user: &User,
+        id: &str,
+        text: &str,
+    ) -> Result<Option<String>, InteractionError>;
+}
@@ -1,2 +1,6 @@
+pub mod archive;
 pub mod hashtag;
+pub mod health;
+pub mod interaction;
 pub mod status;
+pub mod user;
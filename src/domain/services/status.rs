@@ -1,10 +1,17 @@
 use async_trait::async_trait;
 
+use crate::domain::models::feed::MediaFilter;
+use crate::domain::models::hashtag::{RelatedTags, RisingTag};
+use crate::domain::models::repair::RepairReport;
+use crate::domain::models::status::{
+    EngagementSnapshot, IndexConsistency, IndexStats, PersistReport, RecoveryReport, ReindexReport,
+};
 use actix_web::ResponseError;
 use chrono::{DateTime, Utc};
-use megalodon::entities::Status;
+use megalodon::entities::{Context, Status};
 use std::collections::HashMap;
 use std::io;
+use std::time::Duration;
 use thiserror::Error;
 use tokio::task::JoinError;
 
@@ -14,7 +21,7 @@ pub enum StatusServiceError {
     FileError(#[from] io::Error),
 
     #[error("Unable to retrieve statuses from Mastodon API")]
-    CantRetrieveStatuses(#[from] megalodon::error::Error),
+    CantRetrieveStatuses(#[from] Box<megalodon::error::Error>),
     #[error("Unable to update the recent status ID locally")]
     CantUpdateStatuses,
 
@@ -33,19 +40,37 @@ pub trait StatusService: 'static + Sync + Send {
 
     async fn fetch_statuses(&self, ids: &[String]) -> Result<Vec<Status>, StatusServiceError>;
 
-    /// Persist statuses to avoid hitting the public API constantly
-    async fn persist_statuses(&self, statuses: &Vec<Status>) -> Result<(), StatusServiceError>;
+    /// Ancestors and descendants of a status, fetched from Mastodon and cached
+    /// for a while
+    async fn thread_context(&self, id: &str) -> Result<Context, StatusServiceError>;
+
+    /// Forget the most recent status ID of the hashtag, so that the next
+    /// pagination starts again from the latest page
+    async fn reset_cursor(&self, hashtag: &str) -> Result<bool, StatusServiceError>;
+
+    /// Persist statuses to avoid hitting the public API constantly. The files
+    /// are written first, then the statuses written are indexed together.
+    async fn persist_statuses(
+        &self,
+        statuses: &[Status],
+    ) -> Result<PersistReport, StatusServiceError>;
+
+    /// Retrieve the statuses with the given IDs from the storage, in order,
+    /// skipping the ones which are not stored
+    async fn load_statuses(&self, ids: Vec<String>) -> Result<Vec<Status>, StatusServiceError>;
 
     /// Retrieve all statuses for the specified hashtags
     async fn retrieve_statuses(
         &self,
         hashtags: Option<&Vec<String>>,
+        media: MediaFilter,
         limit: u16,
     ) -> Result<Vec<Status>, StatusServiceError>;
 
     async fn popular_statuses(
         &self,
         hashtags: Option<&Vec<String>>,
+        media: MediaFilter,
         since: DateTime<Utc>,
         limit: u16,
     ) -> Result<Vec<Status>, StatusServiceError>;
@@ -59,9 +84,100 @@ pub trait StatusService: 'static + Sync + Send {
     ) -> Result<Vec<String>, StatusServiceError>;
 
     /// Retrieve the list of popular tags from the indexed statuses
-    fn popular_tags(
+    async fn popular_tags(
         &self,
+        hashtags: &[String],
         periods: Vec<u16>,
         limit: u16,
     ) -> Result<HashMap<u16, Vec<(String, u32)>>, StatusServiceError>;
+
+    /// Rename the hashtags with their most common casing in the indexed statuses
+    async fn display_names(&self, hashtags: Vec<String>)
+    -> Result<Vec<String>, StatusServiceError>;
+
+    /// Retrieve the tags used at least `min_ratio` times more in the last
+    /// `window` than on average over the preceding `baseline`
+    async fn rising_tags(
+        &self,
+        hashtags: &[String],
+        window: Duration,
+        baseline: Duration,
+        min_count: u32,
+        min_ratio: f64,
+        limit: u16,
+    ) -> Result<Vec<RisingTag>, StatusServiceError>;
+
+    /// Retrieve the tags outside of `hashtags` most often used with them during
+    /// the last `window`, ranked by lift, along with the tags most often used
+    /// with each of the `hashtags`
+    async fn related_tags(
+        &self,
+        hashtags: &[String],
+        window: Duration,
+        min_count: u32,
+        limit: u16,
+    ) -> Result<RelatedTags, StatusServiceError>;
+
+    /// Retrieve statuses ranked by their time-decayed weighted engagements
+    async fn hot_statuses(
+        &self,
+        hashtags: Option<&Vec<String>>,
+        media: MediaFilter,
+        limit: u16,
+    ) -> Result<Vec<Status>, StatusServiceError>;
+
+    /// Retrieve the statuses gaining engagements the fastest since `since`
+    async fn fastest_growing_statuses(
+        &self,
+        hashtags: Option<&Vec<String>>,
+        media: MediaFilter,
+        since: DateTime<Utc>,
+        limit: u16,
+    ) -> Result<Vec<Status>, StatusServiceError>;
+
+    /// Retrieve the engagement snapshots recorded for a status, oldest first
+    async fn engagement_history(
+        &self,
+        id: &str,
+    ) -> Result<Vec<EngagementSnapshot>, StatusServiceError>;
+
+    /// Downsample the engagement snapshots of statuses created before `created_before`
+    async fn compact_engagements(
+        &self,
+        created_before: DateTime<Utc>,
+        resolution: Duration,
+    ) -> Result<usize, StatusServiceError>;
+
+    /// Remove a status from the storage and the index, returning whether it
+    /// was stored
+    async fn delete_status(&self, id: &str) -> Result<bool, StatusServiceError>;
+
+    /// Previous versions of an edited status, oldest first
+    async fn status_history(&self, id: &str) -> Result<Vec<Status>, StatusServiceError>;
+
+    async fn index_stats(&self) -> Result<IndexStats, StatusServiceError>;
+
+    /// Store statuses refreshed elsewhere, without recording engagement
+    /// snapshots, returning how many were stored
+    async fn restore_statuses(
+        &self,
+        statuses: Vec<(Status, DateTime<Utc>)>,
+    ) -> Result<usize, StatusServiceError>;
+
+    /// Index again every stored status, `batch_size` statuses per transaction
+    async fn reindex(&self, batch_size: usize) -> Result<ReindexReport, StatusServiceError>;
+
+    /// Compare the indexed statuses with the stored ones
+    async fn verify_index(&self) -> Result<IndexConsistency, StatusServiceError>;
+
+    /// Remove the files of interrupted writes, index the stored statuses which
+    /// are not, and queue for a repair the indexed ones which are not stored
+    async fn recover(&self) -> Result<RecoveryReport, StatusServiceError>;
+
+    /// Number of statuses which could not be read, waiting for a repair
+    fn pending_repairs(&self) -> usize;
+
+    /// Fetch again up to `limit` of the statuses which could not be read,
+    /// rewriting their file, and remove the ones deleted from Mastodon
+    async fn repair_statuses(&self, limit: usize) -> Result<RepairReport, StatusServiceError>;
 }
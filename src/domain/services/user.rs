@@ -0,0 +1,106 @@
+use crate::domain::models::user::{User, UserPreferences};
+use actix_web::ResponseError;
+use actix_web::http::StatusCode;
+use async_trait::async_trait;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum UserServiceError {
+    #[error("Invalid instance address")]
+    InvalidInstance,
+    #[error("The sign-in expired, please try again")]
+    InvalidLogin,
+    #[error("Invalid status ID")]
+    InvalidStatus,
+    #[error("Sign-in is not enabled")]
+    SignInDisabled,
+    #[error("Sign-in is not available for new instances at the moment")]
+    RegistrationClosed,
+
+    #[error("Unable to communicate with the instance")]
+    InstanceError(Box<megalodon::error::Error>),
+
+    #[error(transparent)]
+    DbError(#[from] crate::infrastructure::error::DbError),
+}
+
+impl From<Box<megalodon::error::Error>> for UserServiceError {
+    fn from(error: Box<megalodon::error::Error>) -> Self {
+        UserServiceError::InstanceError(error)
+    }
+}
+
+impl ResponseError for UserServiceError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            UserServiceError::InvalidInstance
+            | UserServiceError::InvalidLogin
+            | UserServiceError::InvalidStatus => StatusCode::BAD_REQUEST,
+            UserServiceError::SignInDisabled => StatusCode::NOT_FOUND,
+            UserServiceError::RegistrationClosed => StatusCode::SERVICE_UNAVAILABLE,
+            UserServiceError::InstanceError(_) => StatusCode::BAD_GATEWAY,
+            UserServiceError::DbError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Session created at the end of a sign-in
+pub struct Login {
+    pub session_id: String,
+    pub return_to: String,
+}
+
+#[async_trait]
+pub trait UserService: 'static + Sync + Send {
+    /// Register the application on the instance if needed, and return the
+    /// URL of its authorization page
+    async fn start_login(
+        &self,
+        instance: &str,
+        return_to: &str,
+    ) -> Result<String, UserServiceError>;
+
+    /// Exchange the authorization code for an access token, and open a session
+    async fn complete_login(&self, state: &str, code: &str) -> Result<Login, UserServiceError>;
+
+    /// Base URL of the instance typed by a visitor, such as `mastodon.social`
+    fn home_instance(&self, instance: &str) -> Result<String, UserServiceError>;
+
+    async fn current_user(&self, session_id: &str) -> Result<Option<User>, UserServiceError>;
+
+    async fn logout(&self, session_id: &str) -> Result<(), UserServiceError>;
+
+    async fn preferences(
+        &self,
+        user: &User,
+        feed: &str,
+    ) -> Result<UserPreferences, UserServiceError>;
+
+    async fn follow_tag(
+        &self,
+        user: &User,
+        feed: &str,
+        name: &str,
+        followed: bool,
+    ) -> Result<(), UserServiceError>;
+
+    async fn mute_account(
+        &self,
+        user: &User,
+        acct: &str,
+        muted: bool,
+    ) -> Result<(), UserServiceError>;
+
+    async fn hide_status(
+        &self,
+        user: &User,
+        id: &str,
+        hidden: bool,
+    ) -> Result<(), UserServiceError>;
+
+    async fn save_status(&self, user: &User, id: &str, saved: bool)
+    -> Result<(), UserServiceError>;
+
+    /// List the IDs of the saved statuses, most recently saved first
+    async fn saved_statuses(&self, user: &User) -> Result<Vec<String>, UserServiceError>;
+}
@@ -1 +1,14 @@
+pub mod postgres;
 pub mod sqlite;
+
+/// Run a blocking database call on the blocking thread pool of the runtime,
+/// keeping the worker threads free to serve other requests meanwhile
+pub async fn unblock<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> T {
+    match tokio::task::spawn_blocking(f).await {
+        Ok(result) => result,
+        Err(e) => match e.try_into_panic() {
+            Ok(payload) => std::panic::resume_unwind(payload),
+            Err(e) => panic!("The database call did not complete: {}", e),
+        },
+    }
+}
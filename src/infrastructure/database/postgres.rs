@@ -0,0 +1,158 @@
+use crate::infrastructure::error::DbError;
+use postgres::{Config, NoTls};
+use r2d2::Pool;
+use r2d2_postgres::PostgresConnectionManager;
+use std::error::Error;
+use std::ops::Deref;
+
+mod embedded {
+    use refinery::embed_migrations;
+    embed_migrations!("migrations/postgres");
+}
+
+/// Pool of connections, closed out of the async runtime, see `blocking`
+pub struct Connection(Option<Pool<PostgresConnectionManager<NoTls>>>);
+
+impl Deref for Connection {
+    type Target = Pool<PostgresConnectionManager<NoTls>>;
+
+    fn deref(&self) -> &Self::Target {
+        self.0
+            .as_ref()
+            .expect("the pool is only taken when dropped")
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        if let Some(pool) = self.0.take() {
+            blocking(move || drop(pool));
+        }
+    }
+}
+
+/// Whether the URL designates a PostgreSQL database
+pub fn is_postgres_url(url: &str) -> bool {
+    url.starts_with("postgres://") || url.starts_with("postgresql://")
+}
+
+/// Run `f` where the synchronous client can drive its own runtime, which it
+/// cannot do from a thread of the async runtime. Only meant for the setup and
+/// teardown of a pool, the queries going through `with_connection`.
+pub fn blocking<T: Send>(f: impl FnOnce() -> T + Send) -> T {
+    if tokio::runtime::Handle::try_current().is_err() {
+        return f();
+    }
+    std::thread::scope(|scope| {
+        scope
+            .spawn(f)
+            .join()
+            .unwrap_or_else(|e| std::panic::resume_unwind(e))
+    })
+}
+
+/// Run `f` with a connection of the pool. To be called outside of the async
+/// runtime, like the repositories are through `unblock`.
+pub fn with_connection<T>(
+    pool: &Connection,
+    f: impl FnOnce(&mut postgres::Client) -> Result<T, DbError>,
+) -> Result<T, DbError> {
+    f(&mut *pool.get()?)
+}
+
+/// Apply the pending migrations, returning their names
+pub fn migrate(pool: &Connection) -> Result<Vec<String>, DbError> {
+    blocking(|| {
+        with_connection(pool, |conn| {
+            let report = embedded::migrations::runner().run(conn)?;
+            Ok(report
+                .applied_migrations()
+                .iter()
+                .map(|migration| migration.to_string())
+                .collect())
+        })
+    })
+}
+
+/// Names of the embedded migrations which are not applied to the database
+pub fn pending_migrations(pool: &Connection) -> Result<Vec<String>, DbError> {
+    with_connection(pool, |conn| {
+        let runner = embedded::migrations::runner();
+        let applied = runner.get_applied_migrations(conn)?;
+        Ok(runner
+            .get_migrations()
+            .iter()
+            .filter(|migration| {
+                !applied
+                    .iter()
+                    .any(|other| other.version() == migration.version())
+            })
+            .map(|migration| migration.to_string())
+            .collect())
+    })
+}
+
+pub fn new(url: &str) -> Result<Connection, Box<dyn Error>> {
+    let pool = open(url)?;
+    migrate(&pool)?;
+    Ok(pool)
+}
+
+/// Pool of connections to the database, without applying the migrations
+pub fn open(url: &str) -> Result<Connection, Box<dyn Error>> {
+    let config: Config = url.parse()?;
+    let manager = PostgresConnectionManager::new(config, NoTls);
+    Ok(Connection(Some(blocking(|| Pool::new(manager))?)))
+}
+
+/// Pool of connections to a migrated schema of its own in the database of
+/// `TEST_DATABASE_URL`, dropped along with the pool. `None` when the variable
+/// is not set, the tests using it then being skipped.
+#[cfg(test)]
+pub fn test_database() -> Option<TestDatabase> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    static SCHEMAS: AtomicUsize = AtomicUsize::new(0);
+
+    let Ok(url) = std::env::var("TEST_DATABASE_URL") else {
+        eprintln!("TEST_DATABASE_URL is not set, skipping");
+        return None;
+    };
+    let schema = format!(
+        "test_{}_{}",
+        std::process::id(),
+        SCHEMAS.fetch_add(1, Ordering::Relaxed)
+    );
+    let mut config: Config = url.parse().unwrap();
+    let mut client = config.connect(NoTls).unwrap();
+    client
+        .batch_execute(&format!("CREATE SCHEMA {}", schema))
+        .unwrap();
+    config.options(&format!("-c search_path={}", schema));
+    let pool = Pool::builder()
+        .max_size(2)
+        .build(PostgresConnectionManager::new(config, NoTls))
+        .unwrap();
+    let pool = std::sync::Arc::new(Connection(Some(pool)));
+    migrate(&pool).unwrap();
+    Some(TestDatabase {
+        pool,
+        client,
+        schema,
+    })
+}
+
+#[cfg(test)]
+pub struct TestDatabase {
+    pub pool: std::sync::Arc<Connection>,
+    client: postgres::Client,
+    schema: String,
+}
+
+#[cfg(test)]
+impl Drop for TestDatabase {
+    fn drop(&mut self) {
+        let _ = self
+            .client
+            .batch_execute(&format!("DROP SCHEMA {} CASCADE", self.schema));
+    }
+}
@@ -1,23 +1,50 @@
+use crate::infrastructure::error::DbError;
 use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
 use std::error::Error;
 use std::ops::DerefMut;
+use std::path::Path;
 
 mod embedded {
     use refinery::embed_migrations;
-    embed_migrations!("migrations");
+    embed_migrations!("migrations/sqlite");
 }
 
 pub type Connection = Pool<SqliteConnectionManager>;
 
 fn create_sqlite_tables(pool: &Connection) -> Result<(), Box<dyn Error>> {
-    let mut conn = pool.get()?;
-    embedded::migrations::runner()
-        .run(conn.deref_mut())
-        .unwrap();
+    migrate(pool)?;
     Ok(())
 }
 
+/// Apply the pending migrations, returning their names
+pub fn migrate(pool: &Connection) -> Result<Vec<String>, DbError> {
+    let mut conn = pool.get()?;
+    let report = embedded::migrations::runner().run(conn.deref_mut())?;
+    Ok(report
+        .applied_migrations()
+        .iter()
+        .map(|migration| migration.to_string())
+        .collect())
+}
+
+/// Names of the embedded migrations which are not applied to the database
+pub fn pending_migrations(pool: &Connection) -> Result<Vec<String>, DbError> {
+    let mut conn = pool.get()?;
+    let runner = embedded::migrations::runner();
+    let applied = runner.get_applied_migrations(conn.deref_mut())?;
+    Ok(runner
+        .get_migrations()
+        .iter()
+        .filter(|migration| {
+            !applied
+                .iter()
+                .any(|other| other.version() == migration.version())
+        })
+        .map(|migration| migration.to_string())
+        .collect())
+}
+
 /// Apply the pragmas needed for concurrent access. WAL lets readers run
 /// alongside the single writer, and `busy_timeout` makes a connection wait for
 /// a lock instead of returning `SQLITE_BUSY` ("database is locked"). Applied via
@@ -29,10 +56,24 @@ fn configure_connection(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
     Ok(())
 }
 
-pub fn new() -> Result<Connection, Box<dyn Error>> {
-    let manager = SqliteConnectionManager::file("data/db.sqlite3")
-        .with_init(|conn| configure_connection(conn));
-    let pool = Pool::new(manager).expect("unable to create db pool");
+pub fn new(path: &Path) -> Result<Connection, Box<dyn Error>> {
+    let pool = open(path)?;
+    create_sqlite_tables(&pool)?;
+    Ok(pool)
+}
+
+/// Pool of connections to the database, without applying the migrations
+pub fn open(path: &Path) -> Result<Connection, Box<dyn Error>> {
+    let manager = SqliteConnectionManager::file(path).with_init(|conn| configure_connection(conn));
+    Ok(Pool::new(manager)?)
+}
+
+/// Pool of a single connection to a migrated in-memory database
+#[cfg(test)]
+pub fn in_memory() -> Result<Connection, Box<dyn Error>> {
+    let pool = Pool::builder()
+        .max_size(1)
+        .build(SqliteConnectionManager::memory())?;
 
     create_sqlite_tables(&pool)?;
     Ok(pool)
@@ -6,4 +6,10 @@ pub enum DbError {
     PoolError(#[from] r2d2::Error),
     #[error(transparent)]
     SqlError(#[from] rusqlite::Error),
+    #[error(transparent)]
+    PostgresError(#[from] postgres::Error),
+    #[error(transparent)]
+    MigrationError(#[from] refinery::Error),
+    #[error("The index writer has stopped")]
+    WriterStopped,
 }
@@ -0,0 +1,231 @@
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+use std::time::Duration;
+
+const NAMESPACE: &str = "media_timeline";
+
+/// Prometheus metrics of the application, exposed on `/metrics`
+pub struct Metrics {
+    registry: Registry,
+    statuses_fetched: IntCounterVec,
+    statuses_persisted: IntCounterVec,
+    statuses_refreshed: IntCounter,
+    mastodon_requests: IntCounterVec,
+    mastodon_request_duration: HistogramVec,
+    mastodon_rate_limit_remaining: IntGauge,
+    status_refresh_backlog: IntGaugeVec,
+    http_request_duration: HistogramVec,
+    cached_status_read_failures: IntCounter,
+    status_repairs_pending: IntGauge,
+    status_repairs: IntCounterVec,
+}
+
+impl Metrics {
+    pub fn new() -> Result<Self, prometheus::Error> {
+        let registry = Registry::new();
+        let opts = |name: &str, help: &str| Opts::new(name, help).namespace(NAMESPACE);
+        let histogram_opts =
+            |name: &str, help: &str| HistogramOpts::new(name, help).namespace(NAMESPACE);
+
+        let metrics = Self {
+            statuses_fetched: IntCounterVec::new(
+                opts(
+                    "statuses_fetched_total",
+                    "Statuses retrieved from the tag timelines",
+                ),
+                &["tag"],
+            )?,
+            statuses_persisted: IntCounterVec::new(
+                opts(
+                    "statuses_persisted_total",
+                    "Statuses from the tag timelines written to the storage",
+                ),
+                &["tag"],
+            )?,
+            statuses_refreshed: IntCounter::with_opts(opts(
+                "statuses_refreshed_total",
+                "Stored statuses refreshed from the API",
+            ))?,
+            mastodon_requests: IntCounterVec::new(
+                opts("mastodon_requests_total", "Calls to the Mastodon API"),
+                &["endpoint", "status"],
+            )?,
+            mastodon_request_duration: HistogramVec::new(
+                histogram_opts(
+                    "mastodon_request_duration_seconds",
+                    "Duration of the calls to the Mastodon API",
+                ),
+                &["endpoint"],
+            )?,
+            mastodon_rate_limit_remaining: IntGauge::with_opts(opts(
+                "mastodon_rate_limit_remaining",
+                "Calls left before hitting the rate limit of the Mastodon API",
+            ))?,
+            status_refresh_backlog: IntGaugeVec::new(
+                opts(
+                    "status_refresh_backlog",
+                    "Stale statuses found by the last refresh, up to the size of a run",
+                ),
+                &["tier"],
+            )?,
+            http_request_duration: HistogramVec::new(
+                histogram_opts(
+                    "http_request_duration_seconds",
+                    "Duration of the HTTP requests",
+                ),
+                &["method", "route", "status"],
+            )?,
+            cached_status_read_failures: IntCounter::with_opts(opts(
+                "cached_status_read_failures_total",
+                "Stored statuses whose file could not be read or parsed",
+            ))?,
+            status_repairs_pending: IntGauge::with_opts(opts(
+                "status_repairs_pending",
+                "Stored statuses waiting to be fetched again",
+            ))?,
+            status_repairs: IntCounterVec::new(
+                opts(
+                    "status_repairs_total",
+                    "Stored statuses fetched again, by outcome",
+                ),
+                &["outcome"],
+            )?,
+            registry,
+        };
+
+        metrics
+            .registry
+            .register(Box::new(metrics.statuses_fetched.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.statuses_persisted.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.statuses_refreshed.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.mastodon_requests.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.mastodon_request_duration.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.mastodon_rate_limit_remaining.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.status_refresh_backlog.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.http_request_duration.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.cached_status_read_failures.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.status_repairs_pending.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.status_repairs.clone()))?;
+        Ok(metrics)
+    }
+
+    /// Render the metrics in the Prometheus text format
+    pub fn encode(&self) -> Result<String, prometheus::Error> {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        String::from_utf8(buffer).map_err(|e| prometheus::Error::Msg(e.to_string()))
+    }
+
+    pub fn statuses_fetched(&self, tag: &str, count: usize) {
+        self.statuses_fetched
+            .with_label_values(&[tag.to_lowercase()])
+            .inc_by(count as u64);
+    }
+
+    pub fn statuses_persisted(&self, tag: &str, count: usize) {
+        self.statuses_persisted
+            .with_label_values(&[tag.to_lowercase()])
+            .inc_by(count as u64);
+    }
+
+    pub fn statuses_refreshed(&self, count: usize) {
+        self.statuses_refreshed.inc_by(count as u64);
+    }
+
+    /// Record a call to the Mastodon API, `status` being `None` when no
+    /// response was received
+    pub fn mastodon_request(
+        &self,
+        endpoint: &str,
+        status: Option<u16>,
+        rate_limit_remaining: Option<i64>,
+        elapsed: Duration,
+    ) {
+        let status = status.map_or_else(|| "error".to_string(), |s| s.to_string());
+        self.mastodon_requests
+            .with_label_values(&[endpoint, status.as_str()])
+            .inc();
+        self.mastodon_request_duration
+            .with_label_values(&[endpoint])
+            .observe(elapsed.as_secs_f64());
+        if let Some(remaining) = rate_limit_remaining {
+            self.mastodon_rate_limit_remaining.set(remaining);
+        }
+    }
+
+    pub fn status_refresh_backlog(&self, tier: &str, size: usize) {
+        self.status_refresh_backlog
+            .with_label_values(&[tier])
+            .set(size as i64);
+    }
+
+    pub fn http_request(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        self.http_request_duration
+            .with_label_values(&[method, route, status.to_string().as_str()])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn cached_status_read_failure(&self) {
+        self.cached_status_read_failures.inc();
+    }
+
+    pub fn status_repairs_pending(&self, pending: usize) {
+        self.status_repairs_pending.set(pending as i64);
+    }
+
+    pub fn status_repairs(&self, outcome: &str, count: usize) {
+        self.status_repairs
+            .with_label_values(&[outcome])
+            .inc_by(count as u64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_renders_the_recorded_metrics() {
+        let metrics = Metrics::new().unwrap();
+        metrics.statuses_fetched("Art", 3);
+        metrics.mastodon_request(
+            "tag_timeline",
+            Some(200),
+            Some(299),
+            Duration::from_millis(5),
+        );
+        metrics.mastodon_request("status", None, None, Duration::from_millis(5));
+
+        let text = metrics.encode().unwrap();
+        assert!(text.contains(r#"media_timeline_statuses_fetched_total{tag="art"} 3"#));
+        assert!(text.contains(
+            r#"media_timeline_mastodon_requests_total{endpoint="tag_timeline",status="200"} 1"#
+        ));
+        assert!(text.contains(
+            r#"media_timeline_mastodon_requests_total{endpoint="status",status="error"} 1"#
+        ));
+        assert!(text.contains("media_timeline_mastodon_rate_limit_remaining 299"));
+    }
+}
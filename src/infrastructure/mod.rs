@@ -1,4 +1,5 @@
 pub mod database;
 pub mod error;
+pub mod metrics;
 pub mod repositories;
 pub mod services;
@@ -0,0 +1,225 @@
+use crate::domain::models::archive::ArchiveRecord;
+use crate::domain::models::status::EngagementSnapshot;
+use crate::domain::repositories::archive::ArchiveRepository;
+use crate::infrastructure::database::sqlite;
+use crate::infrastructure::error::DbError;
+use chrono::{DateTime, Utc};
+use rusqlite::params;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Format of `DATETIME('now')`, the default of `subscribed_hashtags.created_at`
+const SQLITE_DATETIME: &str = "%Y-%m-%d %H:%M:%S";
+
+pub struct ArchiveSqliteRepository {
+    pool: Arc<sqlite::Connection>,
+}
+
+impl ArchiveSqliteRepository {
+    pub fn new(pool: Arc<sqlite::Connection>) -> Self {
+        Self { pool }
+    }
+}
+
+impl ArchiveRepository for ArchiveSqliteRepository {
+    fn list_hashtags(&self) -> Result<Vec<ArchiveRecord>, DbError> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT feed, name, approved, votes, created_at FROM subscribed_hashtags ORDER BY feed, name",
+        )?;
+        let results: rusqlite::Result<Vec<ArchiveRecord>> = stmt
+            .query_map([], |row| {
+                Ok(ArchiveRecord::Hashtag {
+                    feed: row.get(0)?,
+                    name: row.get(1)?,
+                    approved: row.get(2)?,
+                    votes: row.get(3)?,
+                    created_at: row.get(4)?,
+                })
+            })?
+            .collect();
+        Ok(results?)
+    }
+
+    fn list_aliases(&self) -> Result<Vec<ArchiveRecord>, DbError> {
+        let conn = self.pool.get()?;
+        let mut stmt =
+            conn.prepare_cached("SELECT alias, canonical FROM hashtag_aliases ORDER BY alias")?;
+        let results: rusqlite::Result<Vec<ArchiveRecord>> = stmt
+            .query_map([], |row| {
+                Ok(ArchiveRecord::Alias {
+                    alias: row.get(0)?,
+                    canonical: row.get(1)?,
+                })
+            })?
+            .collect();
+        Ok(results?)
+    }
+
+    fn list_cursors(&self) -> Result<Vec<ArchiveRecord>, DbError> {
+        let conn = self.pool.get()?;
+        let mut stmt =
+            conn.prepare_cached("SELECT tag, status_id FROM recent_statuses ORDER BY tag")?;
+        let results: rusqlite::Result<Vec<ArchiveRecord>> = stmt
+            .query_map([], |row| {
+                Ok(ArchiveRecord::Cursor {
+                    tag: row.get(0)?,
+                    status_id: row.get(1)?,
+                })
+            })?
+            .collect();
+        Ok(results?)
+    }
+
+    fn list_refreshes(&self) -> Result<HashMap<String, DateTime<Utc>>, DbError> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare_cached("SELECT id, refreshed_at FROM status_refreshes")?;
+        let results: rusqlite::Result<HashMap<String, DateTime<Utc>>> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect();
+        Ok(results?)
+    }
+
+    fn merge_records(&self, records: &[ArchiveRecord]) -> Result<(), DbError> {
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+        {
+            // The approval and the votes are only ever raised, and a hashtag
+            // keeps its earliest suggestion date
+            let mut hashtag_stmt = tx.prepare_cached(
+                "INSERT INTO subscribed_hashtags (feed, name, approved, votes, created_at)
+                VALUES (?1, ?2, ?3, ?4, ?5)
+                ON CONFLICT (feed, lower(name)) DO UPDATE SET
+                    approved = max(approved, excluded.approved),
+                    votes = max(votes, excluded.votes),
+                    created_at = min(created_at, excluded.created_at)",
+            )?;
+            // The local aliases take precedence
+            let mut alias_stmt = tx.prepare_cached(
+                "INSERT INTO hashtag_aliases (alias, canonical) VALUES (lower(?1), lower(?2))
+                ON CONFLICT (alias) DO NOTHING",
+            )?;
+            // Keep the most recent status ID
+            // https://docs.joinmastodon.org/api/guidelines/#id
+            let mut cursor_stmt = tx.prepare_cached(
+                "INSERT INTO recent_statuses (tag, status_id) VALUES (?1, ?2)
+                ON CONFLICT (tag) DO UPDATE SET status_id = CASE
+                    WHEN length(excluded.status_id) > length(status_id)
+                        OR (length(excluded.status_id) = length(status_id) AND excluded.status_id > status_id)
+                    THEN excluded.status_id
+                    ELSE status_id
+                END",
+            )?;
+            for record in records {
+                match record {
+                    ArchiveRecord::Hashtag {
+                        feed,
+                        name,
+                        approved,
+                        votes,
+                        created_at,
+                    } => {
+                        hashtag_stmt.execute(params![
+                            feed,
+                            name,
+                            approved,
+                            votes,
+                            created_at.format(SQLITE_DATETIME).to_string()
+                        ])?;
+                    }
+                    ArchiveRecord::Alias { alias, canonical } => {
+                        alias_stmt.execute(params![alias, canonical])?;
+                    }
+                    ArchiveRecord::Cursor { tag, status_id } => {
+                        cursor_stmt.execute(params![tag, status_id])?;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn merge_engagements(
+        &self,
+        status_id: &str,
+        snapshots: &[EngagementSnapshot],
+    ) -> Result<usize, DbError> {
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+        let mut inserted = 0;
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO status_engagements (status_id, recorded_at, replies_count, reblogs_count, favourites_count)
+                SELECT ?1, ?2, ?3, ?4, ?5
+                WHERE NOT EXISTS (SELECT 1 FROM status_engagements WHERE status_id = ?1 AND recorded_at = ?2)",
+            )?;
+            for snapshot in snapshots {
+                inserted += stmt.execute(params![
+                    status_id,
+                    snapshot.recorded_at,
+                    snapshot.replies_count,
+                    snapshot.reblogs_count,
+                    snapshot.favourites_count,
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(inserted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn merging_records_is_idempotent() {
+        let repository = ArchiveSqliteRepository::new(Arc::new(sqlite::in_memory().unwrap()));
+        let created_at = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        let hashtag = |approved: bool, votes: u32| ArchiveRecord::Hashtag {
+            feed: "default".to_string(),
+            name: "Minis".to_string(),
+            approved,
+            votes,
+            created_at,
+        };
+        let cursor = |status_id: &str| ArchiveRecord::Cursor {
+            tag: "minis".to_string(),
+            status_id: status_id.to_string(),
+        };
+        let records = vec![hashtag(true, 2), cursor("99")];
+        repository.merge_records(&records).unwrap();
+        repository.merge_records(&records).unwrap();
+        repository
+            .merge_records(&[hashtag(false, 1), cursor("100"), cursor("98")])
+            .unwrap();
+
+        let hashtags = repository.list_hashtags().unwrap();
+        assert_eq!(hashtags.len(), 1);
+        assert!(matches!(
+            &hashtags[0],
+            ArchiveRecord::Hashtag { approved: true, votes: 2, created_at: at, .. } if *at == created_at
+        ));
+        assert!(matches!(
+            &repository.list_cursors().unwrap()[..],
+            [ArchiveRecord::Cursor { status_id, .. }] if status_id == "100"
+        ));
+
+        let snapshot = EngagementSnapshot {
+            recorded_at: created_at,
+            replies_count: 1,
+            reblogs_count: 2,
+            favourites_count: 3,
+        };
+        assert_eq!(
+            repository
+                .merge_engagements("1", std::slice::from_ref(&snapshot))
+                .unwrap(),
+            1
+        );
+        assert_eq!(repository.merge_engagements("1", &[snapshot]).unwrap(), 0);
+    }
+}
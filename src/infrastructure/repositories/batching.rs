@@ -0,0 +1,228 @@
+use crate::domain::models::feed::MediaFilter;
+use crate::domain::models::hashtag::{RisingTag, TagCooccurrence};
+use crate::domain::models::status::{EngagementSnapshot, IndexStats, IndexedStatus};
+use crate::domain::repositories::status::StatusIndexRepository;
+use crate::infrastructure::error::DbError;
+use chrono::{DateTime, Utc};
+use megalodon::entities::Status;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Duration;
+
+/// Inserts written in a single transaction at most
+const MAX_BATCH_INSERTS: usize = 64;
+
+struct Insert {
+    statuses: Vec<Status>,
+    reply: Sender<Result<(), DbError>>,
+}
+
+/// Index whose inserts are written by a single thread, the inserts queued
+/// while it writes being coalesced into one transaction. Reads go straight
+/// to the inner repository and run in parallel.
+pub struct BatchingStatusIndexRepository {
+    inner: Arc<dyn StatusIndexRepository>,
+    sender: Sender<Insert>,
+}
+
+impl BatchingStatusIndexRepository {
+    pub fn new(inner: Arc<dyn StatusIndexRepository>) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let writer = inner.clone();
+        std::thread::Builder::new()
+            .name("index-writer".to_string())
+            .spawn(move || write_inserts(writer.as_ref(), receiver))
+            .expect("Unable to start the index writer");
+        Self { inner, sender }
+    }
+}
+
+/// Write the queued inserts until the repository is dropped
+fn write_inserts(repository: &dyn StatusIndexRepository, receiver: Receiver<Insert>) {
+    while let Ok(first) = receiver.recv() {
+        let mut batch = vec![first];
+        batch.extend(receiver.try_iter().take(MAX_BATCH_INSERTS - 1));
+        let statuses = batch.iter().flat_map(|insert| &insert.statuses).collect();
+        match repository.insert_statuses(statuses) {
+            Ok(()) => batch.into_iter().for_each(|insert| {
+                let _ = insert.reply.send(Ok(()));
+            }),
+            // One failing insert must not fail the others of the batch
+            Err(e) if batch.len() == 1 => {
+                let _ = batch[0].reply.send(Err(e));
+            }
+            Err(_) => batch.into_iter().for_each(|insert| {
+                let result = repository.insert_statuses(insert.statuses.iter().collect());
+                let _ = insert.reply.send(result);
+            }),
+        }
+    }
+}
+
+impl StatusIndexRepository for BatchingStatusIndexRepository {
+    fn insert_statuses(&self, statuses: Vec<&Status>) -> Result<(), DbError> {
+        let (reply, response) = mpsc::channel();
+        let insert = Insert {
+            statuses: statuses.into_iter().cloned().collect(),
+            reply,
+        };
+        self.sender
+            .send(insert)
+            .map_err(|_| DbError::WriterStopped)?;
+        response.recv().map_err(|_| DbError::WriterStopped)?
+    }
+
+    fn search_statuses(
+        &self,
+        hashtags: Option<&Vec<String>>,
+        media: MediaFilter,
+        limit: u16,
+    ) -> Result<Vec<String>, DbError> {
+        self.inner.search_statuses(hashtags, media, limit)
+    }
+
+    fn popular_statuses(
+        &self,
+        hashtags_o: Option<&Vec<String>>,
+        media: MediaFilter,
+        since: DateTime<Utc>,
+        limit: u16,
+    ) -> Result<Vec<String>, DbError> {
+        self.inner.popular_statuses(hashtags_o, media, since, limit)
+    }
+
+    fn list_ranking_candidates(
+        &self,
+        hashtags_o: Option<&Vec<String>>,
+        media: MediaFilter,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<IndexedStatus>, DbError> {
+        self.inner.list_ranking_candidates(hashtags_o, media, since)
+    }
+
+    fn list_stale_statuses(
+        &self,
+        since: DateTime<Utc>,
+        fresh_since: DateTime<Utc>,
+        limit: u16,
+    ) -> Result<Vec<String>, DbError> {
+        self.inner.list_stale_statuses(since, fresh_since, limit)
+    }
+
+    fn popular_tags(
+        &self,
+        hashtags_o: Option<&[String]>,
+        duration_days: &u16,
+        limit: &u16,
+    ) -> Result<Vec<(String, u32)>, DbError> {
+        self.inner.popular_tags(hashtags_o, duration_days, limit)
+    }
+
+    fn preferred_casings(&self, names: &[String]) -> Result<HashMap<String, String>, DbError> {
+        self.inner.preferred_casings(names)
+    }
+
+    fn rising_tags(
+        &self,
+        hashtags_o: Option<&[String]>,
+        since: DateTime<Utc>,
+        baseline_since: DateTime<Utc>,
+        min_count: u32,
+        min_ratio: f64,
+        limit: u16,
+    ) -> Result<Vec<RisingTag>, DbError> {
+        self.inner.rising_tags(
+            hashtags_o,
+            since,
+            baseline_since,
+            min_count,
+            min_ratio,
+            limit,
+        )
+    }
+
+    fn tag_cooccurrences(
+        &self,
+        since: DateTime<Utc>,
+        min_count: u32,
+    ) -> Result<Vec<TagCooccurrence>, DbError> {
+        self.inner.tag_cooccurrences(since, min_count)
+    }
+
+    fn engagement_history(&self, status_id: &str) -> Result<Vec<EngagementSnapshot>, DbError> {
+        self.inner.engagement_history(status_id)
+    }
+
+    fn engagement_velocity(
+        &self,
+        hashtags_o: Option<&Vec<String>>,
+        media: MediaFilter,
+        since: DateTime<Utc>,
+        limit: u16,
+    ) -> Result<Vec<(String, f64)>, DbError> {
+        self.inner
+            .engagement_velocity(hashtags_o, media, since, limit)
+    }
+
+    fn compact_engagements(
+        &self,
+        created_before: DateTime<Utc>,
+        resolution: Duration,
+    ) -> Result<usize, DbError> {
+        self.inner.compact_engagements(created_before, resolution)
+    }
+
+    fn delete_status(&self, status_id: &str) -> Result<bool, DbError> {
+        self.inner.delete_status(status_id)
+    }
+
+    fn reindex_statuses(&self, statuses: Vec<(&Status, DateTime<Utc>)>) -> Result<(), DbError> {
+        self.inner.reindex_statuses(statuses)
+    }
+
+    fn list_status_ids(&self) -> Result<Vec<String>, DbError> {
+        self.inner.list_status_ids()
+    }
+
+    fn is_backfilled(&self, name: &str) -> Result<bool, DbError> {
+        self.inner.is_backfilled(name)
+    }
+
+    fn mark_backfilled(&self, name: &str) -> Result<(), DbError> {
+        self.inner.mark_backfilled(name)
+    }
+
+    fn index_stats(&self) -> Result<IndexStats, DbError> {
+        self.inner.index_stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::database::sqlite;
+    use crate::infrastructure::repositories::status::StatusSqliteRepository;
+
+    #[test]
+    fn concurrent_inserts_are_all_indexed() {
+        let pool = Arc::new(sqlite::in_memory().unwrap());
+        let repository = Arc::new(BatchingStatusIndexRepository::new(Arc::new(
+            StatusSqliteRepository::new(pool),
+        )));
+        let status: Status =
+            serde_json::from_str(include_str!("../../services/testdata/status_current.json"))
+                .unwrap();
+
+        std::thread::scope(|scope| {
+            for i in 0..8 {
+                let repository = repository.clone();
+                let mut status = status.clone();
+                status.id = format!("{}{}", status.id, i);
+                scope.spawn(move || repository.insert_statuses(vec![&status]).unwrap());
+            }
+        });
+
+        assert_eq!(repository.index_stats().unwrap().statuses, 8);
+    }
+}
@@ -1,8 +1,9 @@
+use crate::domain::models::hashtag::HashtagAttributes;
 use crate::domain::repositories::hashtag::SubscribedHashtagRepository;
 use crate::infrastructure::database::sqlite;
+use crate::infrastructure::error::DbError;
 use async_trait::async_trait;
-use rusqlite::{OptionalExtension, params};
-use std::error::Error;
+use rusqlite::{OptionalExtension, Row, params};
 use std::sync::Arc;
 
 pub struct SubscribedHashtagSqliteRepository {
@@ -17,26 +18,35 @@ impl SubscribedHashtagSqliteRepository {
 
 #[async_trait]
 impl SubscribedHashtagRepository for SubscribedHashtagSqliteRepository {
-    fn increment_vote(&self, key: &str) -> Result<(), Box<dyn Error>> {
+    fn increment_vote(&self, feed: &str, key: &str) -> Result<(), DbError> {
         let mut conn = self.pool.get()?;
         let tx = conn.transaction()?;
         {
-            let mut select_stmt =
-                tx.prepare_cached("SELECT COUNT(*) FROM subscribed_hashtags WHERE name = ?1")?;
-            let count = select_stmt.query_row(params![key], |row| row.get(0))?;
+            // Votes for an alias count for its canonical hashtag
+            let mut alias_stmt =
+                tx.prepare_cached("SELECT canonical FROM hashtag_aliases WHERE alias = lower(?1)")?;
+            let key: String = alias_stmt
+                .query_row(params![key], |row| row.get(0))
+                .optional()?
+                .unwrap_or_else(|| key.to_string());
+
+            let mut select_stmt = tx.prepare_cached(
+                "SELECT COUNT(*) FROM subscribed_hashtags WHERE feed = ?1 AND lower(name) = lower(?2)",
+            )?;
+            let count = select_stmt.query_row(params![feed, key], |row| row.get(0))?;
 
             match count {
                 0 => {
                     let mut stmt = tx.prepare_cached(
-                        "INSERT INTO subscribed_hashtags (name, votes) VALUES(?1, ?2);",
+                        "INSERT INTO subscribed_hashtags (feed, name, votes) VALUES(?1, ?2, ?3);",
                     )?;
-                    stmt.execute(params![key, 1])?;
+                    stmt.execute(params![feed, key, 1])?;
                 }
                 _ => {
                     let mut stmt = tx.prepare_cached(
-                        "UPDATE subscribed_hashtags SET votes = votes + 1 WHERE name = ?1;",
+                        "UPDATE subscribed_hashtags SET votes = votes + 1 WHERE feed = ?1 AND lower(name) = lower(?2);",
                     )?;
-                    stmt.execute(params![key])?;
+                    stmt.execute(params![feed, key])?;
                 }
             };
         }
@@ -44,12 +54,14 @@ impl SubscribedHashtagRepository for SubscribedHashtagSqliteRepository {
         Ok(())
     }
 
-    fn list(&self) -> Result<Vec<String>, Box<dyn Error>> {
+    fn list(&self, feed: &str) -> Result<Vec<String>, DbError> {
         let conn = self.pool.get()?;
         let mut stmt = conn.prepare_cached(
-            "SELECT name FROM subscribed_hashtags WHERE approved = 1 ORDER BY name",
+            "SELECT name FROM subscribed_hashtags
+            WHERE feed = ?1 AND approved = 1 AND lower(name) NOT IN (SELECT alias FROM hashtag_aliases)
+            ORDER BY name",
         )?;
-        let result = stmt.query_map((), |row| row.get(0)).optional()?;
+        let result = stmt.query_map(params![feed], |row| row.get(0)).optional()?;
 
         let mut results: Vec<String> = Vec::new();
         match result {
@@ -62,4 +74,94 @@ impl SubscribedHashtagRepository for SubscribedHashtagSqliteRepository {
         };
         Ok(results)
     }
+
+    fn list_pending(&self, feed: &str) -> Result<Vec<(String, HashtagAttributes)>, DbError> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT name, approved, votes, created_at
+            FROM subscribed_hashtags
+            WHERE feed = ?1 AND approved = 0 AND lower(name) NOT IN (SELECT alias FROM hashtag_aliases)
+            ORDER BY votes DESC, created_at",
+        )?;
+
+        fn read_row(row: &Row) -> rusqlite::Result<(String, HashtagAttributes)> {
+            Ok((
+                row.get(0)?,
+                HashtagAttributes {
+                    approved: row.get(1)?,
+                    votes: row.get(2)?,
+                    created_at: row.get(3)?,
+                },
+            ))
+        }
+
+        let results: rusqlite::Result<Vec<(String, HashtagAttributes)>> =
+            stmt.query_map(params![feed], read_row)?.collect();
+        Ok(results?)
+    }
+
+    fn set_approved(&self, feed: &str, key: &str, approved: bool) -> Result<(), DbError> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare_cached(
+            "INSERT INTO subscribed_hashtags (feed, name, approved) VALUES (?1, ?2, ?3)
+            ON CONFLICT (feed, lower(name)) DO UPDATE SET approved = excluded.approved;",
+        )?;
+        stmt.execute(params![feed, key, approved])?;
+        Ok(())
+    }
+
+    fn delete(&self, feed: &str, key: &str) -> Result<(), DbError> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare_cached(
+            "DELETE FROM subscribed_hashtags WHERE feed = ?1 AND lower(name) = lower(?2);",
+        )?;
+        stmt.execute(params![feed, key])?;
+        Ok(())
+    }
+
+    fn list_tracked(&self, feed: Option<&str>) -> Result<Vec<String>, DbError> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT lower(name) FROM subscribed_hashtags
+            WHERE (?1 IS NULL OR feed = ?1) AND approved = 1
+            AND lower(name) NOT IN (SELECT alias FROM hashtag_aliases)
+            UNION
+            SELECT ha.alias
+            FROM hashtag_aliases ha
+            INNER JOIN subscribed_hashtags sh ON lower(sh.name) = ha.canonical
+            WHERE (?1 IS NULL OR sh.feed = ?1) AND sh.approved = 1
+            ORDER BY 1",
+        )?;
+        let results: rusqlite::Result<Vec<String>> =
+            stmt.query_map(params![feed], |row| row.get(0))?.collect();
+        Ok(results?)
+    }
+
+    fn list_aliases(&self) -> Result<Vec<(String, String)>, DbError> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT alias, canonical FROM hashtag_aliases ORDER BY canonical, alias",
+        )?;
+        let results: rusqlite::Result<Vec<(String, String)>> = stmt
+            .query_map((), |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect();
+        Ok(results?)
+    }
+
+    fn set_alias(&self, alias: &str, canonical: &str) -> Result<(), DbError> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare_cached(
+            "INSERT OR REPLACE INTO hashtag_aliases (alias, canonical) VALUES (lower(?1), lower(?2));",
+        )?;
+        stmt.execute(params![alias, canonical])?;
+        Ok(())
+    }
+
+    fn delete_alias(&self, alias: &str) -> Result<(), DbError> {
+        let conn = self.pool.get()?;
+        let mut stmt =
+            conn.prepare_cached("DELETE FROM hashtag_aliases WHERE alias = lower(?1);")?;
+        stmt.execute(params![alias])?;
+        Ok(())
+    }
 }
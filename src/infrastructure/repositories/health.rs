@@ -0,0 +1,38 @@
+use crate::domain::repositories::health::HealthRepository;
+use crate::infrastructure::database::sqlite;
+use crate::infrastructure::error::DbError;
+use std::sync::Arc;
+
+pub struct HealthSqliteRepository {
+    pool: Arc<sqlite::Connection>,
+}
+
+impl HealthSqliteRepository {
+    pub fn new(pool: Arc<sqlite::Connection>) -> Self {
+        Self { pool }
+    }
+}
+
+impl HealthRepository for HealthSqliteRepository {
+    fn ping(&self) -> Result<(), DbError> {
+        let conn = self.pool.get()?;
+        conn.query_row("SELECT 1", [], |_| Ok(()))?;
+        Ok(())
+    }
+
+    fn pending_migrations(&self) -> Result<Vec<String>, DbError> {
+        sqlite::pending_migrations(&self.pool)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrated_database_is_ready() {
+        let repository = HealthSqliteRepository::new(Arc::new(sqlite::in_memory().unwrap()));
+        repository.ping().unwrap();
+        assert!(repository.pending_migrations().unwrap().is_empty());
+    }
+}
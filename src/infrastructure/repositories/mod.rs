@@ -1,2 +1,7 @@
+pub mod archive;
+pub mod batching;
 pub mod hashtag;
+pub mod health;
+pub mod postgres;
 pub mod status;
+pub mod user;
@@ -0,0 +1,221 @@
+use crate::domain::models::archive::ArchiveRecord;
+use crate::domain::models::status::EngagementSnapshot;
+use crate::domain::repositories::archive::ArchiveRepository;
+use crate::infrastructure::database::postgres::{self, with_connection};
+use crate::infrastructure::error::DbError;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+pub struct ArchivePostgresRepository {
+    pool: Arc<postgres::Connection>,
+}
+
+impl ArchivePostgresRepository {
+    pub fn new(pool: Arc<postgres::Connection>) -> Self {
+        Self { pool }
+    }
+}
+
+impl ArchiveRepository for ArchivePostgresRepository {
+    fn list_hashtags(&self) -> Result<Vec<ArchiveRecord>, DbError> {
+        with_connection(&self.pool, |conn| {
+            let rows = conn.query(
+                "SELECT feed, name, approved, votes, created_at FROM subscribed_hashtags ORDER BY feed, name",
+                &[],
+            )?;
+            Ok(rows
+                .iter()
+                .map(|row| ArchiveRecord::Hashtag {
+                    feed: row.get(0),
+                    name: row.get(1),
+                    approved: row.get(2),
+                    votes: row.get::<_, i32>(3).try_into().unwrap_or_default(),
+                    created_at: row.get(4),
+                })
+                .collect())
+        })
+    }
+
+    fn list_aliases(&self) -> Result<Vec<ArchiveRecord>, DbError> {
+        with_connection(&self.pool, |conn| {
+            let rows = conn.query(
+                "SELECT alias, canonical FROM hashtag_aliases ORDER BY alias",
+                &[],
+            )?;
+            Ok(rows
+                .iter()
+                .map(|row| ArchiveRecord::Alias {
+                    alias: row.get(0),
+                    canonical: row.get(1),
+                })
+                .collect())
+        })
+    }
+
+    fn list_cursors(&self) -> Result<Vec<ArchiveRecord>, DbError> {
+        with_connection(&self.pool, |conn| {
+            let rows = conn.query(
+                "SELECT tag, status_id FROM recent_statuses ORDER BY tag",
+                &[],
+            )?;
+            Ok(rows
+                .iter()
+                .map(|row| ArchiveRecord::Cursor {
+                    tag: row.get(0),
+                    status_id: row.get(1),
+                })
+                .collect())
+        })
+    }
+
+    fn list_refreshes(&self) -> Result<HashMap<String, DateTime<Utc>>, DbError> {
+        with_connection(&self.pool, |conn| {
+            let rows = conn.query("SELECT id, refreshed_at FROM status_refreshes", &[])?;
+            Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+        })
+    }
+
+    fn merge_records(&self, records: &[ArchiveRecord]) -> Result<(), DbError> {
+        with_connection(&self.pool, |conn| {
+            let mut tx = conn.transaction()?;
+            // The approval and the votes are only ever raised, and a hashtag
+            // keeps its earliest suggestion date
+            let hashtag_stmt = tx.prepare(
+                "INSERT INTO subscribed_hashtags (feed, name, approved, votes, created_at)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (feed, lower(name)) DO UPDATE SET
+                    approved = subscribed_hashtags.approved OR excluded.approved,
+                    votes = GREATEST(subscribed_hashtags.votes, excluded.votes),
+                    created_at = LEAST(subscribed_hashtags.created_at, excluded.created_at)",
+            )?;
+            // The local aliases take precedence
+            let alias_stmt = tx.prepare(
+                "INSERT INTO hashtag_aliases (alias, canonical) VALUES (lower($1), lower($2))
+                ON CONFLICT (alias) DO NOTHING",
+            )?;
+            // Keep the most recent status ID
+            // https://docs.joinmastodon.org/api/guidelines/#id
+            let cursor_stmt = tx.prepare(
+                "INSERT INTO recent_statuses (tag, status_id) VALUES ($1, $2)
+                ON CONFLICT (tag) DO UPDATE SET status_id = CASE
+                    WHEN length(excluded.status_id) > length(recent_statuses.status_id)
+                        OR (length(excluded.status_id) = length(recent_statuses.status_id)
+                            AND excluded.status_id > recent_statuses.status_id)
+                    THEN excluded.status_id
+                    ELSE recent_statuses.status_id
+                END",
+            )?;
+            for record in records {
+                match record {
+                    ArchiveRecord::Hashtag {
+                        feed,
+                        name,
+                        approved,
+                        votes,
+                        created_at,
+                    } => {
+                        let votes = i32::try_from(*votes).unwrap_or(i32::MAX);
+                        tx.execute(&hashtag_stmt, &[feed, name, approved, &votes, created_at])?;
+                    }
+                    ArchiveRecord::Alias { alias, canonical } => {
+                        tx.execute(&alias_stmt, &[alias, canonical])?;
+                    }
+                    ArchiveRecord::Cursor { tag, status_id } => {
+                        tx.execute(&cursor_stmt, &[tag, status_id])?;
+                    }
+                    _ => {}
+                }
+            }
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    fn merge_engagements(
+        &self,
+        status_id: &str,
+        snapshots: &[EngagementSnapshot],
+    ) -> Result<usize, DbError> {
+        with_connection(&self.pool, |conn| {
+            let mut tx = conn.transaction()?;
+            let stmt = tx.prepare(
+                "INSERT INTO status_engagements (status_id, recorded_at, replies_count, reblogs_count, favourites_count)
+                SELECT $1, $2, $3, $4, $5
+                WHERE NOT EXISTS (SELECT 1 FROM status_engagements WHERE status_id = $1 AND recorded_at = $2)",
+            )?;
+            let mut inserted = 0;
+            for snapshot in snapshots {
+                inserted += tx.execute(
+                    &stmt,
+                    &[
+                        &status_id,
+                        &snapshot.recorded_at,
+                        &i64::from(snapshot.replies_count),
+                        &i64::from(snapshot.reblogs_count),
+                        &i64::from(snapshot.favourites_count),
+                    ],
+                )? as usize;
+            }
+            tx.commit()?;
+            Ok(inserted)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn merging_records_is_idempotent() {
+        let Some(database) = postgres::test_database() else {
+            return;
+        };
+        let repository = ArchivePostgresRepository::new(database.pool.clone());
+        let created_at = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        let hashtag = |approved: bool, votes: u32| ArchiveRecord::Hashtag {
+            feed: "default".to_string(),
+            name: "Minis".to_string(),
+            approved,
+            votes,
+            created_at,
+        };
+        let cursor = |status_id: &str| ArchiveRecord::Cursor {
+            tag: "minis".to_string(),
+            status_id: status_id.to_string(),
+        };
+        let records = vec![hashtag(true, 2), cursor("99")];
+        repository.merge_records(&records).unwrap();
+        repository.merge_records(&records).unwrap();
+        repository
+            .merge_records(&[hashtag(false, 1), cursor("100"), cursor("98")])
+            .unwrap();
+
+        let hashtags = repository.list_hashtags().unwrap();
+        assert_eq!(hashtags.len(), 1);
+        assert!(matches!(
+            &hashtags[0],
+            ArchiveRecord::Hashtag { approved: true, votes: 2, created_at: at, .. } if *at == created_at
+        ));
+        assert!(matches!(
+            &repository.list_cursors().unwrap()[..],
+            [ArchiveRecord::Cursor { status_id, .. }] if status_id == "100"
+        ));
+
+        let snapshot = EngagementSnapshot {
+            recorded_at: created_at,
+            replies_count: 1,
+            reblogs_count: 2,
+            favourites_count: 3,
+        };
+        assert_eq!(
+            repository
+                .merge_engagements("1", std::slice::from_ref(&snapshot))
+                .unwrap(),
+            1
+        );
+        assert_eq!(repository.merge_engagements("1", &[snapshot]).unwrap(), 0);
+    }
+}
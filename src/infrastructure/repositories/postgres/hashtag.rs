@@ -0,0 +1,174 @@
+use crate::domain::models::hashtag::HashtagAttributes;
+use crate::domain::repositories::hashtag::SubscribedHashtagRepository;
+use crate::infrastructure::database::postgres::{self, with_connection};
+use crate::infrastructure::error::DbError;
+use std::sync::Arc;
+
+pub struct SubscribedHashtagPostgresRepository {
+    pool: Arc<postgres::Connection>,
+}
+
+impl SubscribedHashtagPostgresRepository {
+    pub fn new(pool: Arc<postgres::Connection>) -> Self {
+        Self { pool }
+    }
+}
+
+impl SubscribedHashtagRepository for SubscribedHashtagPostgresRepository {
+    fn increment_vote(&self, feed: &str, key: &str) -> Result<(), DbError> {
+        with_connection(&self.pool, |conn| {
+            // Votes for an alias count for its canonical hashtag
+            let key: String = conn
+                .query_opt(
+                    "SELECT canonical FROM hashtag_aliases WHERE alias = lower($1)",
+                    &[&key],
+                )?
+                .map(|row| row.get(0))
+                .unwrap_or_else(|| key.to_string());
+            conn.execute(
+                "INSERT INTO subscribed_hashtags (feed, name, votes) VALUES ($1, $2, 1)
+                ON CONFLICT (feed, lower(name)) DO UPDATE SET votes = subscribed_hashtags.votes + 1",
+                &[&feed, &key],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn list(&self, feed: &str) -> Result<Vec<String>, DbError> {
+        with_connection(&self.pool, |conn| {
+            let rows = conn.query(
+                "SELECT name FROM subscribed_hashtags
+                WHERE feed = $1 AND approved AND lower(name) NOT IN (SELECT alias FROM hashtag_aliases)
+                ORDER BY name",
+                &[&feed],
+            )?;
+            Ok(rows.iter().map(|row| row.get(0)).collect())
+        })
+    }
+
+    fn list_pending(&self, feed: &str) -> Result<Vec<(String, HashtagAttributes)>, DbError> {
+        with_connection(&self.pool, |conn| {
+            let rows = conn.query(
+                "SELECT name, approved, votes, created_at
+                FROM subscribed_hashtags
+                WHERE feed = $1 AND NOT approved AND lower(name) NOT IN (SELECT alias FROM hashtag_aliases)
+                ORDER BY votes DESC, created_at",
+                &[&feed],
+            )?;
+            Ok(rows
+                .iter()
+                .map(|row| {
+                    (
+                        row.get(0),
+                        HashtagAttributes {
+                            approved: row.get(1),
+                            votes: row.get::<_, i32>(2).try_into().unwrap_or(u16::MAX),
+                            created_at: row.get(3),
+                        },
+                    )
+                })
+                .collect())
+        })
+    }
+
+    fn set_approved(&self, feed: &str, key: &str, approved: bool) -> Result<(), DbError> {
+        with_connection(&self.pool, |conn| {
+            conn.execute(
+                "INSERT INTO subscribed_hashtags (feed, name, approved) VALUES ($1, $2, $3)
+                ON CONFLICT (feed, lower(name)) DO UPDATE SET approved = excluded.approved",
+                &[&feed, &key, &approved],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn delete(&self, feed: &str, key: &str) -> Result<(), DbError> {
+        with_connection(&self.pool, |conn| {
+            conn.execute(
+                "DELETE FROM subscribed_hashtags WHERE feed = $1 AND lower(name) = lower($2)",
+                &[&feed, &key],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn list_tracked(&self, feed: Option<&str>) -> Result<Vec<String>, DbError> {
+        with_connection(&self.pool, |conn| {
+            let rows = conn.query(
+                "SELECT lower(name) FROM subscribed_hashtags
+                WHERE ($1::TEXT IS NULL OR feed = $1) AND approved
+                AND lower(name) NOT IN (SELECT alias FROM hashtag_aliases)
+                UNION
+                SELECT ha.alias
+                FROM hashtag_aliases ha
+                INNER JOIN subscribed_hashtags sh ON lower(sh.name) = ha.canonical
+                WHERE ($1::TEXT IS NULL OR sh.feed = $1) AND sh.approved
+                ORDER BY 1",
+                &[&feed],
+            )?;
+            Ok(rows.iter().map(|row| row.get(0)).collect())
+        })
+    }
+
+    fn list_aliases(&self) -> Result<Vec<(String, String)>, DbError> {
+        with_connection(&self.pool, |conn| {
+            let rows = conn.query(
+                "SELECT alias, canonical FROM hashtag_aliases ORDER BY canonical, alias",
+                &[],
+            )?;
+            Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+        })
+    }
+
+    fn set_alias(&self, alias: &str, canonical: &str) -> Result<(), DbError> {
+        with_connection(&self.pool, |conn| {
+            conn.execute(
+                "INSERT INTO hashtag_aliases (alias, canonical) VALUES (lower($1), lower($2))
+                ON CONFLICT (alias) DO UPDATE SET canonical = excluded.canonical",
+                &[&alias, &canonical],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn delete_alias(&self, alias: &str) -> Result<(), DbError> {
+        with_connection(&self.pool, |conn| {
+            conn.execute(
+                "DELETE FROM hashtag_aliases WHERE alias = lower($1)",
+                &[&alias],
+            )?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn votes_for_an_alias_count_for_its_canonical_hashtag() {
+        let Some(database) = postgres::test_database() else {
+            return;
+        };
+        let repository = SubscribedHashtagPostgresRepository::new(database.pool.clone());
+        repository.set_alias("Minis", "miniatures").unwrap();
+        repository.increment_vote("default", "miniatures").unwrap();
+        repository.increment_vote("default", "MINIS").unwrap();
+
+        let pending = repository.list_pending("default").unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].0, "miniatures");
+        assert_eq!(pending[0].1.votes, 2);
+
+        repository
+            .set_approved("default", "Miniatures", true)
+            .unwrap();
+        assert_eq!(repository.list("default").unwrap(), vec!["miniatures"]);
+        assert_eq!(
+            repository.list_tracked(None).unwrap(),
+            vec!["miniatures", "minis"]
+        );
+        assert!(repository.list_tracked(Some("other")).unwrap().is_empty());
+    }
+}
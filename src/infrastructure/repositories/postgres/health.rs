@@ -0,0 +1,27 @@
+use crate::domain::repositories::health::HealthRepository;
+use crate::infrastructure::database::postgres::{self, with_connection};
+use crate::infrastructure::error::DbError;
+use std::sync::Arc;
+
+pub struct HealthPostgresRepository {
+    pool: Arc<postgres::Connection>,
+}
+
+impl HealthPostgresRepository {
+    pub fn new(pool: Arc<postgres::Connection>) -> Self {
+        Self { pool }
+    }
+}
+
+impl HealthRepository for HealthPostgresRepository {
+    fn ping(&self) -> Result<(), DbError> {
+        with_connection(&self.pool, |conn| {
+            conn.query_one("SELECT 1", &[])?;
+            Ok(())
+        })
+    }
+
+    fn pending_migrations(&self) -> Result<Vec<String>, DbError> {
+        postgres::pending_migrations(&self.pool)
+    }
+}
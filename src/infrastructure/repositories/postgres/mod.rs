@@ -0,0 +1,4 @@
+pub mod archive;
+pub mod hashtag;
+pub mod health;
+pub mod status;
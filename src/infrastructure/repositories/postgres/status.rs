@@ -0,0 +1,825 @@
+use crate::domain::models::feed::MediaFilter;
+use crate::domain::models::hashtag::{RisingTag, TagCooccurrence};
+use crate::domain::models::status::{EngagementSnapshot, IndexStats, IndexedStatus};
+use crate::domain::repositories::status::{RecentStatusRepository, StatusIndexRepository};
+use crate::infrastructure::database::postgres::{self, with_connection};
+use crate::infrastructure::error::DbError;
+use ::postgres::{Row, Transaction};
+use chrono::{DateTime, Utc};
+use megalodon::entities::Status;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Lowercased hashtags, bound as a `TEXT[]` which is `NULL` when unset
+fn lowercase(hashtags_o: Option<&[String]>) -> Option<Vec<String>> {
+    hashtags_o.map(|hashtags| hashtags.iter().map(|tag| tag.to_lowercase()).collect())
+}
+
+/// Condition keeping the statuses whose ID is `status_id` and which have one
+/// of the hashtags of the `TEXT[]` parameter `param`, or all when it is `NULL`
+fn hashtags_clause(param: &str, status_id: &str) -> String {
+    format!(
+        "({param}::TEXT[] IS NULL OR {status_id} IN (SELECT ht.status_id FROM status_tags ht WHERE lower(ht.name) = ANY({param})))"
+    )
+}
+
+/// Condition leaving out the statuses with other attachments than images, as
+/// defined by `MediaFilter::Images`, when the `BOOLEAN` parameter `param` is true
+fn media_clause(param: &str, status_id: &str) -> String {
+    format!(
+        "(NOT {param} OR NOT EXISTS (SELECT 1 FROM status_attachments sa WHERE sa.status_id = {status_id} AND sa.type NOT IN ('image', 'unknown')))"
+    )
+}
+
+/// Counts are stored as `BIGINT`
+fn count(row: &Row, idx: usize) -> u32 {
+    row.get::<_, i64>(idx).try_into().unwrap_or(u32::MAX)
+}
+
+fn read_snapshot(row: &Row) -> EngagementSnapshot {
+    EngagementSnapshot {
+        recorded_at: row.get(0),
+        replies_count: count(row, 1),
+        reblogs_count: count(row, 2),
+        favourites_count: count(row, 3),
+    }
+}
+
+/// Replace the tags and attachment types of a stored status, as edits may
+/// remove some of them
+fn replace_tags_and_attachments(
+    tx: &mut Transaction,
+    status: &Status,
+) -> Result<(), ::postgres::Error> {
+    let names: Vec<&str> = status.tags.iter().map(|tag| tag.name.as_str()).collect();
+    let types: Vec<String> = status
+        .media_attachments
+        .iter()
+        .map(|attachment| attachment.r#type.to_string())
+        .collect();
+    tx.execute(
+        "DELETE FROM status_tags WHERE status_id = $1",
+        &[&status.id],
+    )?;
+    tx.execute(
+        "INSERT INTO status_tags (status_id, name) SELECT $1, unnest($2::TEXT[]) ON CONFLICT DO NOTHING",
+        &[&status.id, &names],
+    )?;
+    tx.execute(
+        "DELETE FROM status_attachments WHERE status_id = $1",
+        &[&status.id],
+    )?;
+    tx.execute(
+        "INSERT INTO status_attachments (status_id, position, type)
+        SELECT $1, (a.ordinality - 1)::INT, a.type FROM unnest($2::TEXT[]) WITH ORDINALITY AS a(type, ordinality)",
+        &[&status.id, &types],
+    )?;
+    Ok(())
+}
+
+pub struct RecentStatusPostgresRepository {
+    pool: Arc<postgres::Connection>,
+}
+
+impl RecentStatusPostgresRepository {
+    pub fn new(pool: Arc<postgres::Connection>) -> Self {
+        Self { pool }
+    }
+}
+
+impl RecentStatusRepository for RecentStatusPostgresRepository {
+    fn get_recent_status_id(&self, key: &str) -> Result<Option<String>, DbError> {
+        with_connection(&self.pool, |conn| {
+            let row = conn.query_opt(
+                "SELECT status_id FROM recent_statuses WHERE tag = $1",
+                &[&key],
+            )?;
+            Ok(row.map(|row| row.get(0)))
+        })
+    }
+
+    fn set_recent_status_id(&self, key: &String, value: &String) -> Result<(), DbError> {
+        with_connection(&self.pool, |conn| {
+            conn.execute(
+                "INSERT INTO recent_statuses (tag, status_id) VALUES ($1, $2)
+                ON CONFLICT (tag) DO UPDATE SET status_id = excluded.status_id",
+                &[key, value],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn delete_recent_status_id(&self, key: &str) -> Result<bool, DbError> {
+        with_connection(&self.pool, |conn| {
+            Ok(conn.execute("DELETE FROM recent_statuses WHERE tag = $1", &[&key])? > 0)
+        })
+    }
+}
+
+pub struct StatusPostgresRepository {
+    pool: Arc<postgres::Connection>,
+}
+
+impl StatusPostgresRepository {
+    pub fn new(pool: Arc<postgres::Connection>) -> Self {
+        Self { pool }
+    }
+}
+
+const UPSERT_STATUS: &str = "INSERT INTO statuses (id, created_at, account_id, account_acct, replies_count, reblogs_count, favourites_count, edited_at)
+    VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+    ON CONFLICT (id) DO UPDATE SET
+        created_at = excluded.created_at,
+        account_id = excluded.account_id,
+        account_acct = excluded.account_acct,
+        replies_count = excluded.replies_count,
+        reblogs_count = excluded.reblogs_count,
+        favourites_count = excluded.favourites_count,
+        edited_at = excluded.edited_at";
+
+impl StatusIndexRepository for StatusPostgresRepository {
+    fn insert_statuses(&self, statuses: Vec<&Status>) -> Result<(), DbError> {
+        with_connection(&self.pool, |conn| {
+            let mut tx = conn.transaction()?;
+            let stmt = tx.prepare(UPSERT_STATUS)?;
+            let refresh_stmt = tx.prepare(
+                "INSERT INTO status_refreshes (id, refreshed_at) VALUES ($1, $2)
+                ON CONFLICT (id) DO UPDATE SET refreshed_at = excluded.refreshed_at",
+            )?;
+            let engagement_stmt = tx.prepare(
+                "INSERT INTO status_engagements (status_id, recorded_at, replies_count, reblogs_count, favourites_count)
+                VALUES ($1, $2, $3, $4, $5)",
+            )?;
+            let now = Utc::now();
+            for status in statuses {
+                let counts = [
+                    i64::from(status.replies_count),
+                    i64::from(status.reblogs_count),
+                    i64::from(status.favourites_count),
+                ];
+                tx.execute(
+                    &stmt,
+                    &[
+                        &status.id,
+                        &status.created_at,
+                        &status.account.id,
+                        &status.account.acct,
+                        &counts[0],
+                        &counts[1],
+                        &counts[2],
+                        &status.edited_at,
+                    ],
+                )?;
+                replace_tags_and_attachments(&mut tx, status)?;
+                tx.execute(&refresh_stmt, &[&status.id, &now])?;
+                tx.execute(
+                    &engagement_stmt,
+                    &[&status.id, &now, &counts[0], &counts[1], &counts[2]],
+                )?;
+            }
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    fn search_statuses(
+        &self,
+        hashtags: Option<&Vec<String>>,
+        media: MediaFilter,
+        limit: u16,
+    ) -> Result<Vec<String>, DbError> {
+        let sql = format!(
+            "SELECT s.id
+            FROM statuses s
+            WHERE {}
+            AND {}
+            ORDER BY s.created_at DESC
+            LIMIT $2",
+            hashtags_clause("$1", "s.id"),
+            media_clause("$3::BOOLEAN", "s.id")
+        );
+        with_connection(&self.pool, |conn| {
+            let rows = conn.query(
+                &sql,
+                &[
+                    &lowercase(hashtags.map(|tags| tags.as_slice())),
+                    &i64::from(limit),
+                    &(media == MediaFilter::Images),
+                ],
+            )?;
+            Ok(rows.iter().map(|row| row.get(0)).collect())
+        })
+    }
+
+    fn popular_statuses(
+        &self,
+        hashtags_o: Option<&Vec<String>>,
+        media: MediaFilter,
+        since: DateTime<Utc>,
+        limit: u16,
+    ) -> Result<Vec<String>, DbError> {
+        let sql = format!(
+            "SELECT s.id
+            FROM statuses s
+            WHERE {}
+            AND {}
+            AND s.created_at >= $2
+            ORDER BY s.engagements_count DESC
+            LIMIT $3",
+            hashtags_clause("$1", "s.id"),
+            media_clause("$4::BOOLEAN", "s.id")
+        );
+        with_connection(&self.pool, |conn| {
+            let rows = conn.query(
+                &sql,
+                &[
+                    &lowercase(hashtags_o.map(|tags| tags.as_slice())),
+                    &since,
+                    &i64::from(limit),
+                    &(media == MediaFilter::Images),
+                ],
+            )?;
+            Ok(rows.iter().map(|row| row.get(0)).collect())
+        })
+    }
+
+    fn list_ranking_candidates(
+        &self,
+        hashtags_o: Option<&Vec<String>>,
+        media: MediaFilter,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<IndexedStatus>, DbError> {
+        let sql = format!(
+            "SELECT s.id, s.created_at, s.account_acct, s.replies_count, s.reblogs_count, s.favourites_count
+            FROM statuses s
+            WHERE {}
+            AND {}
+            AND s.created_at >= $2",
+            hashtags_clause("$1", "s.id"),
+            media_clause("$3::BOOLEAN", "s.id")
+        );
+        with_connection(&self.pool, |conn| {
+            let rows = conn.query(
+                &sql,
+                &[
+                    &lowercase(hashtags_o.map(|tags| tags.as_slice())),
+                    &since,
+                    &(media == MediaFilter::Images),
+                ],
+            )?;
+            Ok(rows
+                .iter()
+                .map(|row| IndexedStatus {
+                    id: row.get(0),
+                    created_at: row.get(1),
+                    account_acct: row.get(2),
+                    replies_count: count(row, 3),
+                    reblogs_count: count(row, 4),
+                    favourites_count: count(row, 5),
+                })
+                .collect())
+        })
+    }
+
+    fn list_stale_statuses(
+        &self,
+        since: DateTime<Utc>,
+        fresh_since: DateTime<Utc>,
+        limit: u16,
+    ) -> Result<Vec<String>, DbError> {
+        with_connection(&self.pool, |conn| {
+            let rows = conn.query(
+                "SELECT s.id
+                FROM statuses s
+                LEFT JOIN status_refreshes sr ON s.id = sr.id
+                WHERE s.created_at >= $1 AND s.created_at < $2 AND (sr.id IS NULL OR sr.refreshed_at < $2)
+                ORDER BY s.created_at DESC
+                LIMIT $3",
+                &[&since, &fresh_since, &i64::from(limit)],
+            )?;
+            Ok(rows.iter().map(|row| row.get(0)).collect())
+        })
+    }
+
+    fn popular_tags(
+        &self,
+        hashtags_o: Option<&[String]>,
+        duration_days: &u16,
+        limit: &u16,
+    ) -> Result<Vec<(String, u32)>, DbError> {
+        // Count the statuses per alias group, and display each group with the
+        // most common casing of its canonical hashtag.
+        let sql = format!(
+            "WITH tag_usage AS (
+                SELECT COALESCE(ha.canonical, lower(st.name)) AS canonical, st.name, st.status_id
+                FROM status_tags st
+                INNER JOIN statuses s ON st.status_id = s.id
+                LEFT JOIN hashtag_aliases ha ON ha.alias = lower(st.name)
+                WHERE {}
+                AND s.created_at >= now() - make_interval(days => $2)
+            )
+            SELECT (
+                    SELECT tu2.name
+                    FROM tag_usage tu2
+                    WHERE tu2.canonical = tu.canonical
+                    GROUP BY tu2.name
+                    ORDER BY lower(tu2.name) = tu.canonical DESC, COUNT(*) DESC
+                    LIMIT 1
+                ),
+                COUNT(DISTINCT tu.status_id)
+            FROM tag_usage tu
+            GROUP BY tu.canonical
+            ORDER BY 2 DESC
+            LIMIT $3",
+            hashtags_clause("$1", "st.status_id")
+        );
+        with_connection(&self.pool, |conn| {
+            let rows = conn.query(
+                &sql,
+                &[
+                    &lowercase(hashtags_o),
+                    &i32::from(*duration_days),
+                    &i64::from(*limit),
+                ],
+            )?;
+            Ok(rows.iter().map(|row| (row.get(0), count(row, 1))).collect())
+        })
+    }
+
+    fn preferred_casings(&self, names: &[String]) -> Result<HashMap<String, String>, DbError> {
+        with_connection(&self.pool, |conn| {
+            let stmt = conn.prepare(
+                "SELECT name
+                FROM status_tags
+                WHERE lower(name) = lower($1)
+                GROUP BY name
+                ORDER BY COUNT(*) DESC
+                LIMIT 1",
+            )?;
+            let mut casings = HashMap::with_capacity(names.len());
+            for name in names {
+                if let Some(row) = conn.query_opt(&stmt, &[name])? {
+                    casings.insert(name.to_lowercase(), row.get(0));
+                }
+            }
+            Ok(casings)
+        })
+    }
+
+    fn rising_tags(
+        &self,
+        hashtags_o: Option<&[String]>,
+        since: DateTime<Utc>,
+        baseline_since: DateTime<Utc>,
+        min_count: u32,
+        min_ratio: f64,
+        limit: u16,
+    ) -> Result<Vec<RisingTag>, DbError> {
+        // Count the statuses per alias group, scale the baseline count to the
+        // length of the current window, and smooth both counts so tags absent
+        // from the baseline don't divide by 0.
+        let scale = (Utc::now() - since).num_seconds() as f64
+            / (since - baseline_since).num_seconds().max(1) as f64;
+
+        let sql = format!(
+            "SELECT name, current_count, baseline_count, ratio
+            FROM (
+                SELECT name, current_count, baseline_count,
+                    (current_count + 1)::FLOAT8 / (baseline_count + 1) AS ratio
+                FROM (
+                    SELECT COALESCE(ha.canonical, lower(st.name)) AS name,
+                        COUNT(DISTINCT s.id) FILTER (WHERE s.created_at >= $2) AS current_count,
+                        COUNT(DISTINCT s.id) FILTER (WHERE s.created_at < $2) * $3::FLOAT8 AS baseline_count
+                    FROM status_tags st
+                    INNER JOIN statuses s ON st.status_id = s.id
+                    LEFT JOIN hashtag_aliases ha ON ha.alias = lower(st.name)
+                    WHERE {}
+                    AND s.created_at >= $4
+                    GROUP BY COALESCE(ha.canonical, lower(st.name))
+                ) counts
+            ) ratios
+            WHERE current_count >= $5 AND ratio >= $6
+            ORDER BY ratio DESC, current_count DESC
+            LIMIT $7",
+            hashtags_clause("$1", "s.id")
+        );
+        with_connection(&self.pool, |conn| {
+            let rows = conn.query(
+                &sql,
+                &[
+                    &lowercase(hashtags_o),
+                    &since,
+                    &scale,
+                    &baseline_since,
+                    &i64::from(min_count),
+                    &min_ratio,
+                    &i64::from(limit),
+                ],
+            )?;
+            Ok(rows
+                .iter()
+                .map(|row| RisingTag {
+                    name: row.get(0),
+                    count: count(row, 1),
+                    baseline_count: row.get(2),
+                    ratio: row.get(3),
+                })
+                .collect())
+        })
+    }
+
+    fn tag_cooccurrences(
+        &self,
+        since: DateTime<Utc>,
+        min_count: u32,
+    ) -> Result<Vec<TagCooccurrence>, DbError> {
+        with_connection(&self.pool, |conn| {
+            let rows = conn.query(
+                "WITH window_tags AS (
+                    SELECT DISTINCT st.status_id, lower(st.name) AS name
+                    FROM status_tags st
+                    INNER JOIN statuses s ON s.id = st.status_id
+                    WHERE s.created_at >= $1
+                ),
+                tag_counts AS (
+                    SELECT name, COUNT(*) AS n FROM window_tags GROUP BY name
+                ),
+                total AS (
+                    SELECT COUNT(DISTINCT status_id) AS n FROM window_tags
+                )
+                SELECT a.name, b.name, COUNT(*) AS together,
+                    COUNT(*)::FLOAT8 * (SELECT n FROM total) / (MAX(ca.n) * MAX(cb.n)) AS lift
+                FROM window_tags a
+                INNER JOIN window_tags b ON a.status_id = b.status_id AND a.name <> b.name
+                INNER JOIN tag_counts ca ON ca.name = a.name
+                INNER JOIN tag_counts cb ON cb.name = b.name
+                GROUP BY a.name, b.name
+                HAVING COUNT(*) >= $2
+                ORDER BY a.name, lift DESC",
+                &[&since, &i64::from(min_count)],
+            )?;
+            Ok(rows
+                .iter()
+                .map(|row| TagCooccurrence {
+                    name: row.get(0),
+                    related: row.get(1),
+                    count: count(row, 2),
+                    lift: row.get(3),
+                })
+                .collect())
+        })
+    }
+
+    fn engagement_history(&self, status_id: &str) -> Result<Vec<EngagementSnapshot>, DbError> {
+        with_connection(&self.pool, |conn| {
+            let rows = conn.query(
+                "SELECT recorded_at, replies_count, reblogs_count, favourites_count
+                FROM status_engagements
+                WHERE status_id = $1
+                ORDER BY recorded_at",
+                &[&status_id],
+            )?;
+            Ok(rows.iter().map(read_snapshot).collect())
+        })
+    }
+
+    fn engagement_velocity(
+        &self,
+        hashtags_o: Option<&Vec<String>>,
+        media: MediaFilter,
+        since: DateTime<Utc>,
+        limit: u16,
+    ) -> Result<Vec<(String, f64)>, DbError> {
+        let sql = format!(
+            "WITH snapshots AS (
+                SELECT se.status_id, se.recorded_at,
+                    se.replies_count + se.reblogs_count + se.favourites_count AS total,
+                    ROW_NUMBER() OVER (PARTITION BY se.status_id ORDER BY se.recorded_at, se.id) AS first_rank,
+                    ROW_NUMBER() OVER (PARTITION BY se.status_id ORDER BY se.recorded_at DESC, se.id DESC) AS last_rank
+                FROM status_engagements se
+                WHERE {}
+                AND {}
+                AND se.recorded_at >= $2
+            )
+            SELECT earliest.status_id,
+                (latest.total - earliest.total)::FLOAT8
+                    / (EXTRACT(EPOCH FROM latest.recorded_at - earliest.recorded_at)::FLOAT8 / 3600) AS velocity
+            FROM snapshots earliest
+            INNER JOIN snapshots latest ON latest.status_id = earliest.status_id AND latest.last_rank = 1
+            WHERE earliest.first_rank = 1 AND latest.recorded_at > earliest.recorded_at
+            ORDER BY velocity DESC
+            LIMIT $3",
+            hashtags_clause("$1", "se.status_id"),
+            media_clause("$4::BOOLEAN", "se.status_id")
+        );
+        with_connection(&self.pool, |conn| {
+            let rows = conn.query(
+                &sql,
+                &[
+                    &lowercase(hashtags_o.map(|tags| tags.as_slice())),
+                    &since,
+                    &i64::from(limit),
+                    &(media == MediaFilter::Images),
+                ],
+            )?;
+            Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+        })
+    }
+
+    fn compact_engagements(
+        &self,
+        created_before: DateTime<Utc>,
+        resolution: Duration,
+    ) -> Result<usize, DbError> {
+        let resolution = i64::try_from(resolution.as_secs().max(1)).unwrap_or(i64::MAX);
+        with_connection(&self.pool, |conn| {
+            let deleted = conn.execute(
+                "DELETE FROM status_engagements
+                WHERE status_id IN (SELECT id FROM statuses WHERE created_at < $1)
+                AND id NOT IN (
+                    SELECT MAX(se.id)
+                    FROM status_engagements se
+                    INNER JOIN statuses s ON s.id = se.status_id
+                    WHERE s.created_at < $1
+                    GROUP BY se.status_id, EXTRACT(EPOCH FROM se.recorded_at)::BIGINT / $2
+                )",
+                &[&created_before, &resolution],
+            )?;
+            Ok(deleted as usize)
+        })
+    }
+
+    fn delete_status(&self, status_id: &str) -> Result<bool, DbError> {
+        with_connection(&self.pool, |conn| {
+            let mut tx = conn.transaction()?;
+            tx.execute(
+                "DELETE FROM status_tags WHERE status_id = $1",
+                &[&status_id],
+            )?;
+            tx.execute(
+                "DELETE FROM status_attachments WHERE status_id = $1",
+                &[&status_id],
+            )?;
+            tx.execute(
+                "DELETE FROM status_engagements WHERE status_id = $1",
+                &[&status_id],
+            )?;
+            tx.execute("DELETE FROM status_refreshes WHERE id = $1", &[&status_id])?;
+            let deleted = tx.execute("DELETE FROM statuses WHERE id = $1", &[&status_id])?;
+            tx.commit()?;
+            Ok(deleted > 0)
+        })
+    }
+
+    fn reindex_statuses(&self, statuses: Vec<(&Status, DateTime<Utc>)>) -> Result<(), DbError> {
+        with_connection(&self.pool, |conn| {
+            let mut tx = conn.transaction()?;
+            let stmt = tx.prepare(UPSERT_STATUS)?;
+            let refresh_stmt = tx.prepare(
+                "INSERT INTO status_refreshes (id, refreshed_at) VALUES ($1, $2)
+                ON CONFLICT (id) DO UPDATE SET refreshed_at = GREATEST(status_refreshes.refreshed_at, excluded.refreshed_at)",
+            )?;
+            for (status, written_at) in statuses {
+                tx.execute(
+                    &stmt,
+                    &[
+                        &status.id,
+                        &status.created_at,
+                        &status.account.id,
+                        &status.account.acct,
+                        &i64::from(status.replies_count),
+                        &i64::from(status.reblogs_count),
+                        &i64::from(status.favourites_count),
+                        &status.edited_at,
+                    ],
+                )?;
+                replace_tags_and_attachments(&mut tx, status)?;
+                tx.execute(&refresh_stmt, &[&status.id, &written_at])?;
+            }
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    fn list_status_ids(&self) -> Result<Vec<String>, DbError> {
+        with_connection(&self.pool, |conn| {
+            let rows = conn.query("SELECT id FROM statuses", &[])?;
+            Ok(rows.iter().map(|row| row.get(0)).collect())
+        })
+    }
+
+    fn is_backfilled(&self, name: &str) -> Result<bool, DbError> {
+        with_connection(&self.pool, |conn| {
+            let row = conn.query_one(
+                "SELECT EXISTS (SELECT 1 FROM index_backfills WHERE name = $1)",
+                &[&name],
+            )?;
+            Ok(row.get(0))
+        })
+    }
+
+    fn mark_backfilled(&self, name: &str) -> Result<(), DbError> {
+        with_connection(&self.pool, |conn| {
+            conn.execute(
+                "INSERT INTO index_backfills (name, completed_at) VALUES ($1, now())
+                ON CONFLICT (name) DO NOTHING",
+                &[&name],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn index_stats(&self) -> Result<IndexStats, DbError> {
+        with_connection(&self.pool, |conn| {
+            let row = conn.query_one(
+                "SELECT
+                    (SELECT COUNT(*) FROM statuses),
+                    (SELECT COUNT(DISTINCT lower(name)) FROM status_tags),
+                    (SELECT COUNT(*) FROM status_engagements),
+                    (SELECT MIN(created_at) FROM statuses),
+                    (SELECT MAX(created_at) FROM statuses)",
+                &[],
+            )?;
+            Ok(IndexStats {
+                statuses: row.get::<_, i64>(0) as u64,
+                tags: row.get::<_, i64>(1) as u64,
+                engagement_snapshots: row.get::<_, i64>(2) as u64,
+                oldest: row.get(3),
+                newest: row.get(4),
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+
+    #[test]
+    fn statuses_are_indexed_searched_and_deleted() {
+        let Some(database) = postgres::test_database() else {
+            return;
+        };
+        let repository = StatusPostgresRepository::new(database.pool.clone());
+        let mut status: Status = serde_json::from_str(include_str!(
+            "../../../services/testdata/status_current.json"
+        ))
+        .unwrap();
+        status.created_at = Utc::now() - TimeDelta::hours(1);
+        let tag = status.tags[0].name.clone();
+        repository.insert_statuses(vec![&status]).unwrap();
+        repository.insert_statuses(vec![&status]).unwrap();
+
+        let stats = repository.index_stats().unwrap();
+        assert_eq!(stats.statuses, 1);
+        assert_eq!(stats.engagement_snapshots, 2);
+        assert_eq!(stats.tags, status.tags.len() as u64);
+
+        let tags = vec![tag.to_uppercase()];
+        assert_eq!(
+            repository
+                .search_statuses(Some(&tags), MediaFilter::Images, 10)
+                .unwrap(),
+            vec![status.id.clone()]
+        );
+        assert_eq!(
+            repository
+                .search_statuses(None, MediaFilter::All, 10)
+                .unwrap(),
+            vec![status.id.clone()]
+        );
+        assert!(
+            repository
+                .search_statuses(Some(&vec!["unknown".to_string()]), MediaFilter::All, 10)
+                .unwrap()
+                .is_empty()
+        );
+        let popular = repository.popular_tags(None, &1, &10).unwrap();
+        assert!(popular.iter().all(|(_, count)| *count == 1));
+        assert_eq!(popular.len(), status.tags.len());
+        assert_eq!(
+            repository
+                .list_ranking_candidates(
+                    Some(&tags),
+                    MediaFilter::All,
+                    Utc::now() - TimeDelta::days(1),
+                )
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(repository.engagement_history(&status.id).unwrap().len(), 2);
+        // Migrated without statuses, so with nothing to backfill
+        assert!(repository.is_backfilled("attachments").unwrap());
+        assert!(!repository.is_backfilled("other").unwrap());
+        repository.mark_backfilled("other").unwrap();
+        repository.mark_backfilled("other").unwrap();
+        assert!(repository.is_backfilled("other").unwrap());
+
+        // A video left out of the images, then removed by an edit
+        let mut edited = status.clone();
+        edited.media_attachments = serde_json::from_str(
+            r#"[{"id": "1", "type": "image", "url": "https://example.test/1.png", "remote_url": null,
+                "preview_url": null, "text_url": null, "meta": null, "description": null, "blurhash": null},
+               {"id": "2", "type": "video", "url": "https://example.test/2.mp4", "remote_url": null,
+                "preview_url": null, "text_url": null, "meta": null, "description": null, "blurhash": null}]"#,
+        )
+        .unwrap();
+        repository
+            .reindex_statuses(vec![(&edited, Utc::now())])
+            .unwrap();
+        assert!(
+            repository
+                .search_statuses(Some(&tags), MediaFilter::Images, 10)
+                .unwrap()
+                .is_empty()
+        );
+        edited.media_attachments.truncate(1);
+        repository.insert_statuses(vec![&edited]).unwrap();
+        assert_eq!(
+            repository
+                .search_statuses(Some(&tags), MediaFilter::Images, 10)
+                .unwrap(),
+            vec![status.id.clone()]
+        );
+
+        let day_ago = Utc::now() - TimeDelta::days(1);
+        assert_eq!(
+            repository
+                .rising_tags(None, day_ago, day_ago - TimeDelta::days(7), 1, 1.0, 10)
+                .unwrap()
+                .len(),
+            status.tags.len()
+        );
+        assert_eq!(
+            repository.tag_cooccurrences(day_ago, 1).unwrap().len(),
+            status.tags.len() * (status.tags.len() - 1)
+        );
+        assert_eq!(
+            repository
+                .preferred_casings(&[tag.to_uppercase()])
+                .unwrap()
+                .get(&tag.to_lowercase()),
+            Some(&tag)
+        );
+        repository
+            .engagement_velocity(Some(&tags), MediaFilter::Images, day_ago, 10)
+            .unwrap();
+        assert!(
+            repository
+                .list_stale_statuses(day_ago, Utc::now() - TimeDelta::minutes(30), 10)
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            repository
+                .list_stale_statuses(day_ago, Utc::now() + TimeDelta::minutes(1), 10)
+                .unwrap(),
+            vec![status.id.clone()]
+        );
+        repository
+            .compact_engagements(Utc::now(), Duration::from_secs(3600))
+            .unwrap();
+        assert_eq!(repository.engagement_history(&status.id).unwrap().len(), 1);
+
+        assert!(repository.delete_status(&status.id).unwrap());
+        assert!(!repository.delete_status(&status.id).unwrap());
+        assert_eq!(repository.index_stats().unwrap(), IndexStats::default());
+    }
+
+    #[test]
+    fn engagement_velocity_follows_the_change_over_time() {
+        let Some(database) = postgres::test_database() else {
+            return;
+        };
+        let repository = StatusPostgresRepository::new(database.pool.clone());
+        let mut growing: Status = serde_json::from_str(include_str!(
+            "../../../services/testdata/status_current.json"
+        ))
+        .unwrap();
+        growing.favourites_count = 10;
+        let mut shrinking = growing.clone();
+        shrinking.id = format!("{}0", growing.id);
+        shrinking.favourites_count = 50;
+        repository
+            .insert_statuses(vec![&growing, &shrinking])
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(10));
+        growing.favourites_count = 50;
+        shrinking.favourites_count = 10;
+        repository
+            .insert_statuses(vec![&growing, &shrinking])
+            .unwrap();
+
+        let velocities = repository
+            .engagement_velocity(None, MediaFilter::All, Utc::now() - TimeDelta::days(1), 10)
+            .unwrap();
+        assert_eq!(velocities.len(), 2);
+        assert_eq!(velocities[0].0, growing.id);
+        assert!(velocities[0].1 > 0.0);
+        assert_eq!(velocities[1].0, shrinking.id);
+        assert_eq!(velocities[1].1, -velocities[0].1);
+    }
+}
@@ -1,3 +1,6 @@
+use crate::domain::models::feed::MediaFilter;
+use crate::domain::models::hashtag::{RisingTag, TagCooccurrence};
+use crate::domain::models::status::{EngagementSnapshot, IndexStats, IndexedStatus};
 use crate::domain::repositories::status::{RecentStatusRepository, StatusIndexRepository};
 use crate::infrastructure::database::sqlite;
 use crate::infrastructure::error::DbError;
@@ -5,8 +8,10 @@ use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use megalodon::entities::Status;
 use rusqlite::fallible_iterator::FallibleIterator;
-use rusqlite::{OptionalExtension, Row, ToSql, params};
+use rusqlite::{OptionalExtension, Row, Statement, ToSql, Transaction, params};
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
 pub struct RecentStatusSqliteRepository {
     pool: Arc<sqlite::Connection>,
@@ -36,6 +41,69 @@ impl RecentStatusRepository for RecentStatusSqliteRepository {
         stmt.execute(params![key, value])?;
         Ok(())
     }
+
+    fn delete_recent_status_id(&self, key: &str) -> Result<bool, DbError> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare_cached("DELETE FROM recent_statuses WHERE tag = ?1")?;
+        Ok(stmt.execute(params![key])? > 0)
+    }
+}
+
+/// Condition keeping the statuses whose ID is `status_id` and which have one
+/// of the hashtags, to be followed by another condition. Its placeholders are
+/// bound by `bind_hashtags`.
+fn hashtags_clause(hashtags_o: Option<&[String]>, status_id: &str) -> String {
+    match hashtags_o {
+        Some(hashtags) => format!(
+            "{} IN (SELECT ht.status_id FROM status_tags ht WHERE lower(ht.name) IN ({})) AND",
+            status_id,
+            vec!["?"; hashtags.len()].join(",")
+        ),
+        None => String::new(),
+    }
+}
+
+/// Bind the hashtags of `hashtags_clause`. The statement mixes them with named
+/// parameters, so the clause has to come first in the query for its
+/// placeholders to be numbered from 1.
+fn bind_hashtags(stmt: &mut Statement, hashtags_o: Option<&[String]>) -> rusqlite::Result<()> {
+    for (i, tag) in hashtags_o.into_iter().flatten().enumerate() {
+        stmt.raw_bind_parameter(i + 1, tag.to_lowercase())?;
+    }
+    Ok(())
+}
+
+/// Condition leaving out the statuses with other attachments than images, as
+/// defined by `MediaFilter::Images`, to be followed by another condition
+fn media_clause(media: MediaFilter, status_id: &str) -> String {
+    match media {
+        MediaFilter::All => String::new(),
+        MediaFilter::Images => format!(
+            "NOT EXISTS (SELECT 1 FROM status_attachments sa WHERE sa.status_id = {} AND sa.type NOT IN ('image', 'unknown')) AND",
+            status_id
+        ),
+    }
+}
+
+/// Replace the tags and attachment types of a stored status, as edits may
+/// remove some of them
+fn replace_tags_and_attachments(tx: &Transaction, status: &Status) -> rusqlite::Result<()> {
+    tx.prepare_cached("DELETE FROM status_tags WHERE status_id = ?1")?
+        .execute(params![&status.id])?;
+    let mut tag_stmt =
+        tx.prepare_cached("INSERT OR IGNORE INTO status_tags (status_id, name) VALUES (?1, ?2)")?;
+    for tag in &status.tags {
+        tag_stmt.execute(params![&status.id, &tag.name])?;
+    }
+    tx.prepare_cached("DELETE FROM status_attachments WHERE status_id = ?1")?
+        .execute(params![&status.id])?;
+    let mut attachment_stmt = tx.prepare_cached(
+        "INSERT INTO status_attachments (status_id, position, type) VALUES (?1, ?2, ?3)",
+    )?;
+    for (position, attachment) in status.media_attachments.iter().enumerate() {
+        attachment_stmt.execute(params![&status.id, position, attachment.r#type.to_string()])?;
+    }
+    Ok(())
 }
 
 pub struct StatusSqliteRepository {
@@ -53,13 +121,13 @@ impl StatusIndexRepository for StatusSqliteRepository {
         let mut conn = self.pool.get()?;
         let tx = conn.transaction()?;
         {
-            let mut stmt = tx.prepare_cached("INSERT OR REPLACE INTO statuses (id, created_at, account_id, account_acct, replies_count, reblogs_count, favourites_count) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)")?;
-            let mut tag_stmt = tx.prepare_cached(
-                "INSERT OR REPLACE INTO status_tags (status_id, name) VALUES (?1, ?2)",
-            )?;
+            let mut stmt = tx.prepare_cached("INSERT OR REPLACE INTO statuses (id, created_at, account_id, account_acct, replies_count, reblogs_count, favourites_count, edited_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)")?;
             let mut refresh_stmt = tx.prepare_cached(
                 "INSERT OR REPLACE INTO status_refreshes (id, refreshed_at) VALUES (?1, ?2)",
             )?;
+            let mut engagement_stmt = tx.prepare_cached(
+                "INSERT INTO status_engagements (status_id, recorded_at, replies_count, reblogs_count, favourites_count) VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
             let now = Utc::now();
             for status in statuses {
                 let created_at = ToSql::to_sql(&status.created_at)?;
@@ -71,13 +139,19 @@ impl StatusIndexRepository for StatusSqliteRepository {
                     &status.replies_count,
                     &status.reblogs_count,
                     &status.favourites_count,
+                    &status.edited_at,
                 ])?;
 
-                for tag in &status.tags {
-                    tag_stmt.execute(params![&status.id, &tag.name])?;
-                }
+                replace_tags_and_attachments(&tx, status)?;
 
                 refresh_stmt.execute(params![&status.id, &now])?;
+                engagement_stmt.execute(params![
+                    &status.id,
+                    &now,
+                    &status.replies_count,
+                    &status.reblogs_count,
+                    &status.favourites_count,
+                ])?;
             }
         }
         tx.commit()?;
@@ -87,37 +161,23 @@ impl StatusIndexRepository for StatusSqliteRepository {
     fn search_statuses(
         &self,
         hashtags_o: Option<&Vec<String>>,
+        media: MediaFilter,
         limit: u16,
     ) -> Result<Vec<String>, DbError> {
-        let hashtags_clause: String = match hashtags_o {
-            Some(hashtags) => {
-                let n = hashtags.len();
-                let mut s = "?,".repeat(n);
-                s.pop();
-                format!("WHERE lower(st.name) IN ({})", s)
-            }
-            None => String::new(),
-        };
-
         let conn = self.pool.get()?;
         let sql = format!(
-            "SELECT DISTINCT(s.id)
+            "SELECT s.id
             FROM statuses s
-            LEFT JOIN status_tags st ON st.status_id = s.id
-            {}
+            WHERE {} {}
+            TRUE
             ORDER BY s.created_at DESC
             LIMIT :limit;",
-            hashtags_clause
+            hashtags_clause(hashtags_o.map(Vec::as_slice), "s.id"),
+            media_clause(media, "s.id")
         );
         let mut stmt = conn.prepare(&sql)?;
 
-        // use raw_bind_parameter because we mix parameters of different types
-        // and dynamic number of parameters
-        if let Some(hashtags) = hashtags_o {
-            for (i, tag) in hashtags.iter().enumerate() {
-                stmt.raw_bind_parameter(i + 1, tag.to_lowercase())?;
-            }
-        }
+        bind_hashtags(&mut stmt, hashtags_o.map(Vec::as_slice))?;
         stmt.raw_bind_parameter(c":limit", limit)?;
 
         let statuses: rusqlite::Result<Vec<String>> =
@@ -128,39 +188,24 @@ impl StatusIndexRepository for StatusSqliteRepository {
     fn popular_statuses(
         &self,
         hashtags_o: Option<&Vec<String>>,
+        media: MediaFilter,
         since: DateTime<Utc>,
         limit: u16,
     ) -> Result<Vec<String>, DbError> {
-        let hashtags_clause: String = match hashtags_o {
-            Some(hashtags) => {
-                let n = hashtags.len();
-                let mut s = "?,".repeat(n);
-                s.pop();
-                format!("WHERE lower(st.name) IN ({})", s)
-            }
-            None => String::new(),
-        };
-
         let conn = self.pool.get()?;
         let sql = format!(
-            "SELECT DISTINCT(s.id)
+            "SELECT s.id
             FROM statuses s
-            LEFT JOIN status_tags st ON st.status_id = s.id
-            {}
-            AND s.created_at >= :created_at
+            WHERE {} {}
+            s.created_at >= :created_at
             ORDER BY s.engagements_count DESC
             LIMIT :limit;",
-            hashtags_clause
+            hashtags_clause(hashtags_o.map(Vec::as_slice), "s.id"),
+            media_clause(media, "s.id")
         );
         let mut stmt = conn.prepare(&sql)?;
 
-        // use raw_bind_parameter because we mix parameters of different types
-        // and dynamic number of parameters
-        if let Some(hashtags) = hashtags_o {
-            for (i, tag) in hashtags.iter().enumerate() {
-                stmt.raw_bind_parameter(i + 1, tag.to_lowercase())?;
-            }
-        }
+        bind_hashtags(&mut stmt, hashtags_o.map(Vec::as_slice))?;
         stmt.raw_bind_parameter(c":created_at", since)?;
         stmt.raw_bind_parameter(c":limit", limit)?;
 
@@ -169,6 +214,42 @@ impl StatusIndexRepository for StatusSqliteRepository {
         Ok(statuses?)
     }
 
+    fn list_ranking_candidates(
+        &self,
+        hashtags_o: Option<&Vec<String>>,
+        media: MediaFilter,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<IndexedStatus>, DbError> {
+        let conn = self.pool.get()?;
+        let sql = format!(
+            "SELECT s.id, s.created_at, s.account_acct, s.replies_count, s.reblogs_count, s.favourites_count
+            FROM statuses s
+            WHERE {} {}
+            s.created_at >= :created_at;",
+            hashtags_clause(hashtags_o.map(Vec::as_slice), "s.id"),
+            media_clause(media, "s.id")
+        );
+        let mut stmt = conn.prepare(&sql)?;
+
+        bind_hashtags(&mut stmt, hashtags_o.map(Vec::as_slice))?;
+        stmt.raw_bind_parameter(c":created_at", since)?;
+
+        let statuses: rusqlite::Result<Vec<IndexedStatus>> = stmt
+            .raw_query()
+            .map(|row| {
+                Ok(IndexedStatus {
+                    id: row.get(0)?,
+                    created_at: row.get(1)?,
+                    account_acct: row.get(2)?,
+                    replies_count: row.get(3)?,
+                    reblogs_count: row.get(4)?,
+                    favourites_count: row.get(5)?,
+                })
+            })
+            .collect();
+        Ok(statuses?)
+    }
+
     fn list_stale_statuses(
         &self,
         since: DateTime<Utc>,
@@ -192,30 +273,639 @@ impl StatusIndexRepository for StatusSqliteRepository {
 
     fn popular_tags(
         &self,
+        hashtags_o: Option<&[String]>,
         duration_days: &u16,
         limit: &u16,
     ) -> Result<Vec<(String, u32)>, DbError> {
+        let conn = self.pool.get()?;
+        // Count the statuses per alias group, and display each group with the
+        // most common casing of its canonical hashtag.
+        let sql = format!(
+            "WITH tag_usage AS (
+                SELECT COALESCE(ha.canonical, lower(st.name)) AS canonical, st.name, st.status_id
+                FROM status_tags st
+                LEFT JOIN statuses s ON st.status_id = s.id
+                LEFT JOIN hashtag_aliases ha ON ha.alias = lower(st.name)
+                WHERE {}
+                s.created_at >= datetime('now', :period)
+            )
+            SELECT (
+                    SELECT tu2.name
+                    FROM tag_usage tu2
+                    WHERE tu2.canonical = tu.canonical
+                    GROUP BY tu2.name
+                    ORDER BY lower(tu2.name) = tu2.canonical DESC, COUNT(*) DESC
+                    LIMIT 1
+                ),
+                COUNT(DISTINCT tu.status_id)
+            FROM tag_usage tu
+            GROUP BY tu.canonical
+            ORDER BY 2 DESC
+            LIMIT :limit;",
+            hashtags_clause(hashtags_o, "st.status_id")
+        );
+        let mut stmt = conn.prepare(&sql)?;
+
+        bind_hashtags(&mut stmt, hashtags_o)?;
+        stmt.raw_bind_parameter(c":period", format!("-{} days", &duration_days))?;
+        stmt.raw_bind_parameter(c":limit", limit)?;
+
+        let results: rusqlite::Result<Vec<(String, u32)>> = stmt
+            .raw_query()
+            .map(|row| Ok((row.get(0)?, row.get(1)?)))
+            .collect();
+        Ok(results?)
+    }
+
+    fn preferred_casings(&self, names: &[String]) -> Result<HashMap<String, String>, DbError> {
         let conn = self.pool.get()?;
         let mut stmt = conn.prepare_cached(
-            "SELECT name, COUNT(*)
-            FROM status_tags st
-            LEFT JOIN statuses s ON st.status_id = s.id
-            WHERE s.created_at >= datetime('now', ?1)
+            "SELECT name
+            FROM status_tags
+            WHERE lower(name) = lower(?1)
             GROUP BY name
-            ORDER BY 2 DESC
-            LIMIT ?2;",
+            ORDER BY COUNT(*) DESC
+            LIMIT 1;",
+        )?;
+        let mut casings = HashMap::with_capacity(names.len());
+        for name in names {
+            if let Some(casing) = stmt
+                .query_row(params![name], |row| row.get::<_, String>(0))
+                .optional()?
+            {
+                casings.insert(name.to_lowercase(), casing);
+            }
+        }
+        Ok(casings)
+    }
+
+    fn rising_tags(
+        &self,
+        hashtags_o: Option<&[String]>,
+        since: DateTime<Utc>,
+        baseline_since: DateTime<Utc>,
+        min_count: u32,
+        min_ratio: f64,
+        limit: u16,
+    ) -> Result<Vec<RisingTag>, DbError> {
+        // Count the statuses per alias group, scale the baseline count to the
+        // length of the current window, and smooth both counts so tags absent
+        // from the baseline don't divide by 0.
+        let scale = (Utc::now() - since).num_seconds() as f64
+            / (since - baseline_since).num_seconds().max(1) as f64;
+
+        let conn = self.pool.get()?;
+        let sql = format!(
+            "WITH candidates AS (
+                SELECT s.id, s.created_at
+                FROM statuses s
+                WHERE {}
+                s.created_at >= :baseline_since
+            )
+            SELECT name, current_count, baseline_count, (current_count + 1.0) / (baseline_count + 1.0) AS ratio
+            FROM (
+                SELECT COALESCE(ha.canonical, lower(st.name)) AS name,
+                    COUNT(DISTINCT CASE WHEN c.created_at >= :since THEN c.id END) AS current_count,
+                    COUNT(DISTINCT CASE WHEN c.created_at < :since THEN c.id END) * :scale AS baseline_count
+                FROM status_tags st
+                INNER JOIN candidates c ON st.status_id = c.id
+                LEFT JOIN hashtag_aliases ha ON ha.alias = lower(st.name)
+                GROUP BY COALESCE(ha.canonical, lower(st.name))
+            )
+            WHERE current_count >= :min_count AND ratio >= :min_ratio
+            ORDER BY ratio DESC, current_count DESC
+            LIMIT :limit;",
+            hashtags_clause(hashtags_o, "s.id")
+        );
+        let mut stmt = conn.prepare(&sql)?;
+
+        bind_hashtags(&mut stmt, hashtags_o)?;
+        stmt.raw_bind_parameter(c":since", since)?;
+        stmt.raw_bind_parameter(c":scale", scale)?;
+        stmt.raw_bind_parameter(c":baseline_since", baseline_since)?;
+        stmt.raw_bind_parameter(c":min_count", min_count)?;
+        stmt.raw_bind_parameter(c":min_ratio", min_ratio)?;
+        stmt.raw_bind_parameter(c":limit", limit)?;
+
+        fn read_row(row: &Row) -> rusqlite::Result<RisingTag> {
+            Ok(RisingTag {
+                name: row.get(0)?,
+                count: row.get(1)?,
+                baseline_count: row.get(2)?,
+                ratio: row.get(3)?,
+            })
+        }
+
+        let results: rusqlite::Result<Vec<RisingTag>> = stmt.raw_query().map(read_row).collect();
+        Ok(results?)
+    }
+
+    fn tag_cooccurrences(
+        &self,
+        since: DateTime<Utc>,
+        min_count: u32,
+    ) -> Result<Vec<TagCooccurrence>, DbError> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare_cached(
+            "WITH window_tags AS (
+                SELECT DISTINCT st.status_id, lower(st.name) AS name
+                FROM status_tags st
+                INNER JOIN statuses s ON s.id = st.status_id
+                WHERE s.created_at >= ?1
+            ),
+            tag_counts AS (
+                SELECT name, COUNT(*) AS n FROM window_tags GROUP BY name
+            ),
+            total AS (
+                SELECT COUNT(DISTINCT status_id) AS n FROM window_tags
+            )
+            SELECT a.name, b.name, COUNT(*) AS together,
+                CAST(COUNT(*) AS REAL) * (SELECT n FROM total) / (MAX(ca.n) * MAX(cb.n)) AS lift
+            FROM window_tags a
+            INNER JOIN window_tags b ON a.status_id = b.status_id AND a.name <> b.name
+            INNER JOIN tag_counts ca ON ca.name = a.name
+            INNER JOIN tag_counts cb ON cb.name = b.name
+            GROUP BY a.name, b.name
+            HAVING together >= ?2
+            ORDER BY a.name, lift DESC;",
         )?;
 
-        fn read_row(row: &Row) -> rusqlite::Result<(String, u32)> {
-            Ok((row.get(0)?, row.get(1)?))
+        fn read_row(row: &Row) -> rusqlite::Result<TagCooccurrence> {
+            Ok(TagCooccurrence {
+                name: row.get(0)?,
+                related: row.get(1)?,
+                count: row.get(2)?,
+                lift: row.get(3)?,
+            })
         }
 
-        let results: Result<Vec<(String, u32)>, _> = stmt
-            .query_map(
-                params![format!("-{} days", &duration_days), &limit],
-                read_row,
-            )?
+        let results: rusqlite::Result<Vec<TagCooccurrence>> = stmt
+            .query_map(params![since, min_count], read_row)?
             .collect();
         Ok(results?)
     }
+
+    fn engagement_history(&self, status_id: &str) -> Result<Vec<EngagementSnapshot>, DbError> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT recorded_at, replies_count, reblogs_count, favourites_count
+            FROM status_engagements
+            WHERE status_id = ?1
+            ORDER BY recorded_at;",
+        )?;
+
+        fn read_row(row: &Row) -> rusqlite::Result<EngagementSnapshot> {
+            Ok(EngagementSnapshot {
+                recorded_at: row.get(0)?,
+                replies_count: row.get(1)?,
+                reblogs_count: row.get(2)?,
+                favourites_count: row.get(3)?,
+            })
+        }
+
+        let results: rusqlite::Result<Vec<EngagementSnapshot>> =
+            stmt.query_map(params![status_id], read_row)?.collect();
+        Ok(results?)
+    }
+
+    fn engagement_velocity(
+        &self,
+        hashtags_o: Option<&Vec<String>>,
+        media: MediaFilter,
+        since: DateTime<Utc>,
+        limit: u16,
+    ) -> Result<Vec<(String, f64)>, DbError> {
+        let conn = self.pool.get()?;
+        let sql = format!(
+            "WITH snapshots AS (
+                SELECT se.status_id, se.recorded_at,
+                    se.replies_count + se.reblogs_count + se.favourites_count AS total,
+                    ROW_NUMBER() OVER (PARTITION BY se.status_id ORDER BY se.recorded_at, se.rowid) AS first_rank,
+                    ROW_NUMBER() OVER (PARTITION BY se.status_id ORDER BY se.recorded_at DESC, se.rowid DESC) AS last_rank
+                FROM status_engagements se
+                WHERE {} {}
+                se.recorded_at >= :since
+            )
+            SELECT earliest.status_id,
+                CAST(latest.total - earliest.total AS REAL)
+                    / ((julianday(latest.recorded_at) - julianday(earliest.recorded_at)) * 24) AS velocity
+            FROM snapshots earliest
+            INNER JOIN snapshots latest ON latest.status_id = earliest.status_id AND latest.last_rank = 1
+            WHERE earliest.first_rank = 1 AND latest.recorded_at > earliest.recorded_at
+            ORDER BY velocity DESC
+            LIMIT :limit;",
+            hashtags_clause(hashtags_o.map(Vec::as_slice), "se.status_id"),
+            media_clause(media, "se.status_id")
+        );
+        let mut stmt = conn.prepare(&sql)?;
+
+        bind_hashtags(&mut stmt, hashtags_o.map(Vec::as_slice))?;
+        stmt.raw_bind_parameter(c":since", since)?;
+        stmt.raw_bind_parameter(c":limit", limit)?;
+
+        let results: rusqlite::Result<Vec<(String, f64)>> = stmt
+            .raw_query()
+            .map(|row| Ok((row.get(0)?, row.get(1)?)))
+            .collect();
+        Ok(results?)
+    }
+
+    fn compact_engagements(
+        &self,
+        created_before: DateTime<Utc>,
+        resolution: Duration,
+    ) -> Result<usize, DbError> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare_cached(
+            "DELETE FROM status_engagements
+            WHERE status_id IN (SELECT id FROM statuses WHERE created_at < ?1)
+            AND rowid NOT IN (
+                SELECT MAX(se.rowid)
+                FROM status_engagements se
+                INNER JOIN statuses s ON s.id = se.status_id
+                WHERE s.created_at < ?1
+                GROUP BY se.status_id, CAST(strftime('%s', se.recorded_at) AS INTEGER) / ?2
+            );",
+        )?;
+        let deleted = stmt.execute(params![created_before, resolution.as_secs().max(1)])?;
+        Ok(deleted)
+    }
+
+    fn delete_status(&self, status_id: &str) -> Result<bool, DbError> {
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM status_tags WHERE status_id = ?1",
+            params![status_id],
+        )?;
+        tx.execute(
+            "DELETE FROM status_attachments WHERE status_id = ?1",
+            params![status_id],
+        )?;
+        tx.execute(
+            "DELETE FROM status_engagements WHERE status_id = ?1",
+            params![status_id],
+        )?;
+        tx.execute(
+            "DELETE FROM status_refreshes WHERE id = ?1",
+            params![status_id],
+        )?;
+        let deleted = tx.execute("DELETE FROM statuses WHERE id = ?1", params![status_id])?;
+        tx.commit()?;
+        Ok(deleted > 0)
+    }
+
+    fn reindex_statuses(&self, statuses: Vec<(&Status, DateTime<Utc>)>) -> Result<(), DbError> {
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+        {
+            let mut stmt = tx.prepare_cached("INSERT OR REPLACE INTO statuses (id, created_at, account_id, account_acct, replies_count, reblogs_count, favourites_count, edited_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)")?;
+            let mut refresh_stmt = tx.prepare_cached(
+                "INSERT INTO status_refreshes (id, refreshed_at) VALUES (?1, ?2)
+                ON CONFLICT (id) DO UPDATE SET refreshed_at = max(refreshed_at, excluded.refreshed_at)",
+            )?;
+            for (status, written_at) in statuses {
+                stmt.execute(params![
+                    &status.id,
+                    &status.created_at,
+                    &status.account.id,
+                    &status.account.acct,
+                    &status.replies_count,
+                    &status.reblogs_count,
+                    &status.favourites_count,
+                    &status.edited_at,
+                ])?;
+                replace_tags_and_attachments(&tx, status)?;
+                refresh_stmt.execute(params![&status.id, &written_at])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn list_status_ids(&self) -> Result<Vec<String>, DbError> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare_cached("SELECT id FROM statuses")?;
+        let results: rusqlite::Result<Vec<String>> =
+            stmt.query_map([], |row| row.get(0))?.collect();
+        Ok(results?)
+    }
+
+    fn is_backfilled(&self, name: &str) -> Result<bool, DbError> {
+        let conn = self.pool.get()?;
+        let mut stmt =
+            conn.prepare_cached("SELECT EXISTS (SELECT 1 FROM index_backfills WHERE name = ?1)")?;
+        Ok(stmt.query_row(params![name], |row| row.get(0))?)
+    }
+
+    fn mark_backfilled(&self, name: &str) -> Result<(), DbError> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare_cached(
+            "INSERT OR IGNORE INTO index_backfills (name, completed_at) VALUES (?1, ?2)",
+        )?;
+        stmt.execute(params![name, Utc::now()])?;
+        Ok(())
+    }
+
+    fn index_stats(&self) -> Result<IndexStats, DbError> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT
+                (SELECT COUNT(*) FROM statuses),
+                (SELECT COUNT(DISTINCT lower(name)) FROM status_tags),
+                (SELECT COUNT(*) FROM status_engagements),
+                (SELECT MIN(created_at) FROM statuses),
+                (SELECT MAX(created_at) FROM statuses)",
+        )?;
+        let stats = stmt.query_row([], |row| {
+            Ok(IndexStats {
+                statuses: row.get(0)?,
+                tags: row.get(1)?,
+                engagement_snapshots: row.get(2)?,
+                oldest: row.get(3)?,
+                newest: row.get(4)?,
+            })
+        })?;
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use megalodon::entities::{Attachment, attachment::AttachmentType};
+
+    fn attachment(r#type: AttachmentType) -> Attachment {
+        Attachment {
+            id: "1".to_string(),
+            r#type,
+            url: "https://example.com/media".to_string(),
+            remote_url: None,
+            preview_url: None,
+            text_url: None,
+            meta: None,
+            description: None,
+            blurhash: None,
+        }
+    }
+
+    fn test_status() -> Status {
+        serde_json::from_str(include_str!("../../services/testdata/status_current.json")).unwrap()
+    }
+
+    #[test]
+    fn delete_status_removes_it_from_the_index() {
+        let pool = Arc::new(sqlite::in_memory().unwrap());
+        let repository = StatusSqliteRepository::new(pool);
+        let status: Status =
+            serde_json::from_str(include_str!("../../services/testdata/status_current.json"))
+                .unwrap();
+        repository.insert_statuses(vec![&status]).unwrap();
+
+        let stats = repository.index_stats().unwrap();
+        assert_eq!(stats.statuses, 1);
+        assert_eq!(stats.engagement_snapshots, 1);
+        assert_eq!(stats.tags, status.tags.len() as u64);
+        assert_eq!(stats.oldest, Some(status.created_at));
+
+        assert!(repository.delete_status(&status.id).unwrap());
+        assert!(!repository.delete_status(&status.id).unwrap());
+        assert_eq!(repository.index_stats().unwrap(), IndexStats::default());
+    }
+
+    #[test]
+    fn edits_replace_the_tags_of_a_status() {
+        let pool = Arc::new(sqlite::in_memory().unwrap());
+        let repository = StatusSqliteRepository::new(pool.clone());
+        let mut status: Status =
+            serde_json::from_str(include_str!("../../services/testdata/status_current.json"))
+                .unwrap();
+        repository.insert_statuses(vec![&status]).unwrap();
+        repository.insert_statuses(vec![&status]).unwrap();
+        let removed = status.tags.remove(0);
+        status.edited_at = Some(Utc::now());
+        repository.insert_statuses(vec![&status]).unwrap();
+
+        let conn = pool.get().unwrap();
+        let tags: Vec<String> = conn
+            .prepare("SELECT name FROM status_tags WHERE status_id = ?1 ORDER BY name")
+            .unwrap()
+            .query_map(params![&status.id], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let mut expected: Vec<String> = status.tags.iter().map(|tag| tag.name.clone()).collect();
+        expected.sort();
+        assert_eq!(tags, expected);
+        assert!(!tags.contains(&removed.name));
+        let edited_at: Option<DateTime<Utc>> = conn
+            .query_row(
+                "SELECT edited_at FROM statuses WHERE id = ?1",
+                params![&status.id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(edited_at, status.edited_at);
+    }
+
+    #[test]
+    fn images_only_feeds_leave_out_the_other_media() {
+        let pool = Arc::new(sqlite::in_memory().unwrap());
+        let repository = StatusSqliteRepository::new(pool);
+        let mut image: Status =
+            serde_json::from_str(include_str!("../../services/testdata/status_current.json"))
+                .unwrap();
+        image.media_attachments = vec![
+            attachment(AttachmentType::Image),
+            attachment(AttachmentType::Unknown),
+        ];
+        let mut video = image.clone();
+        video.id = format!("{}0", image.id);
+        video
+            .media_attachments
+            .push(attachment(AttachmentType::Video));
+        repository.insert_statuses(vec![&image, &video]).unwrap();
+
+        let mut all = repository
+            .search_statuses(None, MediaFilter::All, 10)
+            .unwrap();
+        all.sort();
+        assert_eq!(all, vec![image.id.clone(), video.id.clone()]);
+        assert_eq!(
+            repository
+                .search_statuses(None, MediaFilter::Images, 10)
+                .unwrap(),
+            vec![image.id.clone()]
+        );
+        let since = image.created_at - chrono::Duration::days(1);
+        assert_eq!(
+            repository
+                .popular_statuses(None, MediaFilter::Images, since, 10)
+                .unwrap(),
+            vec![image.id.clone()]
+        );
+
+        // Edits may remove the video
+        video.media_attachments.pop();
+        repository
+            .reindex_statuses(vec![(&video, Utc::now())])
+            .unwrap();
+        assert_eq!(
+            repository
+                .list_ranking_candidates(None, MediaFilter::Images, since)
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn engagement_velocity_filters_by_hashtags() {
+        let pool = Arc::new(sqlite::in_memory().unwrap());
+        let repository = StatusSqliteRepository::new(pool);
+        let mut status = test_status();
+        repository.insert_statuses(vec![&status]).unwrap();
+        std::thread::sleep(Duration::from_millis(10));
+        status.favourites_count += 3;
+        repository.insert_statuses(vec![&status]).unwrap();
+
+        let since = Utc::now() - chrono::Duration::days(1);
+        let tags = vec!["unknown".to_string(), status.tags[0].name.to_uppercase()];
+        let velocities = repository
+            .engagement_velocity(Some(&tags), MediaFilter::All, since, 10)
+            .unwrap();
+        assert_eq!(velocities.len(), 1);
+        assert_eq!(velocities[0].0, status.id);
+        assert!(velocities[0].1 > 0.0);
+        assert!(
+            repository
+                .engagement_velocity(
+                    Some(&vec!["unknown".to_string()]),
+                    MediaFilter::All,
+                    since,
+                    10
+                )
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn engagement_velocity_follows_the_change_over_time() {
+        let pool = Arc::new(sqlite::in_memory().unwrap());
+        let repository = StatusSqliteRepository::new(pool);
+        let mut growing = test_status();
+        growing.favourites_count = 10;
+        let mut shrinking = test_status();
+        shrinking.id = format!("{}0", growing.id);
+        shrinking.favourites_count = 50;
+        repository
+            .insert_statuses(vec![&growing, &shrinking])
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(10));
+        growing.favourites_count = 50;
+        shrinking.favourites_count = 10;
+        repository
+            .insert_statuses(vec![&growing, &shrinking])
+            .unwrap();
+
+        let since = Utc::now() - chrono::Duration::days(1);
+        let velocities = repository
+            .engagement_velocity(None, MediaFilter::All, since, 10)
+            .unwrap();
+        assert_eq!(velocities.len(), 2);
+        assert_eq!(velocities[0].0, growing.id);
+        assert!(velocities[0].1 > 0.0);
+        assert_eq!(velocities[1].0, shrinking.id);
+        assert_eq!(velocities[1].1, -velocities[0].1);
+    }
+
+    #[test]
+    fn ranking_candidates_filter_by_hashtags() {
+        let pool = Arc::new(sqlite::in_memory().unwrap());
+        let repository = StatusSqliteRepository::new(pool);
+        let status = test_status();
+        repository.insert_statuses(vec![&status]).unwrap();
+
+        let since = status.created_at - chrono::Duration::days(1);
+        let tags = vec!["unknown".to_string(), status.tags[0].name.to_uppercase()];
+        let candidates = repository
+            .list_ranking_candidates(Some(&tags), MediaFilter::All, since)
+            .unwrap();
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].id, status.id);
+        assert!(
+            repository
+                .list_ranking_candidates(
+                    Some(&vec!["unknown".to_string()]),
+                    MediaFilter::All,
+                    since
+                )
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn rising_tags_filter_by_hashtags() {
+        let pool = Arc::new(sqlite::in_memory().unwrap());
+        let repository = StatusSqliteRepository::new(pool);
+        let mut status: Status =
+            serde_json::from_str(include_str!("../../services/testdata/status_current.json"))
+                .unwrap();
+        status.created_at = Utc::now() - chrono::Duration::hours(1);
+        repository.insert_statuses(vec![&status]).unwrap();
+
+        let tag = status.tags[0].name.clone();
+        let since = Utc::now() - chrono::Duration::days(1);
+        let baseline_since = since - chrono::Duration::days(7);
+        let rising = |hashtags: &[String]| {
+            repository
+                .rising_tags(Some(hashtags), since, baseline_since, 1, 1.0, 10)
+                .unwrap()
+        };
+        let names = |tags: Vec<RisingTag>| tags.into_iter().map(|t| t.name).collect::<Vec<_>>();
+        assert_eq!(names(rising(&[tag.to_uppercase()])), vec![tag.clone()]);
+        assert_eq!(
+            names(rising(&[
+                tag.to_uppercase(),
+                "unknown".to_string(),
+                "other".to_string()
+            ])),
+            vec![tag.clone()]
+        );
+        assert!(rising(&["unknown".to_string()]).is_empty());
+    }
+
+    #[test]
+    fn rising_tags_group_the_casings_and_aliases() {
+        let pool = Arc::new(sqlite::in_memory().unwrap());
+        pool.get()
+            .unwrap()
+            .execute(
+                "INSERT INTO hashtag_aliases (alias, canonical) VALUES ('minipaint', 'minipainting')",
+                [],
+            )
+            .unwrap();
+        let repository = StatusSqliteRepository::new(pool);
+        for (id, name) in [
+            ("1", "MiniPainting"),
+            ("2", "minipainting"),
+            ("3", "MiniPaint"),
+        ] {
+            let mut status = test_status();
+            status.id = id.to_string();
+            status.created_at = Utc::now() - chrono::Duration::hours(1);
+            status.tags[0].name = name.to_string();
+            repository.insert_statuses(vec![&status]).unwrap();
+        }
+
+        let since = Utc::now() - chrono::Duration::days(1);
+        let rising = repository
+            .rising_tags(None, since, since - chrono::Duration::days(7), 1, 1.0, 10)
+            .unwrap();
+        assert_eq!(rising.len(), 1);
+        assert_eq!(rising[0].name, "minipainting");
+        assert_eq!(rising[0].count, 3);
+    }
 }
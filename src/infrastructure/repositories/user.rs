@@ -0,0 +1,337 @@
+use crate::domain::models::user::{OAuthApp, PendingLogin, User};
+use crate::domain::repositories::user::UserRepository;
+use crate::infrastructure::database::sqlite;
+use crate::infrastructure::error::DbError;
+use chrono::{DateTime, Utc};
+use rusqlite::{OptionalExtension, Row, params};
+use std::sync::Arc;
+
+pub struct UserSqliteRepository {
+    pool: Arc<sqlite::Connection>,
+}
+
+impl UserSqliteRepository {
+    pub fn new(pool: Arc<sqlite::Connection>) -> Self {
+        Self { pool }
+    }
+
+    fn list_ids(&self, sql: &str, user_id: i64) -> Result<Vec<String>, DbError> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare_cached(sql)?;
+        let ids: rusqlite::Result<Vec<String>> = stmt
+            .query_map(params![user_id], |row| row.get(0))?
+            .collect();
+        Ok(ids?)
+    }
+
+    fn toggle(
+        &self,
+        insert_sql: &str,
+        delete_sql: &str,
+        user_id: i64,
+        value: &str,
+        enabled: bool,
+    ) -> Result<(), DbError> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare_cached(if enabled { insert_sql } else { delete_sql })?;
+        stmt.execute(params![user_id, value])?;
+        Ok(())
+    }
+}
+
+impl UserRepository for UserSqliteRepository {
+    fn get_app(
+        &self,
+        instance: &str,
+        redirect_uri: &str,
+        scopes: &str,
+    ) -> Result<Option<OAuthApp>, DbError> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT client_id, client_secret FROM oauth_apps
+            WHERE instance = ?1 AND redirect_uri = ?2 AND scopes = ?3",
+        )?;
+        Ok(stmt
+            .query_row(params![instance, redirect_uri, scopes], |row| {
+                Ok(OAuthApp {
+                    client_id: row.get(0)?,
+                    client_secret: row.get(1)?,
+                })
+            })
+            .optional()?)
+    }
+
+    fn save_app(
+        &self,
+        instance: &str,
+        redirect_uri: &str,
+        scopes: &str,
+        app: &OAuthApp,
+    ) -> Result<(), DbError> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare_cached(
+            "INSERT OR REPLACE INTO oauth_apps (instance, redirect_uri, scopes, client_id, client_secret)
+            VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?;
+        stmt.execute(params![
+            instance,
+            redirect_uri,
+            scopes,
+            app.client_id,
+            app.client_secret
+        ])?;
+        Ok(())
+    }
+
+    fn count_apps(&self) -> Result<u32, DbError> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare_cached("SELECT COUNT(*) FROM oauth_apps")?;
+        Ok(stmt.query_row([], |row| row.get(0))?)
+    }
+
+    fn save_login(
+        &self,
+        state: &str,
+        login: &PendingLogin,
+        created_at: DateTime<Utc>,
+        expired_before: DateTime<Utc>,
+    ) -> Result<(), DbError> {
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+        {
+            let mut delete_stmt =
+                tx.prepare_cached("DELETE FROM oauth_logins WHERE created_at < ?1")?;
+            delete_stmt.execute(params![expired_before])?;
+
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO oauth_logins (state, instance, redirect_uri, return_to, created_at)
+                VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            stmt.execute(params![
+                state,
+                login.instance,
+                login.redirect_uri,
+                login.return_to,
+                created_at
+            ])?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn take_login(
+        &self,
+        state: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Option<PendingLogin>, DbError> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare_cached(
+            "DELETE FROM oauth_logins WHERE state = ?1
+            RETURNING instance, redirect_uri, return_to, created_at",
+        )?;
+        let login = stmt
+            .query_row(params![state], |row| {
+                Ok((
+                    PendingLogin {
+                        instance: row.get(0)?,
+                        redirect_uri: row.get(1)?,
+                        return_to: row.get(2)?,
+                    },
+                    row.get::<_, DateTime<Utc>>(3)?,
+                ))
+            })
+            .optional()?;
+        Ok(login
+            .filter(|(_, created_at)| *created_at >= since)
+            .map(|(login, _)| login))
+    }
+
+    fn upsert_user(
+        &self,
+        instance: &str,
+        account_id: &str,
+        acct: &str,
+        display_name: &str,
+        access_token: &str,
+    ) -> Result<i64, DbError> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare_cached(
+            "INSERT INTO users (instance, account_id, acct, display_name, access_token)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            ON CONFLICT (instance, account_id) DO UPDATE SET
+                acct = excluded.acct,
+                display_name = excluded.display_name,
+                access_token = excluded.access_token
+            RETURNING id",
+        )?;
+        Ok(stmt.query_row(
+            params![instance, account_id, acct, display_name, access_token],
+            |row| row.get(0),
+        )?)
+    }
+
+    fn create_session(
+        &self,
+        session_id: &str,
+        user_id: i64,
+        created_at: DateTime<Utc>,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), DbError> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare_cached(
+            "INSERT INTO sessions (id, user_id, created_at, expires_at) VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        stmt.execute(params![session_id, user_id, created_at, expires_at])?;
+        Ok(())
+    }
+
+    fn get_session_user(
+        &self,
+        session_id: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Option<User>, DbError> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT u.id, u.instance, u.account_id, u.acct, u.display_name, u.access_token
+            FROM sessions s
+            INNER JOIN users u ON u.id = s.user_id
+            WHERE s.id = ?1 AND s.expires_at > ?2",
+        )?;
+
+        fn read_row(row: &Row) -> rusqlite::Result<User> {
+            Ok(User {
+                id: row.get(0)?,
+                instance: row.get(1)?,
+                account_id: row.get(2)?,
+                acct: row.get(3)?,
+                display_name: row.get(4)?,
+                access_token: row.get(5)?,
+            })
+        }
+
+        Ok(stmt
+            .query_row(params![session_id, now], read_row)
+            .optional()?)
+    }
+
+    fn delete_session(&self, session_id: &str) -> Result<(), DbError> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare_cached("DELETE FROM sessions WHERE id = ?1")?;
+        stmt.execute(params![session_id])?;
+        Ok(())
+    }
+
+    fn delete_expired_sessions(&self, now: DateTime<Utc>) -> Result<usize, DbError> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare_cached("DELETE FROM sessions WHERE expires_at <= ?1")?;
+        Ok(stmt.execute(params![now])?)
+    }
+
+    fn list_followed_tags(&self, user_id: i64, feed: &str) -> Result<Vec<String>, DbError> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT name FROM user_followed_tags WHERE user_id = ?1 AND feed = ?2 ORDER BY name",
+        )?;
+        let names: rusqlite::Result<Vec<String>> = stmt
+            .query_map(params![user_id, feed], |row| row.get(0))?
+            .collect();
+        Ok(names?)
+    }
+
+    fn set_followed_tag(
+        &self,
+        user_id: i64,
+        feed: &str,
+        name: &str,
+        followed: bool,
+    ) -> Result<(), DbError> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare_cached(if followed {
+            "INSERT OR IGNORE INTO user_followed_tags (user_id, feed, name) VALUES (?1, ?2, lower(?3))"
+        } else {
+            "DELETE FROM user_followed_tags WHERE user_id = ?1 AND feed = ?2 AND name = lower(?3)"
+        })?;
+        stmt.execute(params![user_id, feed, name])?;
+        Ok(())
+    }
+
+    fn list_muted_accounts(&self, user_id: i64) -> Result<Vec<String>, DbError> {
+        self.list_ids(
+            "SELECT acct FROM user_muted_accounts WHERE user_id = ?1 ORDER BY acct",
+            user_id,
+        )
+    }
+
+    fn set_muted_account(&self, user_id: i64, acct: &str, muted: bool) -> Result<(), DbError> {
+        self.toggle(
+            "INSERT OR IGNORE INTO user_muted_accounts (user_id, acct) VALUES (?1, lower(?2))",
+            "DELETE FROM user_muted_accounts WHERE user_id = ?1 AND acct = lower(?2)",
+            user_id,
+            acct,
+            muted,
+        )
+    }
+
+    fn list_hidden_statuses(&self, user_id: i64) -> Result<Vec<String>, DbError> {
+        self.list_ids(
+            "SELECT status_id FROM user_hidden_statuses WHERE user_id = ?1",
+            user_id,
+        )
+    }
+
+    fn set_hidden_status(
+        &self,
+        user_id: i64,
+        status_id: &str,
+        hidden: bool,
+    ) -> Result<(), DbError> {
+        self.toggle(
+            "INSERT OR IGNORE INTO user_hidden_statuses (user_id, status_id) VALUES (?1, ?2)",
+            "DELETE FROM user_hidden_statuses WHERE user_id = ?1 AND status_id = ?2",
+            user_id,
+            status_id,
+            hidden,
+        )
+    }
+
+    fn list_saved_statuses(&self, user_id: i64) -> Result<Vec<String>, DbError> {
+        self.list_ids(
+            "SELECT status_id FROM user_saved_statuses WHERE user_id = ?1 ORDER BY created_at DESC, rowid DESC",
+            user_id,
+        )
+    }
+
+    fn set_saved_status(&self, user_id: i64, status_id: &str, saved: bool) -> Result<(), DbError> {
+        self.toggle(
+            "INSERT OR IGNORE INTO user_saved_statuses (user_id, status_id) VALUES (?1, ?2)",
+            "DELETE FROM user_saved_statuses WHERE user_id = ?1 AND status_id = ?2",
+            user_id,
+            status_id,
+            saved,
+        )
+    }
+
+    fn get_resolved_status(&self, instance: &str, uri: &str) -> Result<Option<String>, DbError> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT status_id FROM resolved_statuses WHERE instance = ?1 AND uri = ?2",
+        )?;
+        Ok(stmt
+            .query_row(params![instance, uri], |row| row.get(0))
+            .optional()?)
+    }
+
+    fn set_resolved_status(
+        &self,
+        instance: &str,
+        uri: &str,
+        status_id: &str,
+    ) -> Result<(), DbError> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare_cached(
+            "INSERT OR REPLACE INTO resolved_statuses (instance, uri, status_id) VALUES (?1, ?2, ?3)",
+        )?;
+        stmt.execute(params![instance, uri, status_id])?;
+        Ok(())
+    }
+}
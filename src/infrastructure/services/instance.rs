@@ -0,0 +1,159 @@
+use log::debug;
+use megalodon::error::Error;
+use megalodon::mastodon::Mastodon;
+use megalodon::megalodon::{
+    AppInputOptions, PostStatusInputOptions, PostStatusOutput, SearchInputOptions, SearchType,
+};
+use megalodon::oauth::AppData;
+use megalodon::{Megalodon, entities};
+
+/// Client for the API of the home instances of the users, as opposed to
+/// `MastodonClient` which reads the public timelines of a single instance.
+#[derive(Debug, Clone)]
+pub struct InstanceClient {
+    user_agent: Option<String>,
+}
+
+impl InstanceClient {
+    pub fn new(user_agent: Option<String>) -> InstanceClient {
+        InstanceClient { user_agent }
+    }
+
+    fn client(&self, instance: &str, access_token: Option<String>) -> Result<Mastodon, Box<Error>> {
+        Mastodon::new(instance.to_string(), access_token, self.user_agent.clone()).map_err(Box::new)
+    }
+
+    pub async fn create_app(
+        &self,
+        instance: &str,
+        client_name: &str,
+        redirect_uri: &str,
+        website: Option<String>,
+        scopes: &[&str],
+    ) -> Result<AppData, Box<Error>> {
+        debug!("Registering the application on {}", instance);
+        self.client(instance, None)?
+            .create_app(
+                client_name.to_string(),
+                &AppInputOptions {
+                    scopes: Some(scopes.iter().map(|s| s.to_string()).collect()),
+                    redirect_uris: Some(redirect_uri.to_string()),
+                    website,
+                },
+            )
+            .await
+            .map_err(Box::new)
+    }
+
+    pub async fn fetch_access_token(
+        &self,
+        instance: &str,
+        client_id: &str,
+        client_secret: &str,
+        code: &str,
+        redirect_uri: &str,
+    ) -> Result<String, Box<Error>> {
+        self.client(instance, None)?
+            .fetch_access_token(
+                client_id.to_string(),
+                client_secret.to_string(),
+                code.to_string(),
+                redirect_uri.to_string(),
+            )
+            .await
+            .map(|token| token.access_token)
+            .map_err(Box::new)
+    }
+
+    pub async fn verify_credentials(
+        &self,
+        instance: &str,
+        access_token: &str,
+    ) -> Result<entities::Account, Box<Error>> {
+        self.client(instance, Some(access_token.to_string()))?
+            .verify_account_credentials()
+            .await
+            .map(|res| res.json())
+            .map_err(Box::new)
+    }
+
+    /// Find the ID on the instance of the status identified by `uri`,
+    /// fetching it from its origin if the instance does not know it yet
+    pub async fn resolve_status(
+        &self,
+        instance: &str,
+        access_token: &str,
+        uri: &str,
+    ) -> Result<Option<entities::Status>, Box<Error>> {
+        debug!("Resolving {} on {}", uri, instance);
+        self.client(instance, Some(access_token.to_string()))?
+            .search(
+                uri.to_string(),
+                Some(&SearchInputOptions {
+                    r#type: Some(SearchType::Statuses),
+                    resolve: Some(true),
+                    limit: Some(1),
+                    ..Default::default()
+                }),
+            )
+            .await
+            .map(|res| res.json().statuses.into_iter().next())
+            .map_err(Box::new)
+    }
+
+    pub async fn favourite_status(
+        &self,
+        instance: &str,
+        access_token: &str,
+        id: &str,
+        favourite: bool,
+    ) -> Result<entities::Status, Box<Error>> {
+        let client = self.client(instance, Some(access_token.to_string()))?;
+        let res = if favourite {
+            client.favourite_status(id.to_string()).await?
+        } else {
+            client.unfavourite_status(id.to_string()).await?
+        };
+        Ok(res.json())
+    }
+
+    pub async fn reblog_status(
+        &self,
+        instance: &str,
+        access_token: &str,
+        id: &str,
+        reblog: bool,
+    ) -> Result<entities::Status, Box<Error>> {
+        let client = self.client(instance, Some(access_token.to_string()))?;
+        let res = if reblog {
+            client.reblog_status(id.to_string()).await?
+        } else {
+            client.unreblog_status(id.to_string()).await?
+        };
+        Ok(res.json())
+    }
+
+    /// Post `text` in reply to the status `id`, returning the URL of the reply
+    pub async fn reply(
+        &self,
+        instance: &str,
+        access_token: &str,
+        id: &str,
+        text: &str,
+    ) -> Result<Option<String>, Box<Error>> {
+        let res = self
+            .client(instance, Some(access_token.to_string()))?
+            .post_status(
+                text.to_string(),
+                Some(&PostStatusInputOptions {
+                    in_reply_to_id: Some(id.to_string()),
+                    ..Default::default()
+                }),
+            )
+            .await?;
+        Ok(match res.json() {
+            PostStatusOutput::Status(status) => status.url,
+            PostStatusOutput::ScheduledStatus(_) => None,
+        })
+    }
+}
@@ -1,27 +1,53 @@
+use crate::infrastructure::metrics::Metrics;
 use log::debug;
 use megalodon::error::Error;
 use megalodon::mastodon::Mastodon;
 use megalodon::megalodon::GetHomeTimelineInputOptions;
+use megalodon::response::Response;
 use megalodon::{Megalodon, entities};
+use std::sync::Arc;
+use std::time::Instant;
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct MastodonClient {
     client: Mastodon,
+    metrics: Arc<Metrics>,
 }
 
 impl MastodonClient {
-    pub fn new(base_url: String, user_agent: Option<String>) -> Result<MastodonClient, Error> {
+    pub fn new(
+        base_url: String,
+        user_agent: Option<String>,
+        metrics: Arc<Metrics>,
+    ) -> Result<MastodonClient, Box<Error>> {
         debug!("Using the following User-Agent: {:?}", user_agent);
-        let client = Mastodon::new(base_url, None, user_agent)?;
-        Ok(MastodonClient { client })
+        let client = Mastodon::new(base_url, None, user_agent).map_err(Box::new)?;
+        Ok(MastodonClient { client, metrics })
     }
+
+    fn record<T>(&self, endpoint: &str, started: Instant, result: &Result<Response<T>, Error>) {
+        let (status, header) = match result {
+            Ok(res) => (Some(res.status), Some(&res.header)),
+            Err(Error::OwnError(err)) => (err.status, err.header.as_ref()),
+            Err(_) => (None, None),
+        };
+        let rate_limit_remaining = header
+            .and_then(|header| header.get("x-ratelimit-remaining"))
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok());
+        self.metrics
+            .mastodon_request(endpoint, status, rate_limit_remaining, started.elapsed());
+    }
+
     pub async fn get_tag_timeline(
         &self,
         hashtag: &String,
         min_id: Option<String>,
-    ) -> Result<Vec<entities::Status>, Error> {
+    ) -> Result<Vec<entities::Status>, Box<Error>> {
         debug!("Getting tag timeline for {} from {:?}", hashtag, min_id);
-        self.client
+        let started = Instant::now();
+        let result = self
+            .client
             .get_tag_timeline(
                 hashtag.clone(),
                 Some(&GetHomeTimelineInputOptions {
@@ -33,11 +59,22 @@ impl MastodonClient {
                     local: None,
                 }),
             )
-            .await
-            .map(|res| res.json())
+            .await;
+        self.record("tag_timeline", started, &result);
+        result.map(|res| res.json()).map_err(Box::new)
+    }
+
+    pub async fn get_status(&self, id: String) -> Result<entities::Status, Box<Error>> {
+        let started = Instant::now();
+        let result = self.client.get_status(id).await;
+        self.record("status", started, &result);
+        result.map(|res| res.json()).map_err(Box::new)
     }
 
-    pub async fn get_status(&self, id: String) -> Result<entities::Status, Error> {
-        self.client.get_status(id).await.map(|res| res.json())
+    pub async fn get_status_context(&self, id: String) -> Result<entities::Context, Box<Error>> {
+        let started = Instant::now();
+        let result = self.client.get_status_context(id, None).await;
+        self.record("status_context", started, &result);
+        result.map(|res| res.json()).map_err(Box::new)
     }
 }
@@ -1,2 +1,3 @@
+pub mod instance;
 pub mod mastodon;
 pub mod templating;
@@ -2,6 +2,7 @@ use chrono::{DateTime, Utc};
 use serde_json::to_value;
 use std::collections::HashMap;
 use std::ops::Sub;
+use std::path::Path;
 use tera::{Tera, try_get_value};
 
 fn timedelta_filter(
@@ -21,8 +22,39 @@ fn timedelta_filter(
     Ok(to_value(format!("{}m", delta.num_minutes()))?)
 }
 
-pub fn initialize_tera() -> tera::Result<Tera> {
-    let mut tera = Tera::new("templates/**/*.html")?;
+/// Format a media duration in seconds as `m:ss`, or `h:mm:ss` past an hour
+fn duration_filter(
+    value: &tera::Value,
+    _args: &HashMap<String, tera::Value>,
+) -> tera::Result<tera::Value> {
+    let v = try_get_value!("duration_filter", "value", f64, value);
+    let seconds = v.max(0.0).round() as u64;
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        return Ok(to_value(format!(
+            "{}:{:02}:{:02}",
+            hours, minutes, seconds
+        ))?);
+    }
+    Ok(to_value(format!("{}:{:02}", minutes, seconds))?)
+}
+
+pub fn initialize_tera(templates_dir: &Path) -> tera::Result<Tera> {
+    let mut tera = Tera::new(&format!("{}/**/*.html", templates_dir.display()))?;
     tera.register_filter("timedelta", timedelta_filter);
+    tera.register_filter("duration", duration_filter);
     Ok(tera)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duration_filter_formats_minutes_and_hours() {
+        let format = |seconds: f64| duration_filter(&to_value(seconds).unwrap(), &HashMap::new());
+        assert_eq!(format(7.4).unwrap(), "0:07");
+        assert_eq!(format(125.6).unwrap(), "2:06");
+        assert_eq!(format(3725.0).unwrap(), "1:02:05");
+    }
+}
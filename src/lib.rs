@@ -1,6 +1,7 @@
 extern crate core;
 
 pub mod api;
+pub mod cli;
 
 pub mod domain;
 pub mod infrastructure;
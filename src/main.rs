@@ -1,35 +1,67 @@
 use actix_settings::{ApplySettings, BasicSettings, Mode};
 use actix_web::HttpServer;
+use clap::Parser;
+use media_timeline::cli::{self, Cli, Command};
 use media_timeline::container::Container;
 use media_timeline::create_app::create_app;
-use media_timeline::settings::ApplicationSettings;
+use media_timeline::settings::{self, ApplicationSettings};
+use media_timeline::workers::repairs::StatusRepairer;
 use media_timeline::workers::statuses::StatusRefresher;
 use media_timeline::workers::timeline::TimelineUpdater;
 use media_timeline::workers::tracker::WorkerTracker;
 use std::error::Error;
+use std::process::ExitCode;
 use std::sync::Arc;
 
 #[actix_web::main]
-async fn main() -> Result<(), Box<dyn Error>> {
-    let mut settings: BasicSettings<ApplicationSettings> =
-        BasicSettings::parse_toml("./config.toml")
-            .expect("Failed to parse `Settings` from config.toml");
-
-    BasicSettings::<ApplicationSettings>::override_field_with_env_var(
-        &mut settings.actix.hosts,
-        "ACTIX_HOSTS",
-    )?;
-    BasicSettings::<ApplicationSettings>::override_field_with_env_var(
-        &mut settings.actix.mode,
-        "ACTIX_MODE",
-    )?;
-
-    init_logger(&settings);
-
-    let container: Arc<Container> = Arc::new(Container::new(settings.clone()).await);
-    let mut workers = WorkerTracker::new();
+async fn main() -> ExitCode {
+    match run(Cli::parse()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), Box<dyn Error>> {
+    let command = cli.command.unwrap_or(Command::Serve);
+    let settings = settings::load(&cli.config)?;
+
+    init_logger(&settings, matches!(command, Command::Serve));
+
+    if let Some(result) = cli::run_standalone(&command, &settings) {
+        return result;
+    }
+    let errors = settings.application.paths.check();
+    if !errors.is_empty() {
+        return Err(errors.join("\n").into());
+    }
+    let container: Arc<Container> = Arc::new(Container::new(settings.clone()).await?);
+    if !matches!(command, Command::Serve) {
+        return cli::run(command, &container).await;
+    }
+
+    match container.status_service.recover().await {
+        Ok(report) => log::info!(
+            "Recovered the storage: {} temporary files removed, {} statuses indexed, {} reindexed, {} unparseable, {} queued for a repair",
+            report.temp_files,
+            report.indexed,
+            report.reindexed,
+            report.unparseable.len(),
+            report.queued_repairs
+        ),
+        Err(e) => log::warn!("Unable to recover the storage: {}", e),
+    }
+
+    let mut workers = WorkerTracker::new(
+        container.worker_states.clone(),
+        container.worker_commands.clone(),
+        settings.application.workers.clone(),
+    );
     workers.register_worker(TimelineUpdater::new(container.clone()));
     workers.register_worker(StatusRefresher::new(container.clone()));
+    workers.register_worker(StatusRepairer::new(container.clone()));
     workers.start();
 
     let server =
@@ -48,8 +80,13 @@ async fn main() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-/// Initialize the logging infrastructure.
-fn init_logger(settings: &BasicSettings<ApplicationSettings>) {
+/// Initialize the logging infrastructure. The other commands than `serve`
+/// only log the warnings, unless `RUST_LOG` is set.
+fn init_logger(settings: &BasicSettings<ApplicationSettings>, serve: bool) {
+    if !serve {
+        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+        return;
+    }
     if !settings.actix.enable_log {
         return;
     }
@@ -0,0 +1,295 @@
+use crate::domain::models::archive::{
+    ARCHIVE_FORMAT, ARCHIVE_VERSION, ArchiveRecord, ExportReport, ImportReport,
+};
+use crate::domain::models::status::EngagementSnapshot;
+use crate::domain::repositories::archive::ArchiveRepository;
+use crate::domain::repositories::status::StatusIndexRepository;
+use crate::domain::services::archive::{ArchiveError, ArchiveService};
+use crate::domain::services::status::StatusService;
+use crate::infrastructure::database::unblock;
+use crate::infrastructure::error::DbError;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use log::{debug, warn};
+use megalodon::entities::Status;
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::fs::File;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, BufWriter};
+
+/// Statuses read from the storage, or stored, at once
+const STATUS_BATCH_SIZE: usize = 500;
+
+pub struct ArchiveServiceImpl {
+    repository: Arc<dyn ArchiveRepository>,
+    index_repository: Arc<dyn StatusIndexRepository>,
+    status_service: Arc<dyn StatusService>,
+}
+
+impl ArchiveServiceImpl {
+    pub fn new(
+        repository: Arc<dyn ArchiveRepository>,
+        index_repository: Arc<dyn StatusIndexRepository>,
+        status_service: Arc<dyn StatusService>,
+    ) -> Self {
+        Self {
+            repository,
+            index_repository,
+            status_service,
+        }
+    }
+
+    /// Run calls of the repositories off the async runtime
+    async fn with_repositories<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&dyn ArchiveRepository, &dyn StatusIndexRepository) -> Result<T, DbError>
+        + Send
+        + 'static,
+    ) -> Result<T, DbError> {
+        let repository = self.repository.clone();
+        let index_repository = self.index_repository.clone();
+        unblock(move || f(repository.as_ref(), index_repository.as_ref())).await
+    }
+
+    async fn write_statuses(
+        &self,
+        writer: &mut (impl AsyncWriteExt + Unpin),
+        report: &mut ExportReport,
+    ) -> Result<(), ArchiveError> {
+        let (refreshes, ids) = self
+            .with_repositories(|repository, index_repository| {
+                Ok((
+                    repository.list_refreshes()?,
+                    index_repository.list_status_ids()?,
+                ))
+            })
+            .await?;
+        for batch in ids.chunks(STATUS_BATCH_SIZE) {
+            let statuses = self.status_service.load_statuses(batch.to_vec()).await?;
+            report.missing_files += batch.len() - statuses.len();
+            let loaded: Vec<String> = statuses.iter().map(|status| status.id.clone()).collect();
+            let histories = self
+                .with_repositories(move |_, index_repository| {
+                    loaded
+                        .iter()
+                        .map(|id| index_repository.engagement_history(id))
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .await?;
+            for (status, engagements) in statuses.into_iter().zip(histories) {
+                let record = ArchiveRecord::Status {
+                    refreshed_at: refreshes.get(&status.id).cloned(),
+                    engagements,
+                    status: Box::new(status),
+                };
+                write_record(writer, &record).await?;
+                report.statuses += 1;
+            }
+            debug!("Exported {}/{} statuses", report.statuses, ids.len());
+        }
+        Ok(())
+    }
+
+    /// Write the whole archive to `path`
+    async fn write_archive(&self, path: &Path) -> Result<ExportReport, ArchiveError> {
+        let mut writer = BufWriter::new(File::create(path).await?);
+        let mut report = ExportReport::default();
+
+        write_record(&mut writer, &ArchiveRecord::header()).await?;
+        let (hashtags, aliases, cursors) = self
+            .with_repositories(|repository, _| {
+                Ok((
+                    repository.list_hashtags()?,
+                    repository.list_aliases()?,
+                    repository.list_cursors()?,
+                ))
+            })
+            .await?;
+        for record in hashtags {
+            write_record(&mut writer, &record).await?;
+            report.hashtags += 1;
+        }
+        for record in aliases {
+            write_record(&mut writer, &record).await?;
+            report.aliases += 1;
+        }
+        for record in cursors {
+            write_record(&mut writer, &record).await?;
+            report.cursors += 1;
+        }
+        self.write_statuses(&mut writer, &mut report).await?;
+
+        writer.flush().await?;
+        writer.into_inner().sync_all().await?;
+        Ok(report)
+    }
+
+    /// Record the engagement snapshots of several statuses, returning how
+    /// many were not already recorded
+    async fn merge_engagements(
+        &self,
+        engagements: Vec<(String, Vec<EngagementSnapshot>)>,
+    ) -> Result<usize, ArchiveError> {
+        Ok(self
+            .with_repositories(move |repository, _| {
+                engagements.iter().try_fold(0, |merged, (id, snapshots)| {
+                    Ok(merged + repository.merge_engagements(id, snapshots)?)
+                })
+            })
+            .await?)
+    }
+}
+
+async fn write_record(
+    writer: &mut (impl AsyncWriteExt + Unpin),
+    record: &ArchiveRecord,
+) -> Result<(), io::Error> {
+    let mut line = serde_json::to_vec(record)?;
+    line.push(b'\n');
+    writer.write_all(&line).await
+}
+
+/// Parse a line of an archive of the given version. The records of older
+/// versions are to be converted here when the format changes.
+fn read_record(line: &str, _version: u32) -> serde_json::Result<ArchiveRecord> {
+    serde_json::from_str(line)
+}
+
+/// Whether a status refreshed at `archived` should replace the stored one
+fn is_newer(archived: Option<DateTime<Utc>>, stored: Option<&DateTime<Utc>>) -> bool {
+    match (archived, stored) {
+        (_, None) => true,
+        (Some(archived), Some(stored)) => archived > *stored,
+        (None, Some(_)) => false,
+    }
+}
+
+#[async_trait]
+impl ArchiveService for ArchiveServiceImpl {
+    async fn export(&self, path: &Path) -> Result<ExportReport, ArchiveError> {
+        // Written next to the destination, so that a failed export never
+        // leaves a truncated archive behind
+        let partial = path.with_extension("partial");
+        let written = async {
+            let report = self.write_archive(&partial).await?;
+            tokio::fs::rename(&partial, path).await?;
+            Ok(report)
+        }
+        .await;
+        if written.is_err()
+            && let Err(e) = tokio::fs::remove_file(&partial).await
+            && e.kind() != io::ErrorKind::NotFound
+        {
+            warn!("Unable to remove {}: {}", partial.display(), e);
+        }
+        written
+    }
+
+    async fn import(&self, path: &Path) -> Result<ImportReport, ArchiveError> {
+        let mut lines = BufReader::new(File::open(path).await?).lines();
+        let version = match lines.next_line().await? {
+            Some(line) => match read_record(&line, ARCHIVE_VERSION) {
+                Ok(ArchiveRecord::Header {
+                    format, version, ..
+                }) if format == ARCHIVE_FORMAT => version,
+                _ => return Err(ArchiveError::InvalidHeader),
+            },
+            None => return Err(ArchiveError::InvalidHeader),
+        };
+        if version > ARCHIVE_VERSION {
+            return Err(ArchiveError::UnsupportedVersion(version));
+        }
+
+        let mut report = ImportReport {
+            version,
+            ..Default::default()
+        };
+        let refreshes: HashMap<String, DateTime<Utc>> = self
+            .with_repositories(|repository, _| repository.list_refreshes())
+            .await?;
+        let mut records = vec![];
+        let mut statuses: Vec<(Status, DateTime<Utc>)> = vec![];
+        let mut engagements: Vec<(String, Vec<EngagementSnapshot>)> = vec![];
+        let mut number = 1;
+        while let Some(line) = lines.next_line().await? {
+            number += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record =
+                read_record(&line, version).map_err(|source| ArchiveError::InvalidRecord {
+                    line: number,
+                    source,
+                })?;
+            match record {
+                ArchiveRecord::Status {
+                    status,
+                    refreshed_at,
+                    engagements: snapshots,
+                } => {
+                    engagements.push((status.id.clone(), snapshots));
+                    if engagements.len() >= STATUS_BATCH_SIZE {
+                        report.engagement_snapshots += self
+                            .merge_engagements(std::mem::take(&mut engagements))
+                            .await?;
+                    }
+                    if !is_newer(refreshed_at, refreshes.get(&status.id)) {
+                        report.statuses_up_to_date += 1;
+                        continue;
+                    }
+                    statuses.push((*status, refreshed_at.unwrap_or_else(Utc::now)));
+                    if statuses.len() >= STATUS_BATCH_SIZE {
+                        report.statuses += self
+                            .status_service
+                            .restore_statuses(std::mem::take(&mut statuses))
+                            .await?;
+                    }
+                }
+                record @ ArchiveRecord::Hashtag { .. } => {
+                    report.hashtags += 1;
+                    records.push(record);
+                }
+                record @ ArchiveRecord::Alias { .. } => {
+                    report.aliases += 1;
+                    records.push(record);
+                }
+                record @ ArchiveRecord::Cursor { .. } => {
+                    report.cursors += 1;
+                    records.push(record);
+                }
+                ArchiveRecord::Header { .. } => return Err(ArchiveError::InvalidHeader),
+                ArchiveRecord::Unknown => report.unknown_records += 1,
+            }
+        }
+        report.engagement_snapshots += self.merge_engagements(engagements).await?;
+        report.statuses += self.status_service.restore_statuses(statuses).await?;
+        self.with_repositories(move |repository, _| repository.merge_records(&records))
+            .await?;
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn archived_status_replaces_older_ones() {
+        let at = |hour| Some(Utc.with_ymd_and_hms(2025, 1, 1, hour, 0, 0).unwrap());
+        assert!(is_newer(at(1), None));
+        assert!(is_newer(None, None));
+        assert!(is_newer(at(2), at(1).as_ref()));
+        assert!(!is_newer(at(1), at(1).as_ref()));
+        assert!(!is_newer(None, at(1).as_ref()));
+    }
+
+    #[test]
+    fn read_record_skips_unknown_types() {
+        let record = read_record(r#"{"type":"bookmark","id":"1"}"#, 1).unwrap();
+        assert!(matches!(record, ArchiveRecord::Unknown));
+        assert!(read_record("{", 1).is_err());
+    }
+}
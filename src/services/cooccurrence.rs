@@ -0,0 +1,116 @@
+use crate::domain::models::hashtag::TagCooccurrence;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+fn by_lift(a: &TagCooccurrence, b: &TagCooccurrence) -> Ordering {
+    b.lift
+        .partial_cmp(&a.lift)
+        .unwrap_or(Ordering::Equal)
+        .then_with(|| b.count.cmp(&a.count))
+}
+
+/// Group the co-occurrences by subscribed hashtag, keeping the `limit` related
+/// tags with the highest lift for each of them.
+pub fn related_tags(
+    cooccurrences: &[TagCooccurrence],
+    hashtags: &[String],
+    limit: usize,
+) -> BTreeMap<String, Vec<TagCooccurrence>> {
+    let subscribed: HashSet<String> = hashtags.iter().map(|tag| tag.to_lowercase()).collect();
+
+    let mut related: BTreeMap<String, Vec<TagCooccurrence>> = BTreeMap::new();
+    for cooccurrence in cooccurrences {
+        if subscribed.contains(&cooccurrence.name) {
+            related
+                .entry(cooccurrence.name.clone())
+                .or_default()
+                .push(cooccurrence.clone());
+        }
+    }
+    for tags in related.values_mut() {
+        tags.sort_by(by_lift);
+        tags.truncate(limit);
+    }
+    related
+}
+
+/// Suggest the unsubscribed tags used with the subscribed `hashtags`, ranked
+/// by the lift of their strongest association with a subscribed hashtag.
+pub fn suggest_tags(
+    cooccurrences: &[TagCooccurrence],
+    hashtags: &[String],
+    limit: usize,
+) -> Vec<TagCooccurrence> {
+    let subscribed: HashSet<String> = hashtags.iter().map(|tag| tag.to_lowercase()).collect();
+
+    let mut best: HashMap<&str, &TagCooccurrence> = HashMap::new();
+    for cooccurrence in cooccurrences {
+        if !subscribed.contains(&cooccurrence.name) || subscribed.contains(&cooccurrence.related) {
+            continue;
+        }
+        best.entry(cooccurrence.related.as_str())
+            .and_modify(|current| {
+                if by_lift(cooccurrence, current) == Ordering::Less {
+                    *current = cooccurrence;
+                }
+            })
+            .or_insert(cooccurrence);
+    }
+
+    let mut suggestions: Vec<TagCooccurrence> = best.into_values().cloned().collect();
+    suggestions.sort_by(by_lift);
+    suggestions.truncate(limit);
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair(name: &str, related: &str, count: u32, lift: f64) -> TagCooccurrence {
+        TagCooccurrence {
+            name: name.to_string(),
+            related: related.to_string(),
+            count,
+            lift,
+        }
+    }
+
+    fn matrix() -> Vec<TagCooccurrence> {
+        vec![
+            pair("minipainting", "warhammer", 10, 1.5),
+            pair("minipainting", "terrain", 4, 3.0),
+            pair("minipainting", "miniatures", 8, 2.0),
+            pair("miniatures", "terrain", 5, 4.0),
+            pair("miniatures", "minipainting", 8, 2.0),
+            pair("terrain", "miniatures", 5, 4.0),
+        ]
+    }
+
+    #[test]
+    fn related_tags_groups_by_subscribed_tag() {
+        let hashtags = vec!["MiniPainting".to_string(), "miniatures".to_string()];
+        let related = related_tags(&matrix(), &hashtags, 2);
+
+        assert_eq!(related.len(), 2);
+        let names: Vec<&str> = related["minipainting"]
+            .iter()
+            .map(|c| c.related.as_str())
+            .collect();
+        assert_eq!(names, vec!["terrain", "miniatures"]);
+    }
+
+    #[test]
+    fn suggest_tags_keeps_strongest_unsubscribed_association() {
+        let hashtags = vec!["MiniPainting".to_string(), "miniatures".to_string()];
+        let suggestions = suggest_tags(&matrix(), &hashtags, 10);
+
+        assert_eq!(
+            suggestions,
+            vec![
+                pair("miniatures", "terrain", 5, 4.0),
+                pair("minipainting", "warhammer", 10, 1.5),
+            ]
+        );
+    }
+}
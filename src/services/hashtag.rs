@@ -1,7 +1,10 @@
+use crate::domain::models::hashtag::HashtagAttributes;
 use crate::domain::repositories::hashtag::SubscribedHashtagRepository;
 use crate::domain::services::hashtag::SubscribedHashtagService;
+use crate::infrastructure::database::unblock;
+use crate::infrastructure::error::DbError;
 use async_trait::async_trait;
-use log::debug;
+use log::{debug, info};
 use std::error::Error;
 use std::sync::Arc;
 
@@ -17,15 +20,105 @@ impl SubscribedHashtagServiceImpl {
 
 #[async_trait]
 impl SubscribedHashtagService for SubscribedHashtagServiceImpl {
-    fn list_hashtags(&self) -> Result<Vec<String>, Box<dyn Error>> {
-        self.repository.list()
+    async fn list_hashtags(&self, feed: &str) -> Result<Vec<String>, Box<dyn Error>> {
+        let (repository, feed) = (self.repository.clone(), feed.to_string());
+        Ok(unblock(move || repository.list(&feed)).await?)
     }
 
-    async fn suggest_hashtag(&self, key: &str) -> Result<(), Box<dyn Error>> {
+    async fn list_tracked_hashtags(&self, feed: &str) -> Result<Vec<String>, Box<dyn Error>> {
+        let (repository, feed) = (self.repository.clone(), feed.to_string());
+        Ok(unblock(move || repository.list_tracked(Some(&feed))).await?)
+    }
+
+    async fn list_polled_hashtags(&self) -> Result<Vec<String>, Box<dyn Error>> {
+        let repository = self.repository.clone();
+        Ok(unblock(move || repository.list_tracked(None)).await?)
+    }
+
+    async fn suggest_hashtag(&self, feed: &str, key: &str) -> Result<(), Box<dyn Error>> {
+        if !key.is_empty() {
+            let repository = self.repository.clone();
+            let (feed, key) = (feed.to_string(), key.to_string());
+            unblock(move || {
+                let attributes = repository.increment_vote(&feed, &key)?;
+                debug!(
+                    "Hashtag suggested for {}: {} -> {:?}",
+                    feed, key, attributes
+                );
+                Ok::<_, DbError>(())
+            })
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn list_suggestions(
+        &self,
+        feed: &str,
+    ) -> Result<Vec<(String, HashtagAttributes)>, Box<dyn Error>> {
+        let (repository, feed) = (self.repository.clone(), feed.to_string());
+        Ok(unblock(move || repository.list_pending(&feed)).await?)
+    }
+
+    async fn approve_hashtag(&self, feed: &str, key: &str) -> Result<(), Box<dyn Error>> {
+        if !key.is_empty() {
+            let repository = self.repository.clone();
+            let (feed, key) = (feed.to_string(), key.to_string());
+            unblock(move || {
+                repository.set_approved(&feed, &key, true)?;
+                info!("Hashtag approved for {}: {}", feed, key);
+                Ok::<_, DbError>(())
+            })
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn reject_hashtag(&self, feed: &str, key: &str) -> Result<(), Box<dyn Error>> {
         if !key.is_empty() {
-            let attributes = self.repository.increment_vote(key)?;
-            debug!("Hashtag suggested: {} -> {:?}", key, attributes)
+            let repository = self.repository.clone();
+            let (feed, key) = (feed.to_string(), key.to_string());
+            unblock(move || {
+                repository.delete(&feed, &key)?;
+                info!("Hashtag rejected for {}: {}", feed, key);
+                Ok::<_, DbError>(())
+            })
+            .await?;
         }
         Ok(())
     }
+
+    async fn list_aliases(&self) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+        let repository = self.repository.clone();
+        Ok(unblock(move || repository.list_aliases()).await?)
+    }
+
+    async fn add_alias(&self, alias: &str, canonical: &str) -> Result<(), Box<dyn Error>> {
+        if alias.is_empty() || canonical.is_empty() {
+            return Err("Both the alias and the canonical hashtag are required".into());
+        }
+        if alias.eq_ignore_ascii_case(canonical) {
+            return Err("A hashtag can't be an alias of itself".into());
+        }
+        let repository = self.repository.clone();
+        let (alias, canonical) = (alias.to_string(), canonical.to_string());
+        unblock(move || {
+            repository.set_alias(&alias, &canonical)?;
+            info!("Hashtag alias added: {} -> {}", alias, canonical);
+            Ok::<_, DbError>(())
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn remove_alias(&self, alias: &str) -> Result<(), Box<dyn Error>> {
+        let (repository, alias) = (self.repository.clone(), alias.to_string());
+        unblock(move || {
+            repository.delete_alias(&alias)?;
+            info!("Hashtag alias removed: {}", alias);
+            Ok::<_, DbError>(())
+        })
+        .await?;
+        Ok(())
+    }
 }
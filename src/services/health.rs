@@ -0,0 +1,94 @@
+use crate::domain::models::health::{Check, Readiness, WorkerState, WorkerStates};
+use crate::domain::repositories::health::HealthRepository;
+use crate::domain::services::health::HealthService;
+use crate::infrastructure::database::unblock;
+use async_trait::async_trait;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+fn check(name: &'static str, result: Result<(), String>) -> Check {
+    Check {
+        name,
+        error: result.err(),
+    }
+}
+
+pub struct HealthServiceImpl {
+    repository: Arc<dyn HealthRepository>,
+    worker_states: Arc<WorkerStates>,
+    data_dir: PathBuf,
+}
+
+impl HealthServiceImpl {
+    pub fn new(
+        repository: Arc<dyn HealthRepository>,
+        worker_states: Arc<WorkerStates>,
+        data_dir: impl Into<PathBuf>,
+    ) -> Self {
+        Self {
+            repository,
+            worker_states,
+            data_dir: data_dir.into(),
+        }
+    }
+
+    async fn check_database(&self) -> Result<(), String> {
+        let repository = self.repository.clone();
+        unblock(move || repository.ping())
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn check_migrations(&self) -> Result<(), String> {
+        let repository = self.repository.clone();
+        let pending = unblock(move || repository.pending_migrations())
+            .await
+            .map_err(|e| e.to_string())?;
+        if pending.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("pending migrations: {}", pending.join(", ")))
+        }
+    }
+
+    /// Workers given up by the supervisor
+    fn check_workers(&self) -> Result<(), String> {
+        let stopped: Vec<String> = self
+            .worker_states
+            .list()
+            .into_iter()
+            .filter(|state| state.stopped)
+            .map(|state| state.name)
+            .collect();
+        if stopped.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("stopped workers: {}", stopped.join(", ")))
+        }
+    }
+
+    async fn check_data_dir(&self) -> Result<(), String> {
+        let probe = self
+            .data_dir
+            .join(format!(".readyz-{}", std::process::id()));
+        let error = |e: std::io::Error| format!("{}: {}", self.data_dir.display(), e);
+        tokio::fs::write(&probe, b"").await.map_err(error)?;
+        tokio::fs::remove_file(&probe).await.map_err(error)
+    }
+}
+
+#[async_trait]
+impl HealthService for HealthServiceImpl {
+    async fn readiness(&self) -> Readiness {
+        Readiness::new(vec![
+            check("database", self.check_database().await),
+            check("migrations", self.check_migrations().await),
+            check("data_dir", self.check_data_dir().await),
+            check("workers", self.check_workers()),
+        ])
+    }
+
+    fn workers(&self) -> Vec<WorkerState> {
+        self.worker_states.list()
+    }
+}
@@ -0,0 +1,151 @@
+use crate::domain::models::interaction::{Interaction, InteractionState};
+use crate::domain::models::user::User;
+use crate::domain::repositories::user::UserRepository;
+use crate::domain::services::interaction::{InteractionError, InteractionService};
+use crate::domain::services::status::StatusService;
+use crate::infrastructure::database::unblock;
+use crate::infrastructure::services::instance::InstanceClient;
+use crate::services::user::{check_status_id, full_acct};
+use async_trait::async_trait;
+use log::debug;
+use megalodon::entities::Status;
+use std::sync::Arc;
+
+/// Read the state of the button from the status returned by the instance,
+/// which wraps the original status when boosting it
+fn interaction_state(interaction: Interaction, id: &str, status: Status) -> InteractionState {
+    let status = match status.reblog {
+        Some(original) => *original,
+        None => status,
+    };
+    let (active, count) = match interaction {
+        Interaction::Favourite => (status.favourited, status.favourites_count),
+        Interaction::Reblog => (status.reblogged, status.reblogs_count),
+    };
+    InteractionState {
+        interaction,
+        status_id: id.to_string(),
+        active: active.unwrap_or_default(),
+        count,
+    }
+}
+
+pub struct InteractionServiceImpl {
+    instance_client: Arc<InstanceClient>,
+    repository: Arc<dyn UserRepository>,
+    status_service: Arc<dyn StatusService>,
+}
+
+impl InteractionServiceImpl {
+    pub fn new(
+        instance_client: Arc<InstanceClient>,
+        repository: Arc<dyn UserRepository>,
+        status_service: Arc<dyn StatusService>,
+    ) -> Self {
+        Self {
+            instance_client,
+            repository,
+            status_service,
+        }
+    }
+
+    async fn load_status(&self, id: &str) -> Result<Status, InteractionError> {
+        check_status_id(id).map_err(|_| InteractionError::InvalidStatus)?;
+        self.status_service
+            .load_statuses(vec![id.to_string()])
+            .await?
+            .pop()
+            .ok_or(InteractionError::NotFound)
+    }
+
+    /// ID of the indexed status `id` on the instance of the user
+    async fn resolve(&self, user: &User, id: &str) -> Result<String, InteractionError> {
+        let status = self.load_status(id).await?;
+        let repository = self.repository.clone();
+        let (instance, uri) = (user.instance.clone(), status.uri.clone());
+        if let Some(resolved) =
+            unblock(move || repository.get_resolved_status(&instance, &uri)).await?
+        {
+            return Ok(resolved);
+        }
+        let resolved = self
+            .instance_client
+            .resolve_status(&user.instance, &user.access_token, &status.uri)
+            .await?
+            .ok_or(InteractionError::NotResolved)?;
+        debug!(
+            "{} resolved as {} on {}",
+            status.uri, resolved.id, user.instance
+        );
+        let repository = self.repository.clone();
+        let (instance, id) = (user.instance.clone(), resolved.id.clone());
+        unblock(move || repository.set_resolved_status(&instance, &status.uri, &id)).await?;
+        Ok(resolved.id)
+    }
+}
+
+#[async_trait]
+impl InteractionService for InteractionServiceImpl {
+    async fn interact(
+        &self,
+        user: &User,
+        id: &str,
+        interaction: Interaction,
+        active: bool,
+    ) -> Result<InteractionState, InteractionError> {
+        let resolved = self.resolve(user, id).await?;
+        let status = match interaction {
+            Interaction::Favourite => {
+                self.instance_client
+                    .favourite_status(&user.instance, &user.access_token, &resolved, active)
+                    .await?
+            }
+            Interaction::Reblog => {
+                self.instance_client
+                    .reblog_status(&user.instance, &user.access_token, &resolved, active)
+                    .await?
+            }
+        };
+        Ok(interaction_state(interaction, id, status))
+    }
+
+    async fn author(&self, id: &str) -> Result<String, InteractionError> {
+        let status = self.load_status(id).await?;
+        Ok(full_acct(&status.account.acct, &status.account.url))
+    }
+
+    async fn reply(
+        &self,
+        user: &User,
+        id: &str,
+        text: &str,
+    ) -> Result<Option<String>, InteractionError> {
+        let resolved = self.resolve(user, id).await?;
+        Ok(self
+            .instance_client
+            .reply(&user.instance, &user.access_token, &resolved, text)
+            .await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interaction_state_reads_the_boosted_status() {
+        let mut original: Status =
+            serde_json::from_str(include_str!("testdata/status_current.json")).unwrap();
+        original.reblogged = Some(true);
+        original.reblogs_count = 3;
+        let mut wrapper = original.clone();
+        wrapper.id = "2".to_string();
+        wrapper.reblogs_count = 0;
+        wrapper.reblog = Some(Box::new(original));
+
+        let state = interaction_state(Interaction::Reblog, "1", wrapper);
+        assert!(state.active);
+        assert_eq!(state.count, 3);
+        assert_eq!(state.status_id, "1");
+    }
+}
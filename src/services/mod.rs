@@ -1,2 +1,8 @@
+pub mod archive;
+pub mod cooccurrence;
 pub mod hashtag;
+pub mod health;
+pub mod interaction;
+pub mod ranking;
 pub mod status;
+pub mod user;
@@ -0,0 +1,124 @@
+use crate::domain::models::status::IndexedStatus;
+use crate::settings::HotRankingSettings;
+use chrono::{DateTime, Utc};
+use std::cmp::Ordering;
+
+/// Score statuses with a time-decayed weighted sum of their engagements.
+#[derive(Debug, Clone)]
+pub struct HotRanker {
+    settings: HotRankingSettings,
+}
+
+impl HotRanker {
+    pub fn new(settings: HotRankingSettings) -> Self {
+        Self { settings }
+    }
+
+    /// Only statuses created after this instant are worth ranking.
+    pub fn since(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now - *self.settings.max_age
+    }
+
+    pub fn score(&self, status: &IndexedStatus, now: DateTime<Utc>) -> f64 {
+        let weighted = self.settings.replies_weight * status.replies_count as f64
+            + self.settings.reblogs_weight * status.reblogs_count as f64
+            + self.settings.favourites_weight * status.favourites_count as f64;
+        let boost = self
+            .settings
+            .account_boosts
+            .get(&status.account_acct)
+            .copied()
+            .unwrap_or(1.0);
+
+        let age_hours = ((now - status.created_at).num_seconds().max(0) as f64) / 3600.0;
+        let half_life_hours = self.settings.half_life.as_secs_f64() / 3600.0;
+        let decay = if half_life_hours > 0.0 {
+            0.5_f64.powf(age_hours / half_life_hours)
+        } else {
+            1.0
+        };
+        let gravity = (age_hours + 2.0).powf(self.settings.gravity);
+
+        weighted * boost * decay / gravity
+    }
+
+    /// Sort statuses by decreasing score, most recent first on ties, and keep
+    /// the `limit` first IDs.
+    pub fn rank(
+        &self,
+        statuses: Vec<IndexedStatus>,
+        now: DateTime<Utc>,
+        limit: u16,
+    ) -> Vec<String> {
+        let mut scored: Vec<(f64, IndexedStatus)> = statuses
+            .into_iter()
+            .map(|status| (self.score(&status, now), status))
+            .collect();
+        scored.sort_by(|(a_score, a), (b_score, b)| {
+            b_score
+                .partial_cmp(a_score)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| b.created_at.cmp(&a.created_at))
+        });
+        scored
+            .into_iter()
+            .take(limit.into())
+            .map(|(_, status)| status.id)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn status(id: &str, age_hours: i64, favourites_count: u32) -> IndexedStatus {
+        IndexedStatus {
+            id: id.to_string(),
+            created_at: now() - chrono::Duration::hours(age_hours),
+            account_acct: format!("{id}@example.social"),
+            replies_count: 0,
+            reblogs_count: 0,
+            favourites_count,
+        }
+    }
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2025-01-01T12:00:00Z")
+            .unwrap()
+            .to_utc()
+    }
+
+    #[test]
+    fn score_halves_every_half_life() {
+        let ranker = HotRanker::new(HotRankingSettings {
+            half_life: Duration::from_secs(3600).into(),
+            ..HotRankingSettings::default()
+        });
+        assert_eq!(ranker.score(&status("a", 0, 8), now()), 8.0);
+        assert_eq!(ranker.score(&status("a", 2, 8), now()), 2.0);
+    }
+
+    #[test]
+    fn rank_applies_weights_and_account_boosts() {
+        let mut settings = HotRankingSettings {
+            favourites_weight: 1.0,
+            replies_weight: 3.0,
+            ..HotRankingSettings::default()
+        };
+        settings
+            .account_boosts
+            .insert("boosted@example.social".to_string(), 10.0);
+        let ranker = HotRanker::new(settings);
+
+        let mut replied = status("replied", 0, 0);
+        replied.replies_count = 2;
+        let ranked = ranker.rank(
+            vec![status("favourited", 0, 5), replied, status("boosted", 0, 1)],
+            now(),
+            2,
+        );
+        assert_eq!(ranked, vec!["boosted", "replied"]);
+    }
+}
@@ -1,19 +1,87 @@
+use crate::domain::models::feed::MediaFilter;
+use crate::domain::models::hashtag::{RelatedTags, RisingTag};
+use crate::domain::models::repair::{RepairReport, StatusRepairs};
+use crate::domain::models::status::{
+    EngagementSnapshot, IndexConsistency, IndexStats, PersistFailure, PersistReport,
+    RecoveryReport, ReindexReport,
+};
 use crate::domain::repositories::status::{RecentStatusRepository, StatusIndexRepository};
 
 use crate::domain::services::status::{StatusService, StatusServiceError};
+use crate::infrastructure::database::unblock;
+use crate::infrastructure::error::DbError;
+use crate::infrastructure::metrics::Metrics;
 use crate::infrastructure::services::mastodon::MastodonClient;
+use crate::services::cooccurrence;
+use crate::services::ranking::HotRanker;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use log::{debug, warn};
-use megalodon::entities::Status;
+use megalodon::entities::{Context, Status};
 use megalodon::error::Error::OwnError;
 use std::cmp::Reverse;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::fs::{File, create_dir_all};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{Mutex, MutexGuard};
+use tokio::task::{Id, JoinError, JoinSet};
 
-fn directory_for_status(status_id: &str) -> String {
+/// Status files written at the same time when persisting statuses
+const MAX_CONCURRENT_WRITES: usize = 16;
+
+/// Statuses indexed per transaction when recording the attachment types of
+/// the statuses stored before they were indexed
+const BACKFILL_BATCH_SIZE: usize = 500;
+
+/// Name of that backfill, recorded once completed
+const ATTACHMENTS_BACKFILL: &str = "attachments";
+
+/// Locks serializing the writes of the statuses, see `WriteLocks`
+const WRITE_LOCK_STRIPES: usize = 64;
+
+/// Time during which the cached thread context of a status is served without
+/// fetching it again
+const THREAD_CONTEXT_MAX_AGE: Duration = Duration::from_secs(15 * 60);
+
+/// Age after which a temporary file is left by an interrupted write, rather
+/// than being written by another replica sharing the storage
+const STALE_TEMP_FILE_AGE: Duration = Duration::from_secs(10 * 60);
+
+/// Index the statuses in one transaction. When it fails, they are indexed one
+/// by one so that only the faulty ones are reported as failed.
+fn index_statuses(
+    repository: &dyn StatusIndexRepository,
+    statuses: Vec<Status>,
+    report: &mut PersistReport,
+) {
+    let ids = statuses.iter().map(|status| status.id.clone());
+    if repository
+        .insert_statuses(statuses.iter().collect())
+        .is_ok()
+    {
+        report.persisted.extend(ids);
+        return;
+    }
+    for status in statuses {
+        match repository.insert_statuses(vec![&status]) {
+            Ok(()) => report.persisted.push(status.id),
+            Err(e) => {
+                warn!("Failed to index status {}: {e}", status.id);
+                report.failed.push(PersistFailure {
+                    status_id: status.id,
+                    error: format!("Unable to index the status: {e}"),
+                });
+            }
+        }
+    }
+}
+
+fn directory_for_status(root: &Path, status_id: &str) -> PathBuf {
     let len = status_id.len();
     let dir1 = if len <= 18 {
         "0"
@@ -25,7 +93,215 @@ fn directory_for_status(status_id: &str) -> String {
     } else {
         &status_id[0..len - 14]
     };
-    format!("data/statuses/{}/{}", dir1, dir2)
+    root.join(dir1).join(dir2)
+}
+
+fn status_file(root: &Path, status_id: &str) -> PathBuf {
+    directory_for_status(root, status_id).join(format!("{}.json", status_id))
+}
+
+/// File of the ancestors and descendants of a status, as last fetched
+fn context_file(root: &Path, status_id: &str) -> PathBuf {
+    directory_for_status(root, status_id).join(format!("{}.context", status_id))
+}
+
+/// File of the previous versions of an edited status, oldest first
+fn history_file(root: &Path, status_id: &str) -> PathBuf {
+    directory_for_status(root, status_id).join(format!("{}.history", status_id))
+}
+
+/// Whether the file is a status being written, or left behind by an
+/// interrupted write
+fn is_temp_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "tmp")
+}
+
+async fn is_stale(path: &Path) -> bool {
+    tokio::fs::metadata(path)
+        .await
+        .and_then(|metadata| metadata.modified())
+        .is_ok_and(|modified| {
+            modified
+                .elapsed()
+                .is_ok_and(|age| age >= STALE_TEMP_FILE_AGE)
+        })
+}
+
+/// List the files below `root`
+async fn list_files(root: &Path) -> Result<Vec<PathBuf>, io::Error> {
+    let mut files = vec![];
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let mut entries = match tokio::fs::read_dir(&dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound && dir == root => break,
+            Err(e) => return Err(e),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if entry.file_type().await?.is_dir() {
+                dirs.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// List the status files below `root`, keyed by status ID
+async fn list_status_files(root: &Path) -> Result<Vec<(String, PathBuf)>, io::Error> {
+    let mut files: Vec<(String, PathBuf)> = list_files(root)
+        .await?
+        .into_iter()
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| {
+            let id = path.file_stem()?.to_str()?.to_string();
+            Some((id, path))
+        })
+        .collect();
+    // https://docs.joinmastodon.org/api/guidelines/#id
+    files.sort_by(|(a, _), (b, _)| (a.len(), a).cmp(&(b.len(), b)));
+    Ok(files)
+}
+
+/// Replace the content of the file, by writing a temporary file renamed once
+/// durable, so that the file is never seen truncated, even after a crash.
+async fn write_file_atomically(path: &Path, content: &[u8]) -> Result<(), io::Error> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    // Unique, as the same status may be written by several workers at once
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(format!(".{:08x}.tmp", rand::random::<u32>()));
+    let temp = PathBuf::from(temp);
+    let result = async {
+        let mut file = File::create(&temp).await?;
+        file.write_all(content).await?;
+        file.sync_all().await?;
+        tokio::fs::rename(&temp, path).await
+    }
+    .await;
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&temp).await;
+    }
+    result?;
+    // Make the rename itself durable
+    #[cfg(unix)]
+    File::open(dir).await?.sync_all().await?;
+    Ok(())
+}
+
+/// Locks held while writing a status, so that two edits written at once don't
+/// both read the same history and lose a version. Several statuses share a
+/// lock, keeping their number bounded.
+struct WriteLocks([Mutex<()>; WRITE_LOCK_STRIPES]);
+
+impl Default for WriteLocks {
+    fn default() -> Self {
+        Self(std::array::from_fn(|_| Mutex::new(())))
+    }
+}
+
+impl WriteLocks {
+    async fn lock(&self, status_id: &str) -> MutexGuard<'_, ()> {
+        let mut hasher = DefaultHasher::new();
+        status_id.hash(&mut hasher);
+        self.0[hasher.finish() as usize % WRITE_LOCK_STRIPES]
+            .lock()
+            .await
+    }
+}
+
+/// Write the status to its file, replacing the previous version, which is
+/// kept in the history of the status when it is an edit
+async fn write_status_file(
+    root: &Path,
+    locks: &WriteLocks,
+    status: &Status,
+) -> Result<(), io::Error> {
+    let _guard = locks.lock(&status.id).await;
+    create_dir_all(directory_for_status(root, &status.id)).await?;
+    if let Err(e) = keep_previous_version(root, status).await {
+        warn!(
+            "Unable to keep the previous version of status {}: {e}",
+            status.id
+        );
+    }
+    let json = serde_json::to_string(status)?;
+    write_file_atomically(&status_file(root, &status.id), json.as_bytes()).await
+}
+
+/// Add the stored version of the status to its history, when `status` is a
+/// later edit of it
+async fn keep_previous_version(root: &Path, status: &Status) -> Result<(), io::Error> {
+    let Some(edited_at) = status.edited_at else {
+        return Ok(());
+    };
+    let previous = match tokio::fs::read_to_string(status_file(root, &status.id)).await {
+        Ok(content) => match parse_cached_status(&status.id, &content) {
+            Some(previous) => previous,
+            None => return Ok(()),
+        },
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    if previous.edited_at.is_some_and(|at| at >= edited_at) {
+        return Ok(());
+    }
+    let mut versions = read_history_file(root, &status.id).await?;
+    if versions
+        .iter()
+        .any(|version| version.edited_at == previous.edited_at)
+    {
+        return Ok(());
+    }
+    versions.push(previous);
+    let json = serde_json::to_string(&versions)?;
+    write_file_atomically(&history_file(root, &status.id), json.as_bytes()).await
+}
+
+/// Read the previous versions of a status, oldest first
+async fn read_history_file(root: &Path, status_id: &str) -> Result<Vec<Status>, io::Error> {
+    match tokio::fs::read_to_string(history_file(root, status_id)).await {
+        Ok(content) => Ok(serde_json::from_str(&content).unwrap_or_else(|e| {
+            warn!("Skipping the unparseable history of status {status_id}: {e}");
+            vec![]
+        })),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+        Err(e) => Err(e),
+    }
+}
+
+/// Read the cached thread context of a status along with its age
+async fn read_context_file(root: &Path, status_id: &str) -> Option<(Context, Duration)> {
+    let path = context_file(root, status_id);
+    let content = tokio::fs::read_to_string(&path).await.ok()?;
+    let age = tokio::fs::metadata(&path)
+        .await
+        .ok()?
+        .modified()
+        .ok()?
+        .elapsed()
+        .unwrap_or_default();
+    let context = serde_json::from_str(&content)
+        .inspect_err(|e| warn!("Skipping the unparseable thread context of {status_id}: {e}"))
+        .ok()?;
+    Some((context, age))
+}
+
+/// Read a status file along with the time it was written
+async fn read_status_file(id: &str, path: &Path) -> Option<(Status, DateTime<Utc>)> {
+    let content = tokio::fs::read_to_string(path)
+        .await
+        .inspect_err(|e| warn!("Unable to read {}: {e}", path.display()))
+        .ok()?;
+    let written_at = tokio::fs::metadata(path)
+        .await
+        .and_then(|metadata| metadata.modified())
+        .map(DateTime::<Utc>::from)
+        .unwrap_or_else(|_| Utc::now());
+    parse_cached_status(id, &content)
+        .filter(|status| status.id == id)
+        .map(|status| (status, written_at))
 }
 
 /// Deserialize a cached status, skipping it if the on-disk JSON no longer
@@ -46,6 +322,11 @@ pub struct StatusServiceImpl {
     mastodon_client: Arc<MastodonClient>,
     recent_repository: Arc<dyn RecentStatusRepository>,
     index_repository: Arc<dyn StatusIndexRepository>,
+    hot_ranker: HotRanker,
+    metrics: Arc<Metrics>,
+    statuses_dir: PathBuf,
+    repairs: StatusRepairs,
+    write_locks: Arc<WriteLocks>,
 }
 
 impl StatusServiceImpl {
@@ -53,26 +334,62 @@ impl StatusServiceImpl {
         mastodon_client: Arc<MastodonClient>,
         recent_repository: Arc<dyn RecentStatusRepository>,
         index_repository: Arc<dyn StatusIndexRepository>,
+        hot_ranker: HotRanker,
+        metrics: Arc<Metrics>,
+        statuses_dir: PathBuf,
     ) -> Self {
         Self {
             mastodon_client,
             recent_repository,
             index_repository,
+            hot_ranker,
+            metrics,
+            statuses_dir,
+            repairs: StatusRepairs::default(),
+            write_locks: Arc::default(),
         }
     }
 
+    /// Run a call of the index repository off the async runtime
+    async fn with_index<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&dyn StatusIndexRepository) -> Result<T, DbError> + Send + 'static,
+    ) -> Result<T, DbError> {
+        let repository = self.index_repository.clone();
+        unblock(move || f(repository.as_ref())).await
+    }
+
+    /// Run a call of the recent status repository off the async runtime
+    async fn with_recent<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&dyn RecentStatusRepository) -> Result<T, DbError> + Send + 'static,
+    ) -> Result<T, DbError> {
+        let repository = self.recent_repository.clone();
+        unblock(move || f(repository.as_ref())).await
+    }
+
+    /// Read the statuses from the storage, in order. The ones which cannot be
+    /// read are skipped and queued for a repair.
     async fn load_from_disk(&self, ids: Vec<String>) -> Result<Vec<Status>, StatusServiceError> {
         let mut statuses = Vec::new();
         for id in ids {
-            let dir = directory_for_status(&id);
-            let filepath = format!("{}/{}.json", dir, &id);
-            let mut file = File::open(filepath).await?;
-            let mut content = String::new();
-            file.read_to_string(&mut content).await?;
-            if let Some(status) = parse_cached_status(&id, content.as_str()) {
-                statuses.push(status);
+            let status = match tokio::fs::read_to_string(status_file(&self.statuses_dir, &id)).await
+            {
+                Ok(content) => parse_cached_status(&id, content.as_str()),
+                Err(e) => {
+                    warn!("Skipping unreadable cached status {id}: {e}");
+                    None
+                }
+            };
+            match status {
+                Some(status) => statuses.push(status),
+                None => {
+                    self.metrics.cached_status_read_failure();
+                    self.repairs.queue(&id);
+                }
             }
         }
+        self.metrics.status_repairs_pending(self.repairs.pending());
         debug!("{} statuses read from storage", statuses.len());
         Ok(statuses)
     }
@@ -81,9 +398,10 @@ impl StatusServiceImpl {
 #[async_trait]
 impl StatusService for StatusServiceImpl {
     async fn paginate_timeline(&self, hashtag: &String) -> Result<Vec<Status>, StatusServiceError> {
+        let key = hashtag.clone();
         match self
-            .recent_repository
-            .get_recent_status_id(hashtag.as_str())
+            .with_recent(move |repository| repository.get_recent_status_id(&key))
+            .await
             .unwrap_or(None)
         {
             None => {
@@ -92,8 +410,11 @@ impl StatusService for StatusServiceImpl {
                     .get_tag_timeline(&hashtag, None)
                     .await?;
                 if let Some(status) = statuses.last() {
-                    self.recent_repository
-                        .set_recent_status_id(&hashtag, &status.id)?;
+                    let (key, value) = (hashtag.clone(), status.id.clone());
+                    self.with_recent(move |repository| {
+                        repository.set_recent_status_id(&key, &value)
+                    })
+                    .await?;
                 }
                 Ok(statuses)
             }
@@ -121,9 +442,12 @@ impl StatusService for StatusServiceImpl {
                         hashtag,
                         last_id
                     );
-                    self.recent_repository
-                        .set_recent_status_id(&hashtag, &last_id)
-                        .expect("Unable to update the recent status ID locally");
+                    let (key, value) = (hashtag.clone(), last_id.clone());
+                    self.with_recent(move |repository| {
+                        repository.set_recent_status_id(&key, &value)
+                    })
+                    .await
+                    .expect("Unable to update the recent status ID locally");
                     statuses.extend(page)
                 }
                 statuses.sort_by_key(|status| Reverse((status.id.len(), status.id.clone())));
@@ -138,7 +462,7 @@ impl StatusService for StatusServiceImpl {
             let status = self.mastodon_client.get_status(id.clone()).await;
             match status {
                 Ok(v) => statuses.push(v),
-                Err(OwnError(err)) if err.status == Some(404) => {
+                Err(err) if matches!(err.as_ref(), OwnError(own) if own.status == Some(404)) => {
                     warn!("Status {} not found - probably deleted", id);
                 }
                 Err(err) => return Err(err.into()),
@@ -147,68 +471,148 @@ impl StatusService for StatusServiceImpl {
         Ok(statuses)
     }
 
-    async fn persist_statuses(&self, statuses: &Vec<Status>) -> Result<(), StatusServiceError> {
-        async fn write_status(status: Status, index_repository: Arc<dyn StatusIndexRepository>) {
-            let dir = directory_for_status(status.id.as_str());
-            if let Err(e) = create_dir_all(&dir).await {
-                warn!("Failed to create dir {dir} for status {}: {e}", status.id);
-                return;
-            }
-            let filepath = format!("{}/{}.json", dir, status.id);
-            let json = match serde_json::to_string(&status) {
-                Ok(json) => json,
-                Err(e) => {
-                    warn!("Failed to serialize status {}: {e}", status.id);
-                    return;
+    async fn thread_context(&self, id: &str) -> Result<Context, StatusServiceError> {
+        let cached = read_context_file(&self.statuses_dir, id).await;
+        if let Some((context, age)) = &cached
+            && *age < THREAD_CONTEXT_MAX_AGE
+        {
+            return Ok(context.clone());
+        }
+        match self
+            .mastodon_client
+            .get_status_context(id.to_string())
+            .await
+        {
+            Ok(context) => {
+                let path = context_file(&self.statuses_dir, id);
+                let written = async {
+                    create_dir_all(directory_for_status(&self.statuses_dir, id)).await?;
+                    let json = serde_json::to_string(&context)?;
+                    write_file_atomically(&path, json.as_bytes()).await
+                };
+                if let Err(e) = written.await {
+                    warn!("Unable to cache the thread context of status {id}: {e}");
                 }
-            };
-            let mut file = match File::create(&filepath).await {
-                Ok(file) => file,
-                Err(e) => {
-                    warn!("Failed to create file {filepath}: {e}");
-                    return;
+                Ok(context)
+            }
+            Err(e) => match cached {
+                Some((context, _)) => {
+                    warn!("Serving the stale thread context of status {id}: {e}");
+                    Ok(context)
                 }
-            };
-            if let Err(e) = file.write_all(json.as_bytes()).await {
-                warn!("Failed to write file {filepath}: {e}");
-                return;
+                None => Err(e.into()),
+            },
+        }
+    }
+
+    async fn reset_cursor(&self, hashtag: &str) -> Result<bool, StatusServiceError> {
+        let hashtag = hashtag.to_string();
+        Ok(self
+            .with_recent(move |repository| repository.delete_recent_status_id(&hashtag))
+            .await?)
+    }
+
+    async fn persist_statuses(
+        &self,
+        statuses: &[Status],
+    ) -> Result<PersistReport, StatusServiceError> {
+        type Written = Result<(Id, (Status, Result<(), io::Error>)), JoinError>;
+
+        let mut report = PersistReport::default();
+        let mut written = Vec::with_capacity(statuses.len());
+        // A task which panicked no longer has its status, only its ID
+        let mut pending: HashMap<Id, String> = HashMap::with_capacity(statuses.len());
+        let mut collect = |task: Written, pending: &mut HashMap<Id, String>| match task {
+            Ok((id, (status, Ok(())))) => {
+                pending.remove(&id);
+                written.push(status);
             }
-            if let Err(e) = index_repository.insert_statuses(vec![&status]) {
-                warn!("Failed to index status {}: {e}", status.id);
+            Ok((id, (status, Err(e)))) => {
+                pending.remove(&id);
+                warn!("Failed to write the file of status {}: {e}", status.id);
+                report.failed.push(PersistFailure {
+                    status_id: status.id,
+                    error: format!("Unable to write the file: {e}"),
+                });
+            }
+            Err(e) => {
+                let status_id = pending.remove(&e.id()).unwrap_or_default();
+                warn!("Failed to write the file of status {status_id}: {e}");
+                report.failed.push(PersistFailure {
+                    status_id,
+                    error: format!("Unable to write the file: {e}"),
+                });
+            }
+        };
+        let mut tasks = JoinSet::new();
+        for status in statuses.iter().cloned() {
+            if tasks.len() >= MAX_CONCURRENT_WRITES
+                && let Some(task) = tasks.join_next_with_id().await
+            {
+                collect(task, &mut pending);
             }
+            let (root, locks) = (self.statuses_dir.clone(), self.write_locks.clone());
+            let status_id = status.id.clone();
+            let task = tasks.spawn(async move {
+                let result = write_status_file(&root, &locks, &status).await;
+                (status, result)
+            });
+            pending.insert(task.id(), status_id);
+        }
+        while let Some(task) = tasks.join_next_with_id().await {
+            collect(task, &mut pending);
         }
 
-        let mut tasks = Vec::with_capacity(statuses.len());
-        for status in statuses.iter() {
-            tasks.push(tokio::spawn(write_status(
-                status.clone(),
-                self.index_repository.clone(),
-            )))
+        if written.is_empty() {
+            return Ok(report);
         }
-        for task in tasks {
-            task.await?;
+        Ok(self
+            .with_index(move |repository| {
+                index_statuses(repository, written, &mut report);
+                Ok(report)
+            })
+            .await?)
+    }
+
+    async fn load_statuses(&self, ids: Vec<String>) -> Result<Vec<Status>, StatusServiceError> {
+        // Unlike the indexed statuses, the requested ones may no longer be stored
+        let mut stored = Vec::with_capacity(ids.len());
+        for id in ids {
+            if tokio::fs::try_exists(status_file(&self.statuses_dir, &id)).await? {
+                stored.push(id);
+            }
         }
-        Ok(())
+        self.load_from_disk(stored).await
     }
 
     async fn retrieve_statuses(
         &self,
         hashtags: Option<&Vec<String>>,
+        media: MediaFilter,
         limit: u16,
     ) -> Result<Vec<Status>, StatusServiceError> {
-        let status_ids = self.index_repository.search_statuses(hashtags, limit)?;
+        let hashtags = hashtags.cloned();
+        let status_ids = self
+            .with_index(move |repository| {
+                repository.search_statuses(hashtags.as_ref(), media, limit)
+            })
+            .await?;
         self.load_from_disk(status_ids).await
     }
 
     async fn popular_statuses(
         &self,
         hashtags: Option<&Vec<String>>,
+        media: MediaFilter,
         since: DateTime<Utc>,
         limit: u16,
     ) -> Result<Vec<Status>, StatusServiceError> {
+        let hashtags = hashtags.cloned();
         let status_ids = self
-            .index_repository
-            .popular_statuses(hashtags, since, limit)?;
+            .with_index(move |repository| {
+                repository.popular_statuses(hashtags.as_ref(), media, since, limit)
+            })
+            .await?;
         self.load_from_disk(status_ids).await
     }
 
@@ -218,26 +622,358 @@ impl StatusService for StatusServiceImpl {
         fresh_since: DateTime<Utc>,
         limit: u16,
     ) -> Result<Vec<String>, StatusServiceError> {
-        self.index_repository
-            .list_stale_statuses(since, fresh_since, limit)
-            .map_err(|e| e.into())
+        Ok(self
+            .with_index(move |repository| repository.list_stale_statuses(since, fresh_since, limit))
+            .await?)
     }
 
-    fn popular_tags(
+    async fn popular_tags(
         &self,
+        hashtags: &[String],
         periods: Vec<u16>,
         limit: u16,
     ) -> Result<HashMap<u16, Vec<(String, u32)>>, StatusServiceError> {
-        periods
-            .iter()
-            .map(|&period| Ok((period, self.index_repository.popular_tags(&period, &limit)?)))
-            .collect()
+        let hashtags = hashtags.to_vec();
+        Ok(self
+            .with_index(move |repository| {
+                periods
+                    .iter()
+                    .map(|&period| {
+                        Ok((
+                            period,
+                            repository.popular_tags(Some(&hashtags), &period, &limit)?,
+                        ))
+                    })
+                    .collect()
+            })
+            .await?)
+    }
+
+    async fn display_names(
+        &self,
+        hashtags: Vec<String>,
+    ) -> Result<Vec<String>, StatusServiceError> {
+        let names = hashtags.clone();
+        let casings = self
+            .with_index(move |repository| repository.preferred_casings(&names))
+            .await?;
+        Ok(hashtags
+            .into_iter()
+            .map(|name| casings.get(&name.to_lowercase()).cloned().unwrap_or(name))
+            .collect())
+    }
+
+    async fn rising_tags(
+        &self,
+        hashtags: &[String],
+        window: Duration,
+        baseline: Duration,
+        min_count: u32,
+        min_ratio: f64,
+        limit: u16,
+    ) -> Result<Vec<RisingTag>, StatusServiceError> {
+        let since = Utc::now() - window;
+        let hashtags = hashtags.to_vec();
+        Ok(self
+            .with_index(move |repository| {
+                repository.rising_tags(
+                    Some(&hashtags),
+                    since,
+                    since - baseline,
+                    min_count,
+                    min_ratio,
+                    limit,
+                )
+            })
+            .await?)
+    }
+
+    async fn related_tags(
+        &self,
+        hashtags: &[String],
+        window: Duration,
+        min_count: u32,
+        limit: u16,
+    ) -> Result<RelatedTags, StatusServiceError> {
+        let since = Utc::now() - window;
+        let cooccurrences = self
+            .with_index(move |repository| repository.tag_cooccurrences(since, min_count))
+            .await?;
+        Ok(RelatedTags {
+            suggestions: cooccurrence::suggest_tags(&cooccurrences, hashtags, limit.into()),
+            related: cooccurrence::related_tags(&cooccurrences, hashtags, limit.into()),
+        })
+    }
+
+    async fn hot_statuses(
+        &self,
+        hashtags: Option<&Vec<String>>,
+        media: MediaFilter,
+        limit: u16,
+    ) -> Result<Vec<Status>, StatusServiceError> {
+        let now = Utc::now();
+        let (hashtags, since) = (hashtags.cloned(), self.hot_ranker.since(now));
+        let candidates = self
+            .with_index(move |repository| {
+                repository.list_ranking_candidates(hashtags.as_ref(), media, since)
+            })
+            .await?;
+        let status_ids = self.hot_ranker.rank(candidates, now, limit);
+        self.load_from_disk(status_ids).await
+    }
+
+    async fn fastest_growing_statuses(
+        &self,
+        hashtags: Option<&Vec<String>>,
+        media: MediaFilter,
+        since: DateTime<Utc>,
+        limit: u16,
+    ) -> Result<Vec<Status>, StatusServiceError> {
+        let hashtags = hashtags.cloned();
+        let status_ids = self
+            .with_index(move |repository| {
+                repository.engagement_velocity(hashtags.as_ref(), media, since, limit)
+            })
+            .await?
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        self.load_from_disk(status_ids).await
+    }
+
+    async fn engagement_history(
+        &self,
+        id: &str,
+    ) -> Result<Vec<EngagementSnapshot>, StatusServiceError> {
+        let id = id.to_string();
+        Ok(self
+            .with_index(move |repository| repository.engagement_history(&id))
+            .await?)
+    }
+
+    async fn compact_engagements(
+        &self,
+        created_before: DateTime<Utc>,
+        resolution: Duration,
+    ) -> Result<usize, StatusServiceError> {
+        Ok(self
+            .with_index(move |repository| {
+                repository.compact_engagements(created_before, resolution)
+            })
+            .await?)
+    }
+
+    async fn delete_status(&self, id: &str) -> Result<bool, StatusServiceError> {
+        let removed = match tokio::fs::remove_file(status_file(&self.statuses_dir, id)).await {
+            Ok(()) => true,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => false,
+            Err(e) => return Err(e.into()),
+        };
+        for path in [
+            history_file(&self.statuses_dir, id),
+            context_file(&self.statuses_dir, id),
+        ] {
+            match tokio::fs::remove_file(path).await {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        let id = id.to_string();
+        let deleted = self
+            .with_index(move |repository| repository.delete_status(&id))
+            .await?;
+        Ok(removed || deleted)
+    }
+
+    async fn status_history(&self, id: &str) -> Result<Vec<Status>, StatusServiceError> {
+        Ok(read_history_file(&self.statuses_dir, id).await?)
+    }
+
+    async fn index_stats(&self) -> Result<IndexStats, StatusServiceError> {
+        Ok(self
+            .with_index(|repository| repository.index_stats())
+            .await?)
+    }
+
+    async fn restore_statuses(
+        &self,
+        statuses: Vec<(Status, DateTime<Utc>)>,
+    ) -> Result<usize, StatusServiceError> {
+        for (status, _) in &statuses {
+            write_status_file(&self.statuses_dir, &self.write_locks, status).await?;
+        }
+        let count = statuses.len();
+        self.with_index(move |repository| {
+            repository.reindex_statuses(
+                statuses
+                    .iter()
+                    .map(|(status, refreshed_at)| (status, *refreshed_at))
+                    .collect(),
+            )
+        })
+        .await?;
+        Ok(count)
+    }
+
+    async fn reindex(&self, batch_size: usize) -> Result<ReindexReport, StatusServiceError> {
+        let files = list_status_files(&self.statuses_dir).await?;
+        let mut report = ReindexReport {
+            files: files.len(),
+            ..Default::default()
+        };
+        for batch in files.chunks(batch_size.max(1)) {
+            let mut statuses = Vec::with_capacity(batch.len());
+            for (id, path) in batch {
+                match read_status_file(id, path).await {
+                    Some(status) => statuses.push(status),
+                    None => report.unparseable.push(path.display().to_string()),
+                }
+            }
+            report.indexed += statuses.len();
+            self.with_index(move |repository| {
+                repository.reindex_statuses(
+                    statuses
+                        .iter()
+                        .map(|(status, written_at)| (status, *written_at))
+                        .collect(),
+                )
+            })
+            .await?;
+            debug!("Reindexed {}/{} status files", report.indexed, report.files);
+        }
+        Ok(report)
+    }
+
+    async fn verify_index(&self) -> Result<IndexConsistency, StatusServiceError> {
+        let stored: HashSet<String> = list_status_files(&self.statuses_dir)
+            .await?
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        let indexed: HashSet<String> = self
+            .with_index(|repository| repository.list_status_ids())
+            .await?
+            .into_iter()
+            .collect();
+        let sorted = |ids: Vec<&String>| {
+            let mut ids: Vec<String> = ids.into_iter().cloned().collect();
+            ids.sort_by_key(|id| (id.len(), id.clone()));
+            ids
+        };
+        Ok(IndexConsistency {
+            missing_files: sorted(indexed.difference(&stored).collect()),
+            unindexed_files: sorted(stored.difference(&indexed).collect()),
+        })
+    }
+
+    async fn recover(&self) -> Result<RecoveryReport, StatusServiceError> {
+        let mut report = RecoveryReport::default();
+        for path in list_files(&self.statuses_dir).await? {
+            if !is_temp_file(&path) || !is_stale(&path).await {
+                continue;
+            }
+            match tokio::fs::remove_file(&path).await {
+                Ok(()) => report.temp_files += 1,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        // Done once on the databases which had statuses when the attachment
+        // types started to be indexed, see the `index_backfills` migrations
+        if !self
+            .with_index(|repository| repository.is_backfilled(ATTACHMENTS_BACKFILL))
+            .await?
+        {
+            report.reindexed = self.reindex(BACKFILL_BATCH_SIZE).await?.indexed;
+            self.with_index(|repository| repository.mark_backfilled(ATTACHMENTS_BACKFILL))
+                .await?;
+        }
+
+        let consistency = self.verify_index().await?;
+        let mut statuses = Vec::with_capacity(consistency.unindexed_files.len());
+        for id in &consistency.unindexed_files {
+            let path = status_file(&self.statuses_dir, id);
+            match read_status_file(id, &path).await {
+                Some(status) => statuses.push(status),
+                None => report.unparseable.push(path.display().to_string()),
+            }
+        }
+        report.indexed = statuses.len();
+        if !statuses.is_empty() {
+            self.with_index(move |repository| {
+                repository.reindex_statuses(
+                    statuses
+                        .iter()
+                        .map(|(status, written_at)| (status, *written_at))
+                        .collect(),
+                )
+            })
+            .await?;
+        }
+        for id in &consistency.missing_files {
+            if self.repairs.queue(id) {
+                report.queued_repairs += 1;
+            }
+        }
+        self.metrics.status_repairs_pending(self.repairs.pending());
+        Ok(report)
+    }
+
+    fn pending_repairs(&self) -> usize {
+        self.repairs.pending()
+    }
+
+    async fn repair_statuses(&self, limit: usize) -> Result<RepairReport, StatusServiceError> {
+        // The statuses not repaired are queued again the next time they are read
+        let ids = self.repairs.take(limit);
+        self.metrics.status_repairs_pending(self.repairs.pending());
+        let mut report = RepairReport::default();
+        if ids.is_empty() {
+            return Ok(report);
+        }
+        let fetched = async {
+            let statuses = self.fetch_statuses(&ids).await?;
+            let persisted = self.persist_statuses(&statuses).await?.persisted;
+            Ok::<_, StatusServiceError>((statuses, persisted))
+        }
+        .await;
+        let (statuses, persisted) = match fetched {
+            Ok(fetched) => fetched,
+            Err(e) => {
+                // Not the fault of the statuses, try them again next time
+                for id in &ids {
+                    self.repairs.queue(id);
+                }
+                self.metrics.status_repairs_pending(self.repairs.pending());
+                return Err(e);
+            }
+        };
+        for id in &ids {
+            if persisted.contains(id) {
+                report.repaired += 1;
+            } else if statuses.iter().any(|status| &status.id == id) {
+                report.failed += 1;
+            } else {
+                self.delete_status(id).await?;
+                report.deleted += 1;
+            }
+        }
+        self.metrics.status_repairs("repaired", report.repaired);
+        self.metrics.status_repairs("deleted", report.deleted);
+        self.metrics.status_repairs("failed", report.failed);
+        Ok(report)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::infrastructure::database::sqlite;
+    use crate::infrastructure::repositories::status::{
+        RecentStatusSqliteRepository, StatusSqliteRepository,
+    };
+    use megalodon::entities::{Attachment, attachment::AttachmentType};
 
     #[test]
     fn parse_cached_status_accepts_current_schema() {
@@ -255,4 +991,221 @@ mod tests {
     fn parse_cached_status_skips_invalid_json() {
         assert!(parse_cached_status("1", "{ not valid json").is_none());
     }
+
+    #[actix_web::test]
+    async fn list_status_files_walks_the_tree() {
+        let root = std::env::temp_dir().join(format!("statuses-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("0/0")).unwrap();
+        std::fs::create_dir_all(root.join("1/11")).unwrap();
+        std::fs::write(root.join("0/0/12.json"), "{}").unwrap();
+        std::fs::write(root.join("1/11/1100000000000000000.json"), "{}").unwrap();
+        std::fs::write(root.join("0/0/notes.txt"), "").unwrap();
+
+        let ids: Vec<String> = list_status_files(&root)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(ids, vec!["12", "1100000000000000000"]);
+
+        assert!(list_status_files(&root).await.unwrap().is_empty());
+    }
+
+    fn test_service(root: &Path) -> StatusServiceImpl {
+        test_service_on(root, Arc::new(sqlite::in_memory().unwrap()))
+    }
+
+    fn test_service_on(root: &Path, pool: Arc<sqlite::Connection>) -> StatusServiceImpl {
+        let metrics = Arc::new(Metrics::new().unwrap());
+        StatusServiceImpl::new(
+            Arc::new(
+                MastodonClient::new("https://localhost".into(), None, metrics.clone()).unwrap(),
+            ),
+            Arc::new(RecentStatusSqliteRepository::new(pool.clone())),
+            Arc::new(StatusSqliteRepository::new(pool)),
+            HotRanker::new(Default::default()),
+            metrics,
+            root.to_path_buf(),
+        )
+    }
+
+    /// Status with an image, like the statuses fetched from the timelines
+    fn test_status(id: &str) -> Status {
+        let status: Status =
+            serde_json::from_str(include_str!("testdata/status_current.json")).unwrap();
+        Status {
+            id: id.to_string(),
+            media_attachments: vec![Attachment {
+                id: "1".to_string(),
+                r#type: AttachmentType::Image,
+                url: "https://example.com/media".to_string(),
+                remote_url: None,
+                preview_url: None,
+                text_url: None,
+                meta: None,
+                description: None,
+                blurhash: None,
+            }],
+            ..status
+        }
+    }
+
+    #[actix_web::test]
+    async fn persist_statuses_reports_the_statuses_not_written() {
+        let root = std::env::temp_dir().join(format!("persisted-{}", std::process::id()));
+        let service = test_service(&root);
+        let statuses: Vec<Status> = (1..=20)
+            .map(|i| test_status(&format!("{}000000000000000000", i)))
+            .collect();
+        // The directory of the second status cannot be created
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("2"), "").unwrap();
+
+        let report = service.persist_statuses(&statuses).await.unwrap();
+        let stats = service.index_stats().await.unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(report.persisted.len(), 19);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].status_id, "2000000000000000000");
+        assert_eq!(stats.statuses, 19);
+    }
+
+    #[actix_web::test]
+    async fn recover_cleans_up_and_reconciles_the_index() {
+        let root = std::env::temp_dir().join(format!("recovered-{}", std::process::id()));
+        let service = test_service(&root);
+        service
+            .persist_statuses(&[test_status("11"), test_status("12")])
+            .await
+            .unwrap();
+        // A status stored but not indexed, and one indexed but not stored
+        service
+            .with_index(|repository| repository.delete_status("11"))
+            .await
+            .unwrap();
+        std::fs::remove_file(status_file(&root, "12")).unwrap();
+        let stale = root.join("0/0/13.json.0000000a.tmp");
+        std::fs::File::create(&stale)
+            .unwrap()
+            .set_modified(std::time::SystemTime::now() - STALE_TEMP_FILE_AGE)
+            .unwrap();
+        let recent = root.join("0/0/13.json.0000000b.tmp");
+        std::fs::write(&recent, "{").unwrap();
+
+        let report = service.recover().await.unwrap();
+        let consistency = service.verify_index().await.unwrap();
+        let kept = recent.exists();
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(report.temp_files, 1);
+        assert_eq!(report.indexed, 1);
+        assert_eq!(report.queued_repairs, 1);
+        assert!(kept);
+        assert_eq!(consistency.missing_files, vec!["12"]);
+        assert!(consistency.unindexed_files.is_empty());
+        assert_eq!(service.pending_repairs(), 1);
+    }
+
+    #[actix_web::test]
+    async fn recover_records_the_attachments_of_older_statuses() {
+        let root = std::env::temp_dir().join(format!("backfilled-{}", std::process::id()));
+        let pool = Arc::new(sqlite::in_memory().unwrap());
+        // Migrated while it had statuses
+        pool.get()
+            .unwrap()
+            .execute("DELETE FROM index_backfills", [])
+            .unwrap();
+        let service = test_service_on(&root, pool);
+        let mut status = test_status("31");
+        status.media_attachments[0].r#type = AttachmentType::Video;
+        write_status_file(&root, &WriteLocks::default(), &status)
+            .await
+            .unwrap();
+        // Indexed before the attachment types were
+        let indexed = Status {
+            media_attachments: vec![],
+            ..status.clone()
+        };
+        service
+            .with_index(move |repository| repository.insert_statuses(vec![&indexed]))
+            .await
+            .unwrap();
+
+        let images = || {
+            service
+                .with_index(|repository| repository.search_statuses(None, MediaFilter::Images, 10))
+        };
+        let before = images().await.unwrap();
+        let report = service.recover().await.unwrap();
+        let after = images().await.unwrap();
+        let again = service.recover().await.unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(before, vec!["31"]);
+        assert_eq!(report.reindexed, 1);
+        assert_eq!(report.indexed, 0);
+        assert!(after.is_empty());
+        assert_eq!(again.reindexed, 0);
+    }
+
+    #[actix_web::test]
+    async fn edits_keep_the_previous_versions() {
+        let root = std::env::temp_dir().join(format!("edited-{}", std::process::id()));
+        let service = test_service(&root);
+        let original = test_status("21");
+        let edit = |content: &str, minutes: i64| Status {
+            content: content.to_string(),
+            edited_at: Some(original.created_at + chrono::Duration::minutes(minutes)),
+            ..original.clone()
+        };
+        let first_edit = edit("first edit", 1);
+        service
+            .persist_statuses(std::slice::from_ref(&original))
+            .await
+            .unwrap();
+        for _ in 0..2 {
+            service
+                .persist_statuses(std::slice::from_ref(&first_edit))
+                .await
+                .unwrap();
+        }
+        service
+            .persist_statuses(&[edit("second edit", 2)])
+            .await
+            .unwrap();
+
+        let history = service.status_history("21").await.unwrap();
+        service.delete_status("21").await.unwrap();
+        let deleted = service.status_history("21").await.unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(history, vec![original, first_edit]);
+        assert!(deleted.is_empty());
+    }
+
+    #[actix_web::test]
+    async fn thread_context_falls_back_to_the_stale_cache() {
+        let root = std::env::temp_dir().join(format!("threads-{}", std::process::id()));
+        let service = test_service(&root);
+        let context = Context {
+            ancestors: vec![test_status("31")],
+            descendants: vec![test_status("33")],
+        };
+        let path = context_file(&root, "32");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, serde_json::to_string(&context).unwrap()).unwrap();
+        let cached = service.thread_context("32").await.unwrap();
+        // The Mastodon API of the test service cannot be reached
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(std::time::SystemTime::now() - THREAD_CONTEXT_MAX_AGE)
+            .unwrap();
+        let stale = service.thread_context("32").await.unwrap();
+        let missing = service.thread_context("34").await;
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(cached, context);
+        assert_eq!(stale, context);
+        assert!(missing.is_err());
+    }
 }
@@ -0,0 +1,652 @@
+use crate::domain::models::user::{OAuthApp, PendingLogin, User, UserPreferences};
+use crate::domain::repositories::user::UserRepository;
+use crate::domain::services::user::{Login, UserService, UserServiceError};
+use crate::infrastructure::database::unblock;
+use crate::infrastructure::error::DbError;
+use crate::infrastructure::services::instance::InstanceClient;
+use crate::settings::AccountSettings;
+use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::{Duration, Utc};
+use log::{debug, info, warn};
+use rand::Rng;
+use std::net::{IpAddr, ToSocketAddrs};
+use std::sync::Arc;
+use url::{Host, Url};
+
+/// Scopes requested to the instances of the users, to identify them and to
+/// favourite, boost and reply to statuses on their behalf
+const SCOPES: [&str; 4] = [
+    "read:accounts",
+    "read:search",
+    "write:favourites",
+    "write:statuses",
+];
+
+/// Time given to the user to authorize the application on their instance
+const LOGIN_TIMEOUT_MINUTES: i64 = 30;
+
+/// Turn what a visitor typed as their instance (`mastodon.social`,
+/// `@user@mastodon.social`, `https://mastodon.social/`) into its base URL.
+/// Only instances served over https on a public name are accepted, unless
+/// `allow_private` is set to sign in against a local server.
+fn instance_url(input: &str, allow_private: bool) -> Result<String, UserServiceError> {
+    let input = input.trim();
+    let input = match input.rsplit_once('@') {
+        Some((_, host)) if !input.contains("://") => host,
+        _ => input,
+    };
+    let url = if input.contains("://") {
+        Url::parse(input)
+    } else {
+        Url::parse(&format!("https://{}", input))
+    }
+    .map_err(|_| UserServiceError::InvalidInstance)?;
+
+    let public = url.scheme() == "https"
+        && url.port().is_none()
+        && matches!(url.host(), Some(Host::Domain(domain)) if is_public_domain(domain));
+    if !public && !allow_private {
+        return Err(UserServiceError::InvalidInstance);
+    }
+    match (url.scheme(), url.host_str()) {
+        ("https" | "http", Some(host)) if !host.is_empty() => Ok(match url.port() {
+            Some(port) => format!("{}://{}:{}", url.scheme(), host, port),
+            None => format!("{}://{}", url.scheme(), host),
+        }),
+        _ => Err(UserServiceError::InvalidInstance),
+    }
+}
+
+/// Whether a domain may be served on the internet, as opposed to the names
+/// which only resolve on a local network
+fn is_public_domain(domain: &str) -> bool {
+    let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+    domain.contains('.')
+        && !["localhost", "local", "internal", "lan", "home.arpa"]
+            .iter()
+            .any(|local| domain == *local || domain.ends_with(&format!(".{}", local)))
+}
+
+/// Whether an address can be reached from the internet
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || a == 0
+                // Shared address space of the carrier-grade NATs
+                || (a == 100 && b & 0xc0 == 64))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_ip(IpAddr::V4(ip)),
+            None => {
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local())
+            }
+        },
+    }
+}
+
+/// Refuse the instances whose name resolves to a local or private address,
+/// as the application sends requests to them
+async fn check_public_addresses(instance: &str) -> Result<(), UserServiceError> {
+    let host = Url::parse(instance)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .ok_or(UserServiceError::InvalidInstance)?;
+    let addresses = tokio::task::spawn_blocking(move || {
+        (host.as_str(), 443)
+            .to_socket_addrs()
+            .map(|addresses| addresses.map(|address| address.ip()).collect::<Vec<_>>())
+    })
+    .await
+    .map_err(|_| UserServiceError::InvalidInstance)?
+    .map_err(|_| UserServiceError::InvalidInstance)?;
+    if addresses.is_empty() || !addresses.into_iter().all(is_public_ip) {
+        return Err(UserServiceError::InvalidInstance);
+    }
+    Ok(())
+}
+
+/// Full address of an account, as `acct` is relative to the instance for
+/// its local accounts
+pub(crate) fn full_acct(acct: &str, instance: &str) -> String {
+    match (acct.contains('@'), Url::parse(instance)) {
+        (false, Ok(url)) => format!("{}@{}", acct, url.host_str().unwrap_or_default()),
+        _ => acct.to_string(),
+    }
+}
+
+/// Status IDs end up in the path of the cached files, only accept the
+/// numeric IDs used by Mastodon
+pub(crate) fn check_status_id(id: &str) -> Result<(), UserServiceError> {
+    if !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()) {
+        Ok(())
+    } else {
+        Err(UserServiceError::InvalidStatus)
+    }
+}
+
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::rng().fill(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+pub struct UserServiceImpl {
+    instance_client: Arc<InstanceClient>,
+    repository: Arc<dyn UserRepository>,
+    settings: AccountSettings,
+}
+
+impl UserServiceImpl {
+    pub fn new(
+        instance_client: Arc<InstanceClient>,
+        repository: Arc<dyn UserRepository>,
+        settings: AccountSettings,
+    ) -> Self {
+        Self {
+            instance_client,
+            repository,
+            settings,
+        }
+    }
+
+    /// Run a call of the repository off the async runtime
+    async fn with_repository<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&dyn UserRepository) -> Result<T, DbError> + Send + 'static,
+    ) -> Result<T, DbError> {
+        let repository = self.repository.clone();
+        unblock(move || f(repository.as_ref())).await
+    }
+
+    async fn get_or_register_app(
+        &self,
+        instance: &str,
+        redirect_uri: &str,
+    ) -> Result<OAuthApp, UserServiceError> {
+        let scopes = SCOPES.join(" ");
+        let (key, uri, app_scopes) = (
+            instance.to_string(),
+            redirect_uri.to_string(),
+            scopes.clone(),
+        );
+        if let Some(app) = self
+            .with_repository(move |repository| repository.get_app(&key, &uri, &app_scopes))
+            .await?
+        {
+            return Ok(app);
+        }
+        // Anyone can start a sign-in, bound the apps registered on their behalf
+        let registered = self
+            .with_repository(|repository| repository.count_apps())
+            .await?;
+        if registered >= self.settings.max_apps {
+            warn!(
+                "Not registering the application on {}, {} instances already",
+                instance, registered
+            );
+            return Err(UserServiceError::RegistrationClosed);
+        }
+        let data = self
+            .instance_client
+            .create_app(
+                instance,
+                &self.settings.client_name,
+                redirect_uri,
+                self.settings.website.clone(),
+                &SCOPES,
+            )
+            .await?;
+        info!("Application registered on {}", instance);
+        let app = OAuthApp {
+            client_id: data.client_id,
+            client_secret: data.client_secret,
+        };
+        let (key, uri, saved) = (instance.to_string(), redirect_uri.to_string(), app.clone());
+        self.with_repository(move |repository| repository.save_app(&key, &uri, &scopes, &saved))
+            .await?;
+        Ok(app)
+    }
+}
+
+#[async_trait]
+impl UserService for UserServiceImpl {
+    async fn start_login(
+        &self,
+        instance: &str,
+        return_to: &str,
+    ) -> Result<String, UserServiceError> {
+        let public_url = self
+            .settings
+            .public_url
+            .as_ref()
+            .ok_or(UserServiceError::SignInDisabled)?;
+        let redirect_uri = format!("{}/login/callback", public_url.trim_end_matches('/'));
+        let redirect_uri = redirect_uri.as_str();
+        let instance = instance_url(instance, self.settings.allow_private_instances)?;
+        if !self.settings.allow_private_instances {
+            check_public_addresses(&instance).await?;
+        }
+        let app = self.get_or_register_app(&instance, redirect_uri).await?;
+
+        let state = random_token();
+        let now = Utc::now();
+        let login = PendingLogin {
+            instance: instance.clone(),
+            redirect_uri: redirect_uri.to_string(),
+            return_to: return_to.to_string(),
+        };
+        let key = state.clone();
+        self.with_repository(move |repository| {
+            repository.save_login(
+                &key,
+                &login,
+                now,
+                now - Duration::minutes(LOGIN_TIMEOUT_MINUTES),
+            )
+        })
+        .await?;
+
+        let url = Url::parse_with_params(
+            &format!("{}/oauth/authorize", instance),
+            &[
+                ("response_type", "code"),
+                ("client_id", app.client_id.as_str()),
+                ("redirect_uri", redirect_uri),
+                ("scope", SCOPES.join(" ").as_str()),
+                ("state", state.as_str()),
+            ],
+        )
+        .map_err(|_| UserServiceError::InvalidInstance)?;
+        Ok(url.to_string())
+    }
+
+    async fn complete_login(&self, state: &str, code: &str) -> Result<Login, UserServiceError> {
+        let state = state.to_string();
+        let (login, app) = self
+            .with_repository(move |repository| {
+                let Some(login) = repository.take_login(
+                    &state,
+                    Utc::now() - Duration::minutes(LOGIN_TIMEOUT_MINUTES),
+                )?
+                else {
+                    return Ok(None);
+                };
+                let app =
+                    repository.get_app(&login.instance, &login.redirect_uri, &SCOPES.join(" "))?;
+                Ok(app.map(|app| (login, app)))
+            })
+            .await?
+            .ok_or(UserServiceError::InvalidLogin)?;
+
+        let access_token = self
+            .instance_client
+            .fetch_access_token(
+                &login.instance,
+                &app.client_id,
+                &app.client_secret,
+                code,
+                &login.redirect_uri,
+            )
+            .await?;
+        let account = self
+            .instance_client
+            .verify_credentials(&login.instance, &access_token)
+            .await?;
+
+        let session_id = random_token();
+        let now = Utc::now();
+        let lifetime = Duration::from_std(*self.settings.session_lifetime)
+            .unwrap_or_else(|_| Duration::days(30));
+        let (instance, session) = (login.instance.clone(), session_id.clone());
+        let (user_id, expired) = self
+            .with_repository(move |repository| {
+                let user_id = repository.upsert_user(
+                    &instance,
+                    &account.id,
+                    &full_acct(&account.acct, &instance),
+                    &account.display_name,
+                    &access_token,
+                )?;
+                repository.create_session(&session, user_id, now, now + lifetime)?;
+                Ok((user_id, repository.delete_expired_sessions(now)?))
+            })
+            .await?;
+        debug!(
+            "User {} signed in from {}, {} expired sessions deleted",
+            user_id, login.instance, expired
+        );
+
+        Ok(Login {
+            session_id,
+            return_to: login.return_to,
+        })
+    }
+
+    fn home_instance(&self, instance: &str) -> Result<String, UserServiceError> {
+        instance_url(instance, self.settings.allow_private_instances)
+    }
+
+    async fn current_user(&self, session_id: &str) -> Result<Option<User>, UserServiceError> {
+        let session_id = session_id.to_string();
+        Ok(self
+            .with_repository(move |repository| repository.get_session_user(&session_id, Utc::now()))
+            .await?)
+    }
+
+    async fn logout(&self, session_id: &str) -> Result<(), UserServiceError> {
+        let session_id = session_id.to_string();
+        Ok(self
+            .with_repository(move |repository| repository.delete_session(&session_id))
+            .await?)
+    }
+
+    async fn preferences(
+        &self,
+        user: &User,
+        feed: &str,
+    ) -> Result<UserPreferences, UserServiceError> {
+        let (user_id, feed) = (user.id, feed.to_string());
+        Ok(self
+            .with_repository(move |repository| {
+                Ok(UserPreferences {
+                    followed_tags: repository
+                        .list_followed_tags(user_id, &feed)?
+                        .into_iter()
+                        .collect(),
+                    muted_accounts: repository
+                        .list_muted_accounts(user_id)?
+                        .into_iter()
+                        .collect(),
+                    hidden_statuses: repository
+                        .list_hidden_statuses(user_id)?
+                        .into_iter()
+                        .collect(),
+                    saved_statuses: repository
+                        .list_saved_statuses(user_id)?
+                        .into_iter()
+                        .collect(),
+                })
+            })
+            .await?)
+    }
+
+    async fn follow_tag(
+        &self,
+        user: &User,
+        feed: &str,
+        name: &str,
+        followed: bool,
+    ) -> Result<(), UserServiceError> {
+        let (user_id, feed, name) = (user.id, feed.to_string(), name.to_string());
+        Ok(self
+            .with_repository(move |repository| {
+                repository.set_followed_tag(user_id, &feed, &name, followed)
+            })
+            .await?)
+    }
+
+    async fn mute_account(
+        &self,
+        user: &User,
+        acct: &str,
+        muted: bool,
+    ) -> Result<(), UserServiceError> {
+        let acct = acct.trim().trim_start_matches('@');
+        if acct.is_empty() {
+            return Ok(());
+        }
+        let (user_id, acct) = (user.id, acct.to_string());
+        Ok(self
+            .with_repository(move |repository| repository.set_muted_account(user_id, &acct, muted))
+            .await?)
+    }
+
+    async fn hide_status(
+        &self,
+        user: &User,
+        id: &str,
+        hidden: bool,
+    ) -> Result<(), UserServiceError> {
+        check_status_id(id)?;
+        let (user_id, id) = (user.id, id.to_string());
+        Ok(self
+            .with_repository(move |repository| repository.set_hidden_status(user_id, &id, hidden))
+            .await?)
+    }
+
+    async fn save_status(
+        &self,
+        user: &User,
+        id: &str,
+        saved: bool,
+    ) -> Result<(), UserServiceError> {
+        check_status_id(id)?;
+        let (user_id, id) = (user.id, id.to_string());
+        Ok(self
+            .with_repository(move |repository| repository.set_saved_status(user_id, &id, saved))
+            .await?)
+    }
+
+    async fn saved_statuses(&self, user: &User) -> Result<Vec<String>, UserServiceError> {
+        let user_id = user.id;
+        Ok(self
+            .with_repository(move |repository| repository.list_saved_statuses(user_id))
+            .await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::database::sqlite;
+    use crate::infrastructure::repositories::user::UserSqliteRepository;
+    use actix_web::{App, HttpResponse, HttpServer, web};
+    use serde_json::json;
+
+    #[test]
+    fn instance_url_normalizes_addresses() {
+        for input in [
+            "mastodon.social",
+            " mastodon.social/ ",
+            "@someone@mastodon.social",
+            "https://mastodon.social/about",
+        ] {
+            assert_eq!(
+                instance_url(input, false).unwrap(),
+                "https://mastodon.social"
+            );
+        }
+        assert_eq!(
+            instance_url("http://127.0.0.1:3000", true).unwrap(),
+            "http://127.0.0.1:3000"
+        );
+        assert!(instance_url("ftp://mastodon.social", true).is_err());
+        assert!(instance_url("", true).is_err());
+    }
+
+    #[test]
+    fn instance_url_refuses_private_instances() {
+        for input in [
+            "http://mastodon.social",
+            "https://mastodon.social:8443",
+            "https://127.0.0.1",
+            "https://2130706433",
+            "https://[::1]",
+            "10.0.0.1",
+            "localhost",
+            "mastodon.localhost",
+            "intranet",
+            "mastodon.internal",
+        ] {
+            assert!(instance_url(input, false).is_err(), "{} accepted", input);
+        }
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:10.0.0.1",
+        ] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{} is public", ip);
+        }
+        assert!(is_public_ip("1.1.1.1".parse().unwrap()));
+        assert!(is_public_ip("2a00:1450::1".parse().unwrap()));
+    }
+
+    fn local_settings() -> AccountSettings {
+        AccountSettings {
+            public_url: Some("http://localhost".to_string()),
+            allow_private_instances: true,
+            ..Default::default()
+        }
+    }
+
+    /// Minimal stand-in for the OAuth and account endpoints of Mastodon
+    fn stand_in_instance(cfg: &mut web::ServiceConfig) {
+        cfg.route(
+            "/api/v1/apps",
+            web::post().to(|| async {
+                HttpResponse::Ok().json(json!({
+                    "id": "1",
+                    "name": "Media timeline",
+                    "website": null,
+                    "redirect_uri": "http://localhost/login/callback",
+                    "client_id": "client-id",
+                    "client_secret": "client-secret",
+                }))
+            }),
+        )
+        .route(
+            "/oauth/token",
+            web::post().to(|| async {
+                HttpResponse::Ok().json(json!({
+                    "access_token": "access-token",
+                    "token_type": "Bearer",
+                    "scope": "read:accounts read:search write:favourites write:statuses",
+                    "created_at": 1700000000,
+                }))
+            }),
+        )
+        .route(
+            "/api/v1/accounts/verify_credentials",
+            web::get().to(|| async {
+                HttpResponse::Ok().json(json!({
+                    "id": "42",
+                    "username": "painter",
+                    "acct": "painter",
+                    "display_name": "Painter",
+                    "locked": false,
+                    "created_at": "2024-01-01T00:00:00.000Z",
+                    "followers_count": 0,
+                    "following_count": 0,
+                    "statuses_count": 0,
+                    "note": "",
+                    "url": "http://127.0.0.1/@painter",
+                    "avatar": "",
+                    "avatar_static": "",
+                    "header": "",
+                    "header_static": "",
+                    "emojis": [],
+                    "fields": [],
+                    "bot": false,
+                }))
+            }),
+        );
+    }
+
+    #[actix_web::test]
+    async fn sign_in_against_a_local_instance() {
+        let server = HttpServer::new(|| App::new().configure(stand_in_instance))
+            .workers(1)
+            .bind(("127.0.0.1", 0))
+            .unwrap();
+        let instance = format!("http://{}", server.addrs()[0]);
+        let server = server.run();
+        let handle = server.handle();
+        actix_web::rt::spawn(server);
+
+        let pool = Arc::new(sqlite::in_memory().unwrap());
+        let service = UserServiceImpl::new(
+            Arc::new(InstanceClient::new(None)),
+            Arc::new(UserSqliteRepository::new(pool)),
+            local_settings(),
+        );
+
+        let authorize_url = service.start_login(&instance, "/f/terrain").await.unwrap();
+        let authorize_url = Url::parse(&authorize_url).unwrap();
+        assert_eq!(authorize_url.path(), "/oauth/authorize");
+        let state = authorize_url
+            .query_pairs()
+            .find(|(key, _)| key == "state")
+            .map(|(_, value)| value.to_string())
+            .unwrap();
+
+        let login = service.complete_login(&state, "code").await.unwrap();
+        assert_eq!(login.return_to, "/f/terrain");
+        let user = service
+            .current_user(&login.session_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(user.acct, "painter@127.0.0.1");
+        assert_eq!(user.access_token, "access-token");
+
+        // The state can only be used once
+        assert!(service.complete_login(&state, "code").await.is_err());
+
+        service.logout(&login.session_id).await.unwrap();
+        assert!(
+            service
+                .current_user(&login.session_id)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        handle.stop(true).await;
+    }
+
+    #[actix_web::test]
+    async fn app_registrations_are_bounded() {
+        let pool = Arc::new(sqlite::in_memory().unwrap());
+        let settings = AccountSettings {
+            max_apps: 0,
+            ..local_settings()
+        };
+        let service = UserServiceImpl::new(
+            Arc::new(InstanceClient::new(None)),
+            Arc::new(UserSqliteRepository::new(pool.clone())),
+            settings,
+        );
+        assert!(matches!(
+            service.start_login("http://127.0.0.1:1", "/").await,
+            Err(UserServiceError::RegistrationClosed)
+        ));
+
+        let service = UserServiceImpl::new(
+            Arc::new(InstanceClient::new(None)),
+            Arc::new(UserSqliteRepository::new(pool)),
+            AccountSettings::default(),
+        );
+        assert!(matches!(
+            service.start_login("mastodon.social", "/").await,
+            Err(UserServiceError::SignInDisabled)
+        ));
+    }
+}
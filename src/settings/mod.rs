@@ -1,5 +1,12 @@
+use crate::domain::models::feed::{DEFAULT_FEED, Feed, Feeds, MediaFilter};
+use crate::infrastructure::database::postgres;
+use actix_settings::BasicSettings;
 use duration::DurationValue;
 use serde::Deserialize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 pub mod duration;
 
@@ -10,10 +17,512 @@ pub struct StatusRefreshSettings {
     pub frequency: DurationValue,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct EngagementCompactionSettings {
+    pub min_age: DurationValue,
+    pub resolution: DurationValue,
+}
+
+/// Weights of the "hot" ranking. The score of a status is its weighted
+/// engagements, multiplied by the boost of its account, halved every
+/// `half-life` and divided by `(age in hours + 2) ^ gravity`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct HotRankingSettings {
+    pub replies_weight: f64,
+    pub reblogs_weight: f64,
+    pub favourites_weight: f64,
+    pub half_life: DurationValue,
+    pub gravity: f64,
+    /// Only statuses created within this window are ranked
+    pub max_age: DurationValue,
+    /// Score multipliers keyed by account `acct` (e.g. `user@example.social`)
+    pub account_boosts: HashMap<String, f64>,
+}
+
+impl Default for HotRankingSettings {
+    fn default() -> Self {
+        Self {
+            replies_weight: 1.0,
+            reblogs_weight: 1.0,
+            favourites_weight: 1.0,
+            half_life: Duration::from_secs(12 * 3600).into(),
+            gravity: 0.0,
+            max_age: Duration::from_secs(7 * 24 * 3600).into(),
+            account_boosts: HashMap::new(),
+        }
+    }
+}
+
+/// Statuses whose file is missing or unparseable are fetched again in the
+/// background, `batch-size` of them every `frequency`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct StatusRepairSettings {
+    pub frequency: DurationValue,
+    pub batch_size: u16,
+}
+
+impl Default for StatusRepairSettings {
+    fn default() -> Self {
+        Self {
+            frequency: Duration::from_secs(60).into(),
+            batch_size: 20,
+        }
+    }
+}
+
+/// Detection of hashtags used significantly more in the current `window` than
+/// on average over the preceding `baseline` period.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct RisingTagsSettings {
+    pub window: DurationValue,
+    pub baseline: DurationValue,
+    /// Minimum number of statuses in the current window
+    pub min_count: u32,
+    /// Minimum ratio between the current usage and the baseline usage
+    pub min_ratio: f64,
+    pub limit: u16,
+}
+
+impl Default for RisingTagsSettings {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_secs(24 * 3600).into(),
+            baseline: Duration::from_secs(7 * 24 * 3600).into(),
+            min_count: 5,
+            min_ratio: 2.0,
+            limit: 5,
+        }
+    }
+}
+
+/// Analysis of the hashtags used together over a rolling `window`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct TagCooccurrenceSettings {
+    pub window: DurationValue,
+    /// Minimum number of statuses using both hashtags
+    pub min_count: u32,
+    pub limit: u16,
+}
+
+impl Default for TagCooccurrenceSettings {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_secs(30 * 24 * 3600).into(),
+            min_count: 3,
+            limit: 10,
+        }
+    }
+}
+
+/// Sign-in of the visitors with the account of their own Mastodon instance.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct AccountSettings {
+    /// Name of the application registered on the instances of the users
+    pub client_name: String,
+    pub website: Option<String>,
+    pub session_lifetime: DurationValue,
+    /// Public address of the application, to which the instances redirect
+    /// after the sign-in. The sign-in is disabled when unset.
+    pub public_url: Option<String>,
+    /// Instances on which the application can be registered at most
+    pub max_apps: u32,
+    /// Accept instances on local or private addresses and over plain http,
+    /// to sign in against a development server
+    pub allow_private_instances: bool,
+}
+
+impl Default for AccountSettings {
+    fn default() -> Self {
+        Self {
+            client_name: "Media timeline".to_string(),
+            website: None,
+            session_lifetime: Duration::from_secs(30 * 24 * 3600).into(),
+            public_url: None,
+            max_apps: 1000,
+            allow_private_instances: false,
+        }
+    }
+}
+
+/// What to do with a worker whose run panicked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RestartPolicy {
+    Always,
+    /// Restart at most `max-restarts` times per `restart-window`
+    Limited,
+    Never,
+}
+
+/// Supervision of the background workers.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct WorkerSettings {
+    pub restart: RestartPolicy,
+    pub max_restarts: u32,
+    pub restart_window: DurationValue,
+    /// Random variation of the intervals between runs, as a fraction of them
+    pub jitter: f64,
+    /// The interval doubles after each consecutive failure, up to this delay
+    pub max_backoff: DurationValue,
+}
+
+impl Default for WorkerSettings {
+    fn default() -> Self {
+        Self {
+            restart: RestartPolicy::Limited,
+            max_restarts: 5,
+            restart_window: Duration::from_secs(3600).into(),
+            jitter: 0.1,
+            max_backoff: Duration::from_secs(3600).into(),
+        }
+    }
+}
+
+/// Location of the files used by the application, relative to the working
+/// directory unless absolute
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct PathSettings {
+    /// Stored statuses and, unless `database` is set, the SQLite database
+    pub data_dir: PathBuf,
+    pub database: Option<PathBuf>,
+    pub templates_dir: PathBuf,
+    pub static_dir: PathBuf,
+}
+
+impl Default for PathSettings {
+    fn default() -> Self {
+        Self {
+            data_dir: PathBuf::from("data"),
+            database: None,
+            templates_dir: PathBuf::from("templates"),
+            static_dir: PathBuf::from("static"),
+        }
+    }
+}
+
+impl PathSettings {
+    pub fn database(&self) -> PathBuf {
+        self.database
+            .clone()
+            .unwrap_or_else(|| self.data_dir.join("db.sqlite3"))
+    }
+
+    pub fn statuses_dir(&self) -> PathBuf {
+        self.data_dir.join("statuses")
+    }
+
+    /// Override the paths with the `DATA_DIR`, `DATABASE_PATH`,
+    /// `TEMPLATES_DIR` and `STATIC_DIR` environment variables
+    fn override_with_env(&mut self) {
+        let var = |name: &str| std::env::var_os(name).filter(|value| !value.is_empty());
+        if let Some(data_dir) = var("DATA_DIR") {
+            self.data_dir = data_dir.into();
+        }
+        if let Some(database) = var("DATABASE_PATH") {
+            self.database = Some(database.into());
+        }
+        if let Some(templates_dir) = var("TEMPLATES_DIR") {
+            self.templates_dir = templates_dir.into();
+        }
+        if let Some(static_dir) = var("STATIC_DIR") {
+            self.static_dir = static_dir.into();
+        }
+    }
+
+    /// Check that the directories exist, creating the data directory and the
+    /// one of the database when missing, and that those are writable
+    pub fn check(&self) -> Vec<String> {
+        let mut errors = vec![];
+        let database = self.database();
+        let database_dir = match database.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        for (name, dir) in [
+            ("paths.data-dir", self.data_dir.as_path()),
+            ("paths.database", database_dir),
+        ] {
+            if let Err(e) = check_writable(dir) {
+                errors.push(format!("{} {}: {}", name, dir.display(), e));
+            }
+        }
+        if database.is_dir() {
+            errors.push(format!(
+                "paths.database {} is a directory",
+                database.display()
+            ));
+        }
+        for (name, dir) in [
+            ("paths.templates-dir", &self.templates_dir),
+            ("paths.static-dir", &self.static_dir),
+        ] {
+            if !dir.is_dir() {
+                errors.push(format!("{} {} is not a directory", name, dir.display()));
+            }
+        }
+        errors
+    }
+}
+
+fn check_writable(dir: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let probe = dir.join(format!(".probe-{}", std::process::id()));
+    std::fs::write(&probe, b"")?;
+    std::fs::remove_file(&probe)
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct FeedSettings {
+    pub slug: String,
+    pub title: String,
+    #[serde(default)]
+    pub intro: String,
+    /// Overrides `timeline-statuses-count` for this feed
+    pub timeline_statuses_count: Option<u16>,
+    /// `images` to leave out the statuses with videos, animated GIFs or audio
+    #[serde(default)]
+    pub media: MediaFilter,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct ApplicationSettings {
     pub timeline_update_frequency: DurationValue,
     pub timeline_statuses_count: u16,
     pub status_refresh: Vec<StatusRefreshSettings>,
+    /// Feeds served under `/f/{slug}`, the `default` one being served at `/`
+    #[serde(default)]
+    pub feeds: Vec<FeedSettings>,
+    #[serde(default)]
+    pub engagement_compaction: Vec<EngagementCompactionSettings>,
+    #[serde(default)]
+    pub status_repair: StatusRepairSettings,
+    #[serde(default)]
+    pub hot_ranking: HotRankingSettings,
+    #[serde(default)]
+    pub rising_tags: RisingTagsSettings,
+    #[serde(default)]
+    pub tag_cooccurrence: TagCooccurrenceSettings,
+    #[serde(default)]
+    pub accounts: AccountSettings,
+    #[serde(default)]
+    pub workers: WorkerSettings,
+    #[serde(default)]
+    pub paths: PathSettings,
+    /// `postgres://` URL of the database shared by the replicas, holding the
+    /// hashtags and the status index instead of the SQLite one
+    #[serde(default)]
+    pub database_url: Option<String>,
+    /// Token granting access to the `/admin` routes, disabled when unset
+    #[serde(default)]
+    pub admin_token: Option<String>,
+}
+
+impl ApplicationSettings {
+    pub fn feeds(&self) -> Feeds {
+        let feed = |slug: &str, title: &str, intro: &str, count: Option<u16>, media| Feed {
+            slug: slug.to_string(),
+            title: title.to_string(),
+            intro: intro.to_string(),
+            timeline_statuses_count: count.unwrap_or(self.timeline_statuses_count),
+            media,
+            base_path: if slug == DEFAULT_FEED {
+                String::new()
+            } else {
+                format!("/f/{}", slug)
+            },
+        };
+        Feeds::new(
+            self.feeds
+                .iter()
+                .map(|f| {
+                    feed(
+                        &f.slug,
+                        &f.title,
+                        &f.intro,
+                        f.timeline_statuses_count,
+                        f.media,
+                    )
+                })
+                .collect(),
+            feed(DEFAULT_FEED, "Media timeline", "", None, MediaFilter::All),
+        )
+    }
+
+    /// Describe the values which would make the application misbehave
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = vec![];
+        if self.timeline_update_frequency.is_zero() {
+            errors.push("timeline-update-frequency must not be zero".to_string());
+        }
+        if self.timeline_statuses_count == 0 {
+            errors.push("timeline-statuses-count must not be zero".to_string());
+        }
+        for refresh in &self.status_refresh {
+            if refresh.frequency.is_zero() {
+                errors.push(format!(
+                    "status-refresh with max-age {:?} has a zero frequency",
+                    *refresh.max_age
+                ));
+            }
+        }
+        if self.status_repair.frequency.is_zero() || self.status_repair.batch_size == 0 {
+            errors.push("status-repair.frequency and batch-size must not be zero".to_string());
+        }
+        let mut slugs: Vec<&str> = vec![];
+        for feed in &self.feeds {
+            if feed.slug.is_empty()
+                || !feed
+                    .slug
+                    .bytes()
+                    .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+            {
+                errors.push(format!(
+                    "feed slug {:?} must only use lowercase letters, digits and dashes",
+                    feed.slug
+                ));
+            }
+            if slugs.contains(&feed.slug.as_str()) {
+                errors.push(format!("feed slug {:?} is used more than once", feed.slug));
+            }
+            slugs.push(&feed.slug);
+        }
+        if self.hot_ranking.half_life.is_zero() {
+            errors.push("hot-ranking.half-life must not be zero".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.workers.jitter) {
+            errors.push("workers.jitter must be between 0 and 1".to_string());
+        }
+        if let Some(website) = &self.accounts.website
+            && url::Url::parse(website).is_err()
+        {
+            errors.push(format!("accounts.website {:?} is not a valid URL", website));
+        }
+        if let Some(public_url) = &self.accounts.public_url
+            && !url::Url::parse(public_url).is_ok_and(|url| url.scheme().starts_with("http"))
+        {
+            errors.push(format!(
+                "accounts.public-url {:?} is not a valid http(s) URL",
+                public_url
+            ));
+        }
+        if let Some(url) = &self.database_url
+            && !postgres::is_postgres_url(url)
+        {
+            errors.push("database-url must start with postgres:// or postgresql://".to_string());
+        }
+        errors
+    }
+}
+
+/// Read the settings from the TOML file, then override them with the
+/// environment variables
+pub fn load(path: &Path) -> Result<BasicSettings<ApplicationSettings>, Box<dyn Error>> {
+    // `parse_toml` would write a default file, lacking the application settings
+    if !path.is_file() {
+        return Err(format!("The configuration file {} does not exist", path.display()).into());
+    }
+    let mut settings: BasicSettings<ApplicationSettings> = BasicSettings::parse_toml(path)
+        .map_err(|e| format!("Unable to read the configuration {}: {}", path.display(), e))?;
+
+    BasicSettings::<ApplicationSettings>::override_field_with_env_var(
+        &mut settings.actix.hosts,
+        "ACTIX_HOSTS",
+    )?;
+    BasicSettings::<ApplicationSettings>::override_field_with_env_var(
+        &mut settings.actix.mode,
+        "ACTIX_MODE",
+    )?;
+
+    if let Ok(token) = std::env::var("ADMIN_TOKEN") {
+        settings.application.admin_token = Some(token);
+    }
+    if let Some(url) = std::env::var("DATABASE_URL")
+        .ok()
+        .filter(|url| !url.is_empty())
+    {
+        settings.application.database_url = Some(url);
+    }
+    settings.application.paths.override_with_env();
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_reports_invalid_values() {
+        let feed = |slug: &str| FeedSettings {
+            slug: slug.to_string(),
+            title: slug.to_string(),
+            intro: String::new(),
+            timeline_statuses_count: None,
+            media: Default::default(),
+        };
+        let settings = ApplicationSettings {
+            timeline_update_frequency: Duration::from_secs(60).into(),
+            timeline_statuses_count: 20,
+            status_refresh: vec![],
+            feeds: vec![
+                feed("default"),
+                feed("minis"),
+                feed("minis"),
+                feed("Bad slug"),
+            ],
+            engagement_compaction: vec![],
+            status_repair: Default::default(),
+            hot_ranking: Default::default(),
+            rising_tags: Default::default(),
+            tag_cooccurrence: Default::default(),
+            accounts: Default::default(),
+            workers: WorkerSettings {
+                jitter: 2.0,
+                ..Default::default()
+            },
+            admin_token: None,
+            paths: Default::default(),
+            database_url: Some("sqlite://data/db.sqlite3".to_string()),
+        };
+        assert_eq!(
+            settings.validate(),
+            vec![
+                r#"feed slug "minis" is used more than once"#,
+                r#"feed slug "Bad slug" must only use lowercase letters, digits and dashes"#,
+                "workers.jitter must be between 0 and 1",
+                "database-url must start with postgres:// or postgresql://",
+            ]
+        );
+    }
+
+    #[test]
+    fn path_check_creates_the_data_dir_and_reports_missing_ones() {
+        let root = std::env::temp_dir().join(format!("paths-{}", std::process::id()));
+        let paths = PathSettings {
+            data_dir: root.join("data"),
+            database: Some(root.join("db/db.sqlite3")),
+            templates_dir: root.join("templates"),
+            static_dir: root.clone(),
+        };
+        let errors = paths.check();
+        let created = paths.data_dir.is_dir() && root.join("db").is_dir();
+        std::fs::remove_dir_all(&root).unwrap();
+        assert!(created);
+        assert_eq!(
+            errors,
+            vec![format!(
+                "paths.templates-dir {} is not a directory",
+                root.join("templates").display()
+            )]
+        );
+    }
 }
@@ -1,3 +1,4 @@
+pub mod repairs;
 pub mod statuses;
 pub mod timeline;
 pub mod tracker;
@@ -0,0 +1,52 @@
+use crate::container::Container;
+use crate::domain::models::command::STATUS_REPAIRER;
+use crate::domain::services::status::StatusService;
+use crate::workers::tracker::Worker;
+use async_trait::async_trait;
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Fetch again the statuses whose file could not be read by the timelines
+pub struct StatusRepairer {
+    frequency: Duration,
+    batch_size: usize,
+    status_service: Arc<dyn StatusService>,
+}
+
+impl StatusRepairer {
+    pub fn new(container: Arc<Container>) -> Self {
+        let settings = &container.settings.application.status_repair;
+        Self {
+            frequency: *settings.frequency,
+            batch_size: settings.batch_size.into(),
+            status_service: container.status_service.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl Worker for StatusRepairer {
+    fn name(&self) -> &'static str {
+        STATUS_REPAIRER
+    }
+
+    fn interval(&self) -> Option<Duration> {
+        Some(self.frequency)
+    }
+
+    async fn run(&self) -> Result<usize, Box<dyn Error>> {
+        if self.status_service.pending_repairs() == 0 {
+            return Ok(0);
+        }
+        let report = self.status_service.repair_statuses(self.batch_size).await?;
+        log::info!(
+            "Repaired {} statuses, removed {} deleted ones, {} failed - {} still pending",
+            report.repaired,
+            report.deleted,
+            report.failed,
+            self.status_service.pending_repairs()
+        );
+        Ok(report.repaired + report.deleted)
+    }
+}
@@ -1,6 +1,8 @@
 use crate::container::Container;
+use crate::domain::models::command::{STATUS_REFRESHER, WorkerCommand};
 use crate::domain::services::status::StatusService;
-use crate::settings::StatusRefreshSettings;
+use crate::infrastructure::metrics::Metrics;
+use crate::settings::{EngagementCompactionSettings, StatusRefreshSettings};
 use crate::workers::tracker::Worker;
 use async_trait::async_trait;
 use chrono::Utc;
@@ -9,14 +11,15 @@ use std::ops::Deref;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::time::sleep;
-use tokio_util::sync::CancellationToken;
 
 const STATUS_CHUNK_SIZE: u16 = 10;
 const STATUS_CHUNK_COUNT: u16 = 10;
 
 pub struct StatusRefresher {
     frequencies: Vec<StatusRefreshSettings>,
+    compactions: Vec<EngagementCompactionSettings>,
     status_service: Arc<dyn StatusService>,
+    metrics: Arc<Metrics>,
 }
 
 impl StatusRefresher {
@@ -26,11 +29,14 @@ impl StatusRefresher {
 
         Self {
             frequencies,
+            compactions: container.settings.application.engagement_compaction.clone(),
             status_service: container.status_service.clone(),
+            metrics: container.metrics.clone(),
         }
     }
 
-    pub async fn refresh_statuses(&self) -> Result<(), Box<dyn Error>> {
+    pub async fn refresh_statuses(&self) -> Result<usize, Box<dyn Error>> {
+        let mut refreshed = 0;
         for frequency in &self.frequencies {
             let since = Utc::now() - frequency.max_age.deref().clone();
             let fresh_since = Utc::now() - frequency.frequency.deref().clone();
@@ -47,6 +53,10 @@ impl StatusRefresher {
                 .await?;
 
             log::debug!("Found {} stale statuses", status_ids.len());
+            self.metrics.status_refresh_backlog(
+                &format!("{:?}", frequency.max_age.deref()),
+                status_ids.len(),
+            );
 
             let mut statuses = vec![];
             for (i, chunk) in status_ids.chunks(STATUS_CHUNK_SIZE.into()).enumerate() {
@@ -54,8 +64,34 @@ impl StatusRefresher {
                 statuses.extend(self.status_service.fetch_statuses(chunk).await?);
                 sleep(Duration::from_secs(10)).await;
             }
-            self.status_service.persist_statuses(&statuses).await?;
-            log::info!("Refreshed {} statuses", statuses.len());
+            let report = self.status_service.persist_statuses(&statuses).await?;
+            log::info!(
+                "Refreshed {} statuses, {} failed",
+                report.persisted.len(),
+                report.failed.len()
+            );
+            self.metrics.statuses_refreshed(report.persisted.len());
+            refreshed += report.persisted.len();
+        }
+        self.compact_engagements().await?;
+        Ok(refreshed)
+    }
+
+    /// Downsample the engagement history of older statuses, so that the number
+    /// of snapshots stays bounded as statuses age.
+    async fn compact_engagements(&self) -> Result<(), Box<dyn Error>> {
+        for compaction in &self.compactions {
+            let created_before = Utc::now() - *compaction.min_age.deref();
+            let deleted = self
+                .status_service
+                .compact_engagements(created_before, *compaction.resolution.deref())
+                .await?;
+            log::debug!(
+                "Compacted {} engagement snapshots with age={:?} and resolution={:?}",
+                deleted,
+                compaction.min_age.deref(),
+                compaction.resolution.deref()
+            );
         }
         Ok(())
     }
@@ -63,42 +99,33 @@ impl StatusRefresher {
 
 #[async_trait]
 impl Worker for StatusRefresher {
-    async fn run(&self, cancellation_token: CancellationToken) {
-        let minimum_frequency_o = self.frequencies.iter().map(|f| f.frequency.deref()).min();
-        let minimum_frequency: Duration = match minimum_frequency_o {
-            Some(v) => v.clone(),
-            None => {
-                log::warn!(
-                    "no status refresh frequency specified, not starting the status refresher"
-                );
-                return;
-            }
-        };
+    fn name(&self) -> &'static str {
+        STATUS_REFRESHER
+    }
 
-        log::info!("starting status refresher worker");
-        match self.refresh_statuses().await {
-            Ok(_) => {}
-            Err(e) => {
-                log::error!("error while refreshing statuses: {}", e);
-            }
-        };
-        loop {
-            tokio::select! {
-                _ = sleep(minimum_frequency) => {
-                    match self.refresh_statuses().await {
-                        Ok(_) => {}
-                        Err(e) => {
-                            log::error!("error while refreshing statuses: {}", e);
-                        }
-                    };
-                    continue;
-                }
+    fn interval(&self) -> Option<Duration> {
+        self.frequencies.iter().map(|f| *f.frequency.deref()).min()
+    }
+
+    async fn run(&self) -> Result<usize, Box<dyn Error>> {
+        self.refresh_statuses().await
+    }
 
-                _ = cancellation_token.cancelled() => {
-                    log::info!("gracefully shutting down the status refresher");
-                    break;
-                }
+    async fn handle(&self, command: WorkerCommand) -> Result<usize, Box<dyn Error>> {
+        match command {
+            WorkerCommand::RefreshStatuses { ids } => {
+                let statuses = self.status_service.fetch_statuses(&ids).await?;
+                let report = self.status_service.persist_statuses(&statuses).await?;
+                log::info!(
+                    "Refreshed {}/{} requested statuses, {} failed",
+                    report.persisted.len(),
+                    ids.len(),
+                    report.failed.len()
+                );
+                self.metrics.statuses_refreshed(report.persisted.len());
+                Ok(report.persisted.len())
             }
+            command => Err(format!("unsupported command {:?}", command).into()),
         }
     }
 }
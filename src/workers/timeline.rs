@@ -1,24 +1,26 @@
 use crate::container::Container;
+use crate::domain::models::command::{TIMELINE_UPDATER, WorkerCommand};
 use crate::domain::services::hashtag::SubscribedHashtagService;
 use crate::domain::services::status::{StatusService, StatusServiceError};
+use crate::infrastructure::metrics::Metrics;
 use crate::workers::tracker::Worker;
 use async_trait::async_trait;
-use log::debug;
+use log::{debug, warn};
 use megalodon::entities::Status;
 use std::cmp::Reverse;
+use std::collections::HashSet;
 use std::error::Error;
 use std::ops::Deref;
 use std::panic;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::task::JoinSet;
-use tokio::time::sleep;
-use tokio_util::sync::CancellationToken;
 
 pub struct TimelineUpdater {
     update_frequency: Duration,
     status_service: Arc<dyn StatusService>,
     subscribed_hashtag_service: Arc<dyn SubscribedHashtagService>,
+    metrics: Arc<Metrics>,
 }
 
 impl TimelineUpdater {
@@ -32,12 +34,11 @@ impl TimelineUpdater {
                 .clone(),
             status_service: container.status_service.clone(),
             subscribed_hashtag_service: container.subscribed_hashtag_service.clone(),
+            metrics: container.metrics.clone(),
         }
     }
 
-    async fn fetch_new_statuses(&self) -> Result<(), Box<dyn Error>> {
-        let hashtags = self.subscribed_hashtag_service.list_hashtags()?;
-
+    async fn fetch_new_statuses(&self, hashtags: Vec<String>) -> Result<usize, Box<dyn Error>> {
         let mut tasks: JoinSet<Result<(String, Vec<Status>), StatusServiceError>> = JoinSet::new();
         for hashtag in &hashtags {
             let svc = self.status_service.clone();
@@ -50,6 +51,7 @@ impl TimelineUpdater {
         }
 
         let mut statuses = vec![];
+        let mut fetched_by_tag = vec![];
         while let Some(task) = tasks.join_next().await {
             match task {
                 Ok(res) => match res {
@@ -59,6 +61,14 @@ impl TimelineUpdater {
                             h_statuses.len(),
                             &hashtag
                         );
+                        self.metrics.statuses_fetched(&hashtag, h_statuses.len());
+                        fetched_by_tag.push((
+                            hashtag,
+                            h_statuses
+                                .iter()
+                                .map(|status| status.id.clone())
+                                .collect::<Vec<_>>(),
+                        ));
                         statuses.extend(h_statuses);
                     }
                     Err(err) => Err(err)?,
@@ -74,34 +84,47 @@ impl TimelineUpdater {
 
         debug!("{} statuses after deduplication", statuses.len());
 
-        self.status_service.persist_statuses(&statuses).await?;
-        Ok(())
+        let report = self.status_service.persist_statuses(&statuses).await?;
+        if !report.failed.is_empty() {
+            warn!("Failed to persist {} statuses", report.failed.len());
+        }
+        let persisted: HashSet<String> = report.persisted.into_iter().collect();
+        for (hashtag, ids) in fetched_by_tag {
+            let count = ids.iter().filter(|id| persisted.contains(*id)).count();
+            self.metrics.statuses_persisted(&hashtag, count);
+        }
+        Ok(persisted.len())
     }
 }
 
 #[async_trait]
 impl Worker for TimelineUpdater {
-    async fn run(&self, cancellation_token: CancellationToken) {
-        log::info!("starting timeline updater worker");
-        match self.fetch_new_statuses().await {
-            Ok(_) => {}
-            Err(err) => log::error!("failed to fetch new statuses: {}", err),
-        };
-        loop {
-            tokio::select! {
-                _ = sleep(self.update_frequency) => {
-                    match self.fetch_new_statuses().await {
-                        Ok(_) => {}
-                        Err(err) => log::error!("failed to fetch new statuses: {}", err),
-                    };
-                    continue;
-                }
+    fn name(&self) -> &'static str {
+        TIMELINE_UPDATER
+    }
 
-                _ = cancellation_token.cancelled() => {
-                    log::info!("gracefully shutting down the timeline updater");
-                    break;
-                }
+    fn interval(&self) -> Option<Duration> {
+        Some(self.update_frequency)
+    }
+
+    async fn run(&self) -> Result<usize, Box<dyn Error>> {
+        let hashtags = self
+            .subscribed_hashtag_service
+            .list_polled_hashtags()
+            .await?;
+        self.fetch_new_statuses(hashtags).await
+    }
+
+    async fn handle(&self, command: WorkerCommand) -> Result<usize, Box<dyn Error>> {
+        match command {
+            WorkerCommand::UpdateTimeline { tag: None } => self.run().await,
+            WorkerCommand::UpdateTimeline { tag: Some(tag) } => {
+                self.fetch_new_statuses(vec![tag]).await
+            }
+            WorkerCommand::ResetCursor { tag } => {
+                Ok(self.status_service.reset_cursor(&tag).await? as usize)
             }
+            command => Err(format!("unsupported command {:?}", command).into()),
         }
     }
 }
@@ -1,28 +1,60 @@
+use crate::domain::models::command::{CommandQueue, WorkerCommand, WorkerCommands};
+use crate::domain::models::health::{SupervisionEventKind, WorkerState, WorkerStates};
+use crate::settings::{RestartPolicy, WorkerSettings};
 use async_trait::async_trait;
+use std::any::Any;
+use std::collections::VecDeque;
+use std::error::Error;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::time::sleep_until;
 use tokio_util::sync::CancellationToken;
 use tokio_util::task::TaskTracker;
 use tokio_util::task::task_tracker::TaskTrackerWaitFuture;
 
 #[async_trait]
 pub trait Worker {
-    async fn run(&self, cancellation_token: CancellationToken) -> ();
+    /// Name of the worker in the logs and the status endpoint
+    fn name(&self) -> &'static str;
+
+    /// Delay between two runs, `None` when there is nothing to run
+    fn interval(&self) -> Option<Duration>;
+
+    /// Perform a single run, returning the number of processed items
+    async fn run(&self) -> Result<usize, Box<dyn Error>>;
+
+    /// Handle a command queued from the administration, between two runs
+    async fn handle(&self, command: WorkerCommand) -> Result<usize, Box<dyn Error>> {
+        Err(format!("unsupported command {:?}", command).into())
+    }
 }
 
+type SharedWorker = Arc<dyn Worker + Send + Sync + 'static>;
+
 pub struct WorkerTracker {
     tracker: TaskTracker,
     cancellation_token: CancellationToken,
-    workers: Vec<Arc<dyn Worker + Send + Sync + 'static>>,
+    workers: Vec<SharedWorker>,
+    states: Arc<WorkerStates>,
+    commands: Arc<WorkerCommands>,
+    settings: WorkerSettings,
 }
 
 impl WorkerTracker {
-    pub fn new() -> Self {
+    pub fn new(
+        states: Arc<WorkerStates>,
+        commands: Arc<WorkerCommands>,
+        settings: WorkerSettings,
+    ) -> Self {
         let tracker = TaskTracker::new();
         let cancellation_token = CancellationToken::new();
         Self {
             tracker,
             cancellation_token,
             workers: Vec::new(),
+            states,
+            commands,
+            settings,
         }
     }
 
@@ -30,6 +62,8 @@ impl WorkerTracker {
     where
         T: Worker + Send + Sync + 'static,
     {
+        self.states.register(worker.name());
+        self.commands.register(worker.name());
         self.workers.push(Arc::new(worker));
     }
 
@@ -38,8 +72,11 @@ impl WorkerTracker {
         for worker in &self.workers {
             let token = self.cancellation_token.clone();
             let worker_ = worker.clone();
+            let states = self.states.clone();
+            let commands = self.commands.clone();
+            let settings = self.settings.clone();
             self.tracker.spawn(async move {
-                worker_.run(token).await;
+                supervise(worker_, &states, &commands, &settings, token).await;
             });
         }
         self.tracker.close();
@@ -53,3 +90,271 @@ impl WorkerTracker {
         self.tracker.wait()
     }
 }
+
+/// Restarts of a worker within the sliding window of the settings
+#[derive(Default)]
+struct RestartBudget {
+    restarts: VecDeque<Instant>,
+}
+
+impl RestartBudget {
+    /// Whether the worker may be restarted at `now`, recording the restart
+    fn allow(&mut self, now: Instant, settings: &WorkerSettings) -> bool {
+        match settings.restart {
+            RestartPolicy::Always => true,
+            RestartPolicy::Never => false,
+            RestartPolicy::Limited => {
+                while self
+                    .restarts
+                    .front()
+                    .is_some_and(|&at| now.duration_since(at) >= *settings.restart_window)
+                {
+                    self.restarts.pop_front();
+                }
+                if self.restarts.len() >= settings.max_restarts as usize {
+                    return false;
+                }
+                self.restarts.push_back(now);
+                true
+            }
+        }
+    }
+}
+
+/// Delay before the next run: the interval doubles after each consecutive
+/// failure up to `max-backoff`, and is spread by `jitter`, `random` being
+/// picked in `[-1, 1]`
+fn next_delay(
+    interval: Duration,
+    consecutive_failures: u32,
+    settings: &WorkerSettings,
+    random: f64,
+) -> Duration {
+    let delay = if consecutive_failures == 0 {
+        interval
+    } else {
+        interval
+            .saturating_mul(2u32.saturating_pow(consecutive_failures.min(16)))
+            .min((*settings.max_backoff).max(interval))
+    };
+    let jitter = settings.jitter.clamp(0.0, 1.0) * random.clamp(-1.0, 1.0);
+    delay.mul_f64(1.0 + jitter)
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => match payload.downcast::<&str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => "unknown panic".to_string(),
+        },
+    }
+}
+
+/// Handle the queued commands one after the other, recording them as runs.
+/// A failing command does not count towards the backoff of the worker.
+async fn handle_commands(worker: &SharedWorker, queue: &CommandQueue, states: &WorkerStates) {
+    let name = worker.name();
+    while let Some(command) = queue.pop() {
+        log::info!("{} handling {:?}", name, command);
+        states.update(name, WorkerState::started);
+        let worker_ = worker.clone();
+        let command_ = command.clone();
+        let run =
+            tokio::spawn(async move { worker_.handle(command_).await.map_err(|e| e.to_string()) });
+        let error = match run.await {
+            Ok(Ok(items)) => {
+                log::debug!("{} processed {} items for {:?}", name, items, command);
+                states.update(name, |state| state.succeeded(items));
+                continue;
+            }
+            Ok(Err(err)) => err,
+            Err(err) if err.is_panic() => format!("panicked: {}", panic_message(err.into_panic())),
+            Err(err) => err.to_string(),
+        };
+        log::error!("{} failed to handle {:?}: {}", name, command, error);
+        states.update(name, |state| {
+            state.command_failed(format!("{:?}: {}", command, error))
+        });
+    }
+}
+
+/// Run the worker until cancelled, recording the outcome of each run. Each
+/// run is spawned in its own task so that a panic is caught and the worker
+/// restarted according to the settings. Queued commands are handled while
+/// waiting for the next run, so never concurrently with it.
+async fn supervise(
+    worker: SharedWorker,
+    states: &WorkerStates,
+    commands: &WorkerCommands,
+    settings: &WorkerSettings,
+    cancellation_token: CancellationToken,
+) {
+    let name = worker.name();
+    let Some(interval) = worker.interval() else {
+        log::warn!("nothing to run, not starting the {}", name);
+        commands.unregister(name);
+        return;
+    };
+    let queue = commands.register(name);
+
+    log::info!("starting the {}", name);
+    let mut budget = RestartBudget::default();
+    let mut consecutive_failures: u32 = 0;
+    loop {
+        states.update(name, WorkerState::started);
+        let mut panicked = false;
+        let worker_ = worker.clone();
+        let run = tokio::spawn(async move { worker_.run().await.map_err(|e| e.to_string()) });
+        match run.await {
+            Ok(Ok(items)) => {
+                log::debug!("{} processed {} items", name, items);
+                consecutive_failures = 0;
+                states.update(name, |state| state.succeeded(items));
+            }
+            Ok(Err(err)) => {
+                log::error!("{} failed: {}", name, err);
+                consecutive_failures += 1;
+                states.update(name, |state| state.failed(err));
+            }
+            Err(err) if err.is_panic() => {
+                let message = panic_message(err.into_panic());
+                log::error!("{} panicked: {}", name, message);
+                consecutive_failures += 1;
+                panicked = true;
+                states.update(name, |state| state.panicked(message));
+
+                if !budget.allow(Instant::now(), settings) {
+                    let message = format!("not restarting, restart policy {:?}", settings.restart);
+                    log::error!("{}: {}", name, message);
+                    states.update(name, |state| state.stopped(message));
+                    commands.unregister(name);
+                    return;
+                }
+            }
+            Err(err) => {
+                log::warn!("{} run cancelled: {}", name, err);
+                commands.unregister(name);
+                return;
+            }
+        }
+
+        let delay = next_delay(
+            interval,
+            consecutive_failures,
+            settings,
+            rand::random_range(-1.0..=1.0),
+        );
+        if consecutive_failures > 0 {
+            let message = format!(
+                "{} consecutive failures, next run in {:?}",
+                consecutive_failures, delay
+            );
+            log::warn!("{}: {}", name, message);
+            states.update(name, |state| {
+                if panicked {
+                    state.restarted(message)
+                } else {
+                    state.event(SupervisionEventKind::BackingOff, message)
+                }
+            });
+        }
+
+        let next_run = tokio::time::Instant::now() + delay;
+        loop {
+            tokio::select! {
+                _ = sleep_until(next_run) => break,
+
+                _ = queue.notified() => handle_commands(&worker, &queue, states).await,
+
+                _ = cancellation_token.cancelled() => {
+                    log::info!("gracefully shutting down the {}", name);
+                    commands.unregister(name);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Panicking;
+
+    #[async_trait]
+    impl Worker for Panicking {
+        fn name(&self) -> &'static str {
+            "panicking"
+        }
+
+        fn interval(&self) -> Option<Duration> {
+            Some(Duration::from_millis(1))
+        }
+
+        async fn run(&self) -> Result<usize, Box<dyn Error>> {
+            panic!("boom")
+        }
+    }
+
+    #[test]
+    fn next_delay_backs_off_after_failures() {
+        let settings = WorkerSettings {
+            jitter: 0.5,
+            max_backoff: Duration::from_secs(60).into(),
+            ..Default::default()
+        };
+        let interval = Duration::from_secs(10);
+        assert_eq!(next_delay(interval, 0, &settings, 0.0), interval);
+        assert_eq!(
+            next_delay(interval, 0, &settings, 1.0),
+            interval.mul_f64(1.5)
+        );
+        assert_eq!(next_delay(interval, 0, &settings, -1.0), interval / 2);
+        assert_eq!(next_delay(interval, 2, &settings, 0.0), interval * 4);
+        assert_eq!(
+            next_delay(interval, 30, &settings, 0.0),
+            Duration::from_secs(60)
+        );
+    }
+
+    #[test]
+    fn restart_budget_limits_the_restarts_per_window() {
+        let settings = WorkerSettings {
+            max_restarts: 2,
+            restart_window: Duration::from_secs(60).into(),
+            ..Default::default()
+        };
+        let mut budget = RestartBudget::default();
+        let now = Instant::now();
+        assert!(budget.allow(now, &settings));
+        assert!(budget.allow(now + Duration::from_secs(1), &settings));
+        assert!(!budget.allow(now + Duration::from_secs(2), &settings));
+        assert!(budget.allow(now + Duration::from_secs(61), &settings));
+    }
+
+    #[actix_web::test]
+    async fn panicking_worker_is_restarted_then_stopped() {
+        let states = Arc::new(WorkerStates::default());
+        let settings = WorkerSettings {
+            max_restarts: 2,
+            max_backoff: Duration::from_millis(1).into(),
+            ..Default::default()
+        };
+        let mut tracker = WorkerTracker::new(
+            states.clone(),
+            Arc::new(WorkerCommands::default()),
+            settings,
+        );
+        tracker.register_worker(Panicking);
+        tracker.start();
+        tracker.wait().await;
+
+        let state = &states.list()[0];
+        assert!(state.stopped);
+        assert_eq!(state.runs, 3);
+        assert_eq!(state.restarts, 2);
+        assert_eq!(state.last_error.as_deref(), Some("panicked: boom"));
+    }
+}